@@ -17,6 +17,9 @@ pub struct Glue {
     pub reverse: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub out_of_range_behavior: Option<OutOfRangeBehavior>,
+    /// Language of the control and feedback transformation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transformation_language: Option<TransformationLanguage>,
     //endregion
 
     //region Relevant for control only (might change in future)
@@ -35,10 +38,17 @@ pub struct Glue {
     pub takeover_mode: Option<TakeoverMode>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub control_transformation: Option<String>,
+    /// Declarative alternative to `control_transformation`. Takes precedence if both are set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub control_curve: Option<Curve>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub step_size_interval: Option<Interval<f64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub step_factor_interval: Option<Interval<i32>>,
+    /// Software acceleration for relative encoders. Makes increments larger the faster the
+    /// encoder is turned. Only has an effect if the step size/factor interval isn't a single value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relative_acceleration: Option<RelativeAcceleration>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub button_filter: Option<ButtonFilter>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -49,6 +59,9 @@ pub struct Glue {
     pub interaction: Option<Interaction>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fire_mode: Option<FireMode>,
+    /// Makes the target value move smoothly to the desired value instead of jumping to it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glide: Option<Glide>,
     //endregion
 
     //region Relevant for feedback only (guaranteed)
@@ -56,9 +69,114 @@ pub struct Glue {
     pub feedback: Option<Feedback>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub feedback_value_table: Option<FeedbackValueTable>,
+    /// Derives the feedback color from the numeric feedback value. Takes precedence over the
+    /// static feedback color.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feedback_color_map: Option<FeedbackColorMap>,
+    /// Declarative alternative to the feedback transformation. Takes precedence if both are set.
+    ///
+    /// If neither this nor a feedback transformation is set, feedback uses the inverse of the
+    /// control curve (if the control curve is invertible).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feedback_curve: Option<Curve>,
     //endregion
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, JsonSchema)]
+pub enum TransformationLanguage {
+    #[serde(rename = "eel")]
+    Eel,
+    #[serde(rename = "lua")]
+    Lua,
+}
+
+impl Default for TransformationLanguage {
+    fn default() -> Self {
+        TransformationLanguage::Eel
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Glide {
+    pub time: GlideTime,
+    /// Shape of the movement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<CurveSegment>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum GlideTime {
+    Millis {
+        millis: u32,
+    },
+    /// The glide takes the given fraction of a whole note.
+    TempoSynced {
+        numerator: u32,
+        denominator: u32,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RelativeAcceleration {
+    pub profile: AccelerationProfile,
+    /// Factor by which increments get multiplied when turning the encoder very fast.
+    ///
+    /// Defaults to 10. Values above 100 are treated as 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_factor: Option<u32>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, JsonSchema)]
+pub enum AccelerationProfile {
+    /// The factor grows proportionally with the turning speed.
+    #[serde(rename = "linear")]
+    Linear,
+    /// The factor stays small at moderate speeds and grows steeply at high speeds.
+    #[serde(rename = "exponential")]
+    Exponential,
+    /// The factor doubles in a few coarse steps, similar to Mackie Control V-Pots.
+    #[serde(rename = "mackie-like")]
+    MackieLike,
+}
+
+/// A response curve defined by breakpoints.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Curve {
+    /// Breakpoints, both coordinates between 0.0 and 1.0. Order doesn't matter.
+    pub points: Vec<CurvePoint>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CurvePoint {
+    pub x: f64,
+    pub y: f64,
+    /// Shape of the segment leading from this point to the next one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment: Option<CurveSegment>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum CurveSegment {
+    Linear,
+    /// Positive curvature starts slowly and ends steeply, negative curvature the other way around.
+    Exponential {
+        curvature: f64,
+    },
+    SCurve,
+}
+
+impl Default for CurveSegment {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "kind")]
 pub enum FeedbackValueTable {
@@ -71,6 +189,45 @@ pub struct FeedbackValueTableContent<K: Eq + Hash, V> {
     pub value: HashMap<K, V>,
 }
 
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum FeedbackColorMap {
+    /// Blends smoothly between the colors of neighboring stops.
+    Gradient(ColorGradient),
+    /// Uses the color of the first range which contains the value.
+    Ranges(ColorRanges),
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ColorGradient {
+    /// Order doesn't matter.
+    pub stops: Vec<ColorStop>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ColorStop {
+    /// Feedback value between 0.0 and 1.0.
+    pub position: f64,
+    pub color: RgbColor,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ColorRanges {
+    /// If no range contains the value, the static feedback color is used.
+    pub ranges: Vec<ColorRange>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ColorRange {
+    /// Feedback value interval, both ends inclusive.
+    pub interval: Interval<f64>,
+    pub color: RgbColor,
+}
+
 #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum AbsoluteMode {
     Normal,
@@ -154,7 +311,7 @@ pub enum VirtualColor {
     Prop(PropColor),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct RgbColor(pub u8, pub u8, pub u8);
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -251,5 +408,5 @@ pub struct TextFeedback {
     pub text_expression: Option<String>,
 }
 
-#[derive(Copy, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Interval<T>(pub T, pub T);
@@ -1,8 +1,10 @@
+pub use chord::*;
 pub use keyboard::*;
 pub use midi::*;
 pub use osc::*;
 pub use reaper::*;
 use schemars::JsonSchema;
+pub use sequence::*;
 use serde::{Deserialize, Serialize};
 pub use virt::*;
 
@@ -16,7 +18,16 @@ pub enum Source {
     // REAPER
     MidiDeviceChanges(MidiDeviceChangesSource),
     RealearnInstanceStart(RealearnInstanceStartSource),
+    TrackSelectionChanges(TrackSelectionChangesSource),
+    PlayStateChanges(PlayStateChangesSource),
+    RecordStateChanges(RecordStateChangesSource),
+    ProjectSwitch(ProjectSwitchSource),
+    FxFocusChanges(FxFocusChangesSource),
+    MarkerPassed(MarkerPassedSource),
+    CompartmentParameterValue(CompartmentParameterValueSource),
     Timer(TimerSource),
+    Lfo(LfoSource),
+    Beat(BeatSource),
     // MIDI
     MidiNoteVelocity(MidiNoteVelocitySource),
     MidiNoteKeyNumber(MidiNoteKeyNumberSource),
@@ -30,6 +41,7 @@ pub enum Source {
     MidiClockTransport(MidiClockTransportSource),
     MidiRaw(MidiRawSource),
     MidiScript(MidiScriptSource),
+    MidiControlScript(MidiControlScriptSource),
     MackieLcd(MackieLcdSource),
     MackieSevenSegmentDisplay(MackieSevenSegmentDisplaySource),
     SiniConE24Display(SiniConE24DisplaySource),
@@ -40,6 +52,10 @@ pub enum Source {
     Key(KeySource),
     // Virtual
     Virtual(VirtualSource),
+    // Chord
+    Chord(ChordSource),
+    // Sequence
+    Sequence(SequenceSource),
 }
 
 impl Default for Source {
@@ -195,6 +211,16 @@ mod midi {
         pub script: Option<String>,
     }
 
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct MidiControlScriptSource {
+        /// Lua script which turns the incoming message `msg` into a control value.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub script: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub character: Option<SourceCharacter>,
+    }
+
     /// Kind of a MIDI script
     #[derive(
         Clone,
@@ -333,11 +359,126 @@ mod reaper {
     #[serde(deny_unknown_fields)]
     pub struct RealearnInstanceStartSource;
 
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct TrackSelectionChangesSource;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct PlayStateChangesSource;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct RecordStateChangesSource;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct ProjectSwitchSource;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct FxFocusChangesSource;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct MarkerPassedSource;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct CompartmentParameterValueSource {
+        /// Index of the parameter within the compartment, starting at 0.
+        pub index: u32,
+    }
+
     #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
     #[serde(deny_unknown_fields)]
     pub struct TimerSource {
         pub duration: u64,
     }
+
+    #[derive(Copy, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct LfoSource {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub waveform: Option<LfoWaveform>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub rate: Option<LfoRate>,
+        /// Phase offset in cycles (0.0 - 1.0).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub phase: Option<f64>,
+        /// Amount of modulation around the center (0.0 - 1.0).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub depth: Option<f64>,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+    pub enum LfoWaveform {
+        Sine,
+        Triangle,
+        Saw,
+        Square,
+        SampleAndHold,
+    }
+
+    impl Default for LfoWaveform {
+        fn default() -> Self {
+            LfoWaveform::Sine
+        }
+    }
+
+    #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(tag = "kind")]
+    pub enum LfoRate {
+        /// Cycles per second.
+        Hertz { frequency: f64 },
+        /// One cycle takes the given fraction of a whole note.
+        TempoSynced { numerator: u32, denominator: u32 },
+    }
+
+    impl Default for LfoRate {
+        fn default() -> Self {
+            LfoRate::Hertz { frequency: 1.0 }
+        }
+    }
+
+    #[derive(Copy, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct BeatSource {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub grid: Option<BeatGrid>,
+        /// Number of steps per beat if grid is `Subdivision`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub subdivisions: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub only_while_playing: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub output: Option<BeatSourceOutput>,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+    pub enum BeatGrid {
+        Bar,
+        Beat,
+        Subdivision,
+    }
+
+    impl Default for BeatGrid {
+        fn default() -> Self {
+            BeatGrid::Beat
+        }
+    }
+
+    #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+    pub enum BeatSourceOutput {
+        Trigger,
+        PositionInBar,
+    }
+
+    impl Default for BeatSourceOutput {
+        fn default() -> Self {
+            BeatSourceOutput::Trigger
+        }
+    }
 }
 
 mod keyboard {
@@ -348,6 +489,26 @@ mod keyboard {
     pub struct KeySource {
         #[serde(skip_serializing_if = "Option::is_none")]
         pub keystroke: Option<Keystroke>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub mode: Option<KeySourceMode>,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+    pub enum KeySourceMode {
+        /// Emits 100% on press and 0% on release.
+        Normal,
+        /// Like `Normal` but emits 100% again on each key repeat.
+        Repeat,
+        /// Emits an increment on press and on each key repeat, growing while the key is held.
+        Accelerate,
+        /// Typed digits followed by Enter emit the typed percentage.
+        NumericEntry,
+    }
+
+    impl Default for KeySourceMode {
+        fn default() -> Self {
+            KeySourceMode::Normal
+        }
     }
 
     #[derive(Copy, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -358,6 +519,34 @@ mod keyboard {
     }
 }
 
+mod chord {
+    use super::*;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct ChordSource {
+        /// Button-like sources which need to be held together.
+        pub members: Vec<Source>,
+        /// Maximum time in milliseconds between the first and the last member press.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub window: Option<u64>,
+    }
+}
+
+mod sequence {
+    use super::*;
+
+    #[derive(Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct SequenceSource {
+        /// Sources which need to emit a message one after the other.
+        pub steps: Vec<Source>,
+        /// Maximum time in milliseconds between two steps before the sequence starts over.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub timeout: Option<u64>,
+    }
+}
+
 mod virt {
     use crate::schema::{VirtualControlElementCharacter, VirtualControlElementId};
     use schemars::JsonSchema;
@@ -17,6 +17,10 @@ impl ControllerPreset {
         self.data.custom_data.insert(key, value);
     }
 
+    pub fn remove_custom_data(&mut self, key: &str) {
+        self.data.custom_data.remove(key);
+    }
+
     pub fn update_realearn_data(&mut self, data: CompartmentModel) {
         self.data = data;
     }
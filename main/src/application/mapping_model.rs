@@ -502,9 +502,16 @@ impl MappingModel {
         self.mode_model.create_mode(
             self.base_mode_applicability_check_input(),
             &possible_source_characters,
+            self.control_is_always_real_time(),
         )
     }
 
+    /// Virtual targets are resolved in the real-time processor, so the mode of such mappings must
+    /// be able to control in real-time.
+    pub fn control_is_always_real_time(&self) -> bool {
+        self.target_model.category() == TargetCategory::Virtual
+    }
+
     fn create_target(&self) -> Option<UnresolvedCompoundMappingTarget> {
         self.target_model.create_target(self.compartment).ok()
     }
@@ -545,6 +552,9 @@ impl MappingModel {
             source,
             mode,
             self.mode_model.group_interaction(),
+            self.mode_model.create_glide_settings(),
+            self.mode_model.create_feedback_color_map(),
+            self.mode_model.create_relative_accelerator(),
             unresolved_target,
             group_data.activation_condition,
             activation_condition,
@@ -1,17 +1,25 @@
-use crate::domain::{EelTransformation, Mode, OutputVariable};
+use crate::domain::{
+    AccelerationProfile, BackboneState, ColorRange, ColorStop, CurvePoint, CurveSegment,
+    CurveTransformation, EelTransformation, FeedbackColorMap, GlideSettings, GlideTime,
+    GlueTransformation, LuaTransformation, Mode, OutputVariable, RelativeAcceleration,
+    RelativeAccelerator, MAX_ACCELERATION_FACTOR,
+};
 
 use helgoboss_learn::{
     check_mode_applicability, full_discrete_interval, full_unit_interval, AbsoluteMode,
     ButtonUsage, DetailedSourceCharacter, DiscreteIncrement, EncoderUsage, FeedbackType, FireMode,
     GroupInteraction, Interval, ModeApplicabilityCheckInput, ModeParameter, ModeSettings,
-    OutOfRangeBehavior, SoftSymmetricUnitValue, TakeoverMode, UnitValue, ValueSequence,
+    OutOfRangeBehavior, RgbColor, SoftSymmetricUnitValue, TakeoverMode, UnitValue, ValueSequence,
     VirtualColor,
 };
 
 use crate::application::{Affected, Change, GetProcessingRelevance, ProcessingRelevance};
-use realearn_api::schema::FeedbackValueTable;
+use realearn_api::schema;
+use realearn_api::schema::{Curve, FeedbackValueTable, Glide, TransformationLanguage};
 use std::time::Duration;
 
+const DEFAULT_MAX_ACCELERATION_FACTOR: u32 = 10;
+
 pub enum ModeCommand {
     SetAbsoluteMode(AbsoluteMode),
     SetTargetValueInterval(Interval<UnitValue>),
@@ -36,9 +44,14 @@ pub enum ModeCommand {
     SetEncoderUsage(EncoderUsage),
     SetEelControlTransformation(String),
     SetEelFeedbackTransformation(String),
+    SetTransformationLanguage(TransformationLanguage),
+    SetControlCurve(Option<Curve>),
+    SetFeedbackCurve(Option<Curve>),
+    SetGlide(Option<Glide>),
     SetStepInterval(Interval<SoftSymmetricUnitValue>),
     SetMinStep(SoftSymmetricUnitValue),
     SetMaxStep(SoftSymmetricUnitValue),
+    SetRelativeAcceleration(Option<schema::RelativeAcceleration>),
     SetRotate(bool),
     SetMakeAbsolute(bool),
     SetGroupInteraction(GroupInteraction),
@@ -48,6 +61,7 @@ pub enum ModeCommand {
     SetFeedbackColor(Option<VirtualColor>),
     SetFeedbackBackgroundColor(Option<VirtualColor>),
     SetFeedbackValueTable(Option<FeedbackValueTable>),
+    SetFeedbackColorMap(Option<schema::FeedbackColorMap>),
     /// This doesn't reset the mode type, just all the values.
     ResetWithinType,
 }
@@ -69,7 +83,12 @@ pub enum ModeProp {
     EncoderUsage,
     EelControlTransformation,
     EelFeedbackTransformation,
+    TransformationLanguage,
+    ControlCurve,
+    FeedbackCurve,
+    Glide,
     StepInterval,
+    RelativeAcceleration,
     Rotate,
     MakeAbsolute,
     GroupInteraction,
@@ -79,6 +98,7 @@ pub enum ModeProp {
     FeedbackColor,
     FeedbackBackgroundColor,
     FeedbackValueTable,
+    FeedbackColorMap,
 }
 
 impl GetProcessingRelevance for ModeProp {
@@ -106,6 +126,11 @@ pub struct ModeModel {
     encoder_usage: EncoderUsage,
     eel_control_transformation: String,
     eel_feedback_transformation: String,
+    /// Language in which the control and feedback transformations are written.
+    transformation_language: TransformationLanguage,
+    control_curve: Option<Curve>,
+    feedback_curve: Option<Curve>,
+    glide: Option<Glide>,
     // For relative control values.
     /// Depending on the target character, this is either a step count or a step size.
     ///
@@ -124,6 +149,8 @@ pub struct ModeModel {
     /// with buttons. The harder you press the button, the higher the increment. It's limited
     /// by the maximum value.
     step_interval: Interval<SoftSymmetricUnitValue>,
+    /// Software acceleration which makes incoming increments larger when turning fast.
+    relative_acceleration: Option<schema::RelativeAcceleration>,
     rotate: bool,
     make_absolute: bool,
     group_interaction: GroupInteraction,
@@ -133,6 +160,7 @@ pub struct ModeModel {
     feedback_color: Option<VirtualColor>,
     feedback_background_color: Option<VirtualColor>,
     feedback_value_table: Option<FeedbackValueTable>,
+    feedback_color_map: Option<schema::FeedbackColorMap>,
 }
 
 impl Default for ModeModel {
@@ -156,7 +184,12 @@ impl Default for ModeModel {
             encoder_usage: Default::default(),
             eel_control_transformation: String::new(),
             eel_feedback_transformation: String::new(),
+            transformation_language: Default::default(),
+            control_curve: None,
+            feedback_curve: None,
+            glide: None,
             step_interval: Self::default_step_size_interval(),
+            relative_acceleration: None,
             rotate: false,
             make_absolute: false,
             group_interaction: Default::default(),
@@ -166,6 +199,7 @@ impl Default for ModeModel {
             feedback_color: Default::default(),
             feedback_background_color: Default::default(),
             feedback_value_table: None,
+            feedback_color_map: None,
         }
     }
 }
@@ -275,6 +309,22 @@ impl<'a> Change<'a> for ModeModel {
                 self.eel_feedback_transformation = v;
                 One(P::EelFeedbackTransformation)
             }
+            C::SetTransformationLanguage(v) => {
+                self.transformation_language = v;
+                One(P::TransformationLanguage)
+            }
+            C::SetControlCurve(v) => {
+                self.control_curve = v;
+                One(P::ControlCurve)
+            }
+            C::SetFeedbackCurve(v) => {
+                self.feedback_curve = v;
+                One(P::FeedbackCurve)
+            }
+            C::SetGlide(v) => {
+                self.glide = v;
+                One(P::Glide)
+            }
             C::SetStepInterval(v) => {
                 self.step_interval = v;
                 One(P::StepInterval)
@@ -285,6 +335,10 @@ impl<'a> Change<'a> for ModeModel {
             C::SetMaxStep(v) => {
                 return self.change(C::SetStepInterval(self.step_interval.with_max(v)))
             }
+            C::SetRelativeAcceleration(v) => {
+                self.relative_acceleration = v;
+                One(P::RelativeAcceleration)
+            }
             C::SetRotate(v) => {
                 self.rotate = v;
                 One(P::Rotate)
@@ -321,6 +375,10 @@ impl<'a> Change<'a> for ModeModel {
                 self.feedback_value_table = v;
                 One(P::FeedbackValueTable)
             }
+            C::SetFeedbackColorMap(v) => {
+                self.feedback_color_map = v;
+                One(P::FeedbackColorMap)
+            }
             C::ResetWithinType => {
                 *self = Default::default();
                 Multiple
@@ -348,6 +406,43 @@ impl ModeModel {
         self.feedback_value_table.as_ref()
     }
 
+    pub fn feedback_color_map(&self) -> Option<&schema::FeedbackColorMap> {
+        self.feedback_color_map.as_ref()
+    }
+
+    pub fn create_feedback_color_map(&self) -> Option<FeedbackColorMap> {
+        let convert_color = |c: schema::RgbColor| RgbColor::new(c.0, c.1, c.2);
+        match self.feedback_color_map.as_ref()? {
+            schema::FeedbackColorMap::Gradient(g) => {
+                let stops = g
+                    .stops
+                    .iter()
+                    .map(|s| ColorStop {
+                        position: s.position,
+                        color: convert_color(s.color),
+                    })
+                    .collect();
+                FeedbackColorMap::gradient(stops).ok()
+            }
+            schema::FeedbackColorMap::Ranges(r) => {
+                let ranges = r
+                    .ranges
+                    .iter()
+                    .filter(|r| r.interval.0.is_finite() && r.interval.1.is_finite())
+                    .map(|r| ColorRange {
+                        // The range comes from the user, so it might be reversed.
+                        interval: Interval::new_auto(
+                            UnitValue::new_clamped(r.interval.0),
+                            UnitValue::new_clamped(r.interval.1),
+                        ),
+                        color: convert_color(r.color),
+                    })
+                    .collect();
+                Some(FeedbackColorMap::ranges(ranges))
+            }
+        }
+    }
+
     pub fn absolute_mode(&self) -> AbsoluteMode {
         self.absolute_mode
     }
@@ -408,10 +503,64 @@ impl ModeModel {
         &self.eel_feedback_transformation
     }
 
+    pub fn transformation_language(&self) -> TransformationLanguage {
+        self.transformation_language
+    }
+
+    pub fn control_curve(&self) -> Option<&Curve> {
+        self.control_curve.as_ref()
+    }
+
+    pub fn feedback_curve(&self) -> Option<&Curve> {
+        self.feedback_curve.as_ref()
+    }
+
+    pub fn glide(&self) -> Option<&Glide> {
+        self.glide.as_ref()
+    }
+
+    pub fn create_glide_settings(&self) -> Option<GlideSettings> {
+        let glide = self.glide.as_ref()?;
+        let settings = GlideSettings {
+            time: match glide.time {
+                schema::GlideTime::Millis { millis } => GlideTime::Millis(millis),
+                schema::GlideTime::TempoSynced {
+                    numerator,
+                    denominator,
+                } => GlideTime::TempoSynced {
+                    numerator,
+                    denominator,
+                },
+            },
+            shape: convert_curve_segment(glide.shape.unwrap_or_default()),
+        };
+        Some(settings)
+    }
+
     pub fn step_interval(&self) -> Interval<SoftSymmetricUnitValue> {
         self.step_interval
     }
 
+    pub fn relative_acceleration(&self) -> Option<&schema::RelativeAcceleration> {
+        self.relative_acceleration.as_ref()
+    }
+
+    pub fn create_relative_accelerator(&self) -> Option<RelativeAccelerator> {
+        let acceleration = self.relative_acceleration.as_ref()?;
+        let acceleration = RelativeAcceleration {
+            profile: match acceleration.profile {
+                schema::AccelerationProfile::Linear => AccelerationProfile::Linear,
+                schema::AccelerationProfile::Exponential => AccelerationProfile::Exponential,
+                schema::AccelerationProfile::MackieLike => AccelerationProfile::MackieLike,
+            },
+            max_factor: acceleration
+                .max_factor
+                .unwrap_or(DEFAULT_MAX_ACCELERATION_FACTOR)
+                .clamp(1, MAX_ACCELERATION_FACTOR),
+        };
+        Some(RelativeAccelerator::new(acceleration))
+    }
+
     pub fn rotate(&self) -> bool {
         self.rotate
     }
@@ -467,12 +616,61 @@ impl ModeModel {
         })
     }
 
+    fn create_control_transformation(&self, real_time_only: bool) -> Option<GlueTransformation> {
+        if let Some(curve) = &self.control_curve {
+            return create_curve_transformation(curve).map(GlueTransformation::Curve);
+        }
+        let t =
+            self.create_script_transformation(&self.eel_control_transformation, OutputVariable::Y)?;
+        if real_time_only && t.is_main_thread_only() {
+            return None;
+        }
+        Some(t)
+    }
+
+    fn create_feedback_transformation(&self) -> Option<GlueTransformation> {
+        if let Some(curve) = &self.feedback_curve {
+            return create_curve_transformation(curve).map(GlueTransformation::Curve);
+        }
+        if let Some(t) =
+            self.create_script_transformation(&self.eel_feedback_transformation, OutputVariable::X)
+        {
+            return Some(t);
+        }
+        // Without explicit feedback transformation, feedback should reflect the control curve.
+        let inverse = create_curve_transformation(self.control_curve.as_ref()?)?.inverse()?;
+        Some(GlueTransformation::Curve(inverse))
+    }
+
+    fn create_script_transformation(
+        &self,
+        script: &str,
+        output_var: OutputVariable,
+    ) -> Option<GlueTransformation> {
+        match self.transformation_language {
+            TransformationLanguage::Eel => EelTransformation::compile(script, output_var)
+                .ok()
+                .map(GlueTransformation::Eel),
+            TransformationLanguage::Lua => {
+                let lua = unsafe { BackboneState::main_thread_lua() };
+                LuaTransformation::compile(lua, script, output_var)
+                    .ok()
+                    .map(GlueTransformation::Lua)
+            }
+        }
+    }
+
     /// Creates a mode reflecting this model's current values
+    ///
+    /// If `real_time_control_only` is `true`, control transformations which can't be executed in
+    /// the real-time thread (Lua) are left out. That's necessary for mappings whose control is
+    /// always processed in real-time, e.g. those with virtual targets.
     #[allow(clippy::if_same_then_else)]
     pub fn create_mode(
         &self,
         base_input: ModeApplicabilityCheckInput,
         possible_source_characters: &[DetailedSourceCharacter],
+        real_time_control_only: bool,
     ) -> Mode {
         let is_relevant = |mode_parameter: ModeParameter| {
             // We take both control and feedback into account to not accidentally get slightly
@@ -590,13 +788,12 @@ impl ModeModel {
                 OutOfRangeBehavior::default()
             },
             control_transformation: if is_relevant(ModeParameter::ControlTransformation) {
-                EelTransformation::compile(&self.eel_control_transformation, OutputVariable::Y).ok()
+                self.create_control_transformation(real_time_control_only)
             } else {
                 None
             },
             feedback_transformation: if is_relevant(ModeParameter::FeedbackTransformation) {
-                EelTransformation::compile(&self.eel_feedback_transformation, OutputVariable::X)
-                    .ok()
+                self.create_feedback_transformation()
             } else {
                 None
             },
@@ -632,6 +829,28 @@ impl ModeModel {
     }
 }
 
+fn create_curve_transformation(curve: &Curve) -> Option<CurveTransformation> {
+    let points = curve
+        .points
+        .iter()
+        .map(|p| CurvePoint {
+            x: p.x,
+            y: p.y,
+            segment: convert_curve_segment(p.segment.unwrap_or_default()),
+        })
+        .collect();
+    CurveTransformation::new(points).ok()
+}
+
+fn convert_curve_segment(segment: schema::CurveSegment) -> CurveSegment {
+    let segment = match segment {
+        schema::CurveSegment::Linear => CurveSegment::Linear,
+        schema::CurveSegment::Exponential { curvature } => CurveSegment::Exponential { curvature },
+        schema::CurveSegment::SCurve => CurveSegment::SCurve,
+    };
+    segment.sanitized()
+}
+
 pub fn convert_factor_to_unit_value(factor: i32) -> SoftSymmetricUnitValue {
     let result = if factor == 0 {
         0.01
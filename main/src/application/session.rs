@@ -6,8 +6,8 @@ use crate::application::{
     SourceModel, TargetCategory, TargetModel, TargetProp, VirtualControlElementType,
 };
 use crate::base::{
-    prop, when, AsyncNotifier, Global, NamedChannelSender, Prop, SenderToNormalThread,
-    SenderToRealTimeThread,
+    notification, prop, when, AsyncNotifier, Global, NamedChannelSender, Prop,
+    SenderToNormalThread, SenderToRealTimeThread,
 };
 use crate::domain::{
     convert_plugin_param_index_range_to_iter, BackboneState, BasicSettings, Compartment,
@@ -17,9 +17,9 @@ use crate::domain::{
     InputDescriptor, InstanceContainer, InstanceId, InstanceState, MainMapping, MappingId,
     MappingKey, MappingMatchedEvent, MessageCaptureEvent, MidiControlInput, NormalMainTask,
     NormalRealTimeTask, OscFeedbackTask, ParamSetting, PluginParams, ProcessorContext,
-    ProjectionFeedbackValue, QualifiedMappingId, RealearnTarget, ReaperTarget, SharedInstanceState,
-    SourceFeedbackValue, Tag, TargetValueChangedEvent, VirtualControlElementId, VirtualSource,
-    VirtualSourceValue,
+    ProjectionFeedbackValue, QualifiedMappingId, RealearnTarget, ReaperTarget, ServerEvent,
+    ServerEventPayload, SharedInstanceState, SourceFeedbackValue, Tag, TargetValueChangedEvent,
+    VirtualControlElementId, VirtualSource, VirtualSourceValue,
 };
 use derivative::Derivative;
 use enum_map::EnumMap;
@@ -33,8 +33,9 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
 use core::iter;
-use helgoboss_learn::AbsoluteValue;
+use helgoboss_learn::{AbsoluteValue, UnitValue};
 use itertools::Itertools;
+use playtime_clip_engine::main::ClipMatrixEvent;
 use reaper_medium::RecordingInput;
 use std::rc::{Rc, Weak};
 
@@ -43,7 +44,8 @@ pub trait SessionUi {
     fn target_value_changed(&self, event: TargetValueChangedEvent);
     fn parameters_changed(&self, session: &Session);
     fn send_projection_feedback(&self, session: &Session, value: ProjectionFeedbackValue);
-    fn mapping_matched(&self, event: MappingMatchedEvent);
+    fn mapping_matched(&self, session: &Session, event: MappingMatchedEvent);
+    fn clip_matrix_changed(&self, session: &Session, event: &ClipMatrixEvent);
     fn handle_affected(
         &self,
         session: &Session,
@@ -404,6 +406,11 @@ impl Session {
             .send_complaining(NormalMainTask::HitTarget { id, value });
     }
 
+    pub fn emulate_virtual_control_element(&self, value: VirtualSourceValue) {
+        self.normal_main_task_sender
+            .send_complaining(NormalMainTask::EmulateVirtualControlElement(value));
+    }
+
     /// Connects the dots.
     // TODO-low Too large. Split this into several methods.
     pub fn activate(&mut self, weak_session: WeakSession) {
@@ -585,6 +592,16 @@ impl Session {
                 return Some(CompoundMappingSource::Virtual(virt_source));
             }
         }
+        if let Some(guess) = event.result.source_character_guess() {
+            if guess.is_uncertain() {
+                notification::warn(format!(
+                    "Not sure about the character of the learned MIDI source (confidence {:.0}%). \
+                    Please check it or learn again while turning the control element quickly into \
+                    both directions.",
+                    guess.confidence.get() * 100.0
+                ));
+            }
+        }
         CompoundMappingSource::from_message_capture_event(event)
     }
 
@@ -1442,6 +1459,14 @@ impl Session {
             .find(|(_, m)| m.borrow().id() == mapping_id)
     }
 
+    pub fn find_mapping_by_key(
+        &self,
+        compartment: Compartment,
+        key: &MappingKey,
+    ) -> Option<&SharedMapping> {
+        self.mappings(compartment).find(|m| m.borrow().key() == key)
+    }
+
     pub fn mappings(&self, compartment: Compartment) -> impl Iterator<Item = &SharedMapping> {
         self.mappings[compartment].iter()
     }
@@ -1917,6 +1942,22 @@ impl Session {
         self.params.compartment_params_mut(compartment).reset_all();
     }
 
+    /// Sets the value of the given compartment parameter by setting the corresponding plug-in
+    /// parameter of the containing FX (as if the host would have done it).
+    pub fn set_compartment_param_value(
+        &self,
+        compartment: Compartment,
+        index: CompartmentParamIndex,
+        value: UnitValue,
+    ) -> Result<(), &'static str> {
+        let plugin_param_index = compartment.to_plugin_param_index(index);
+        self.processor_context
+            .containing_fx()
+            .parameter_by_index(plugin_param_index.get())
+            .set_reaper_normalized_value(value.get())
+            .map_err(|_| "couldn't set parameter value")
+    }
+
     pub fn update_certain_param_settings(
         &mut self,
         compartment: Compartment,
@@ -2025,6 +2066,53 @@ impl Session {
         self.instance_state.borrow().mapping_is_on(id)
     }
 
+    /// Broadcasts on/off state changes to server clients, but only those which really differ
+    /// from the current state.
+    fn emit_mapping_on_state_changes(&self, new_on_mappings: &HashSet<QualifiedMappingId>) {
+        if !BackboneState::server_events_have_subscribers() {
+            return;
+        }
+        for compartment in Compartment::enum_iter() {
+            for m in self.mappings(compartment) {
+                let m = m.borrow();
+                let id = m.qualified_id();
+                let is_on = new_on_mappings.contains(&id);
+                if self.mapping_is_on(id) != is_on {
+                    self.emit_server_event(ServerEventPayload::MappingOnStateChanged {
+                        compartment,
+                        mapping_key: m.key().clone(),
+                        is_on,
+                    });
+                }
+            }
+        }
+    }
+
+    fn emit_target_value_change(&self, event: &TargetValueChangedEvent) {
+        if !BackboneState::server_events_have_subscribers() {
+            return;
+        }
+        let mapping_key =
+            match self.find_mapping_and_index_by_id(event.compartment, event.mapping_id) {
+                None => return,
+                Some((_, m)) => m.borrow().key().clone(),
+            };
+        self.emit_server_event(ServerEventPayload::TargetValueChanged {
+            compartment: event.compartment,
+            mapping_key,
+            value: event.new_value.to_unit_value(),
+        });
+    }
+
+    fn emit_server_event(&self, payload: ServerEventPayload) {
+        let event = ServerEvent {
+            instance_id: self.instance_id,
+            payload,
+        };
+        // An error just means that there are no subscribers, which is fine.
+        let _ = BackboneState::server_event_sender().send(event);
+    }
+
     fn log_debug_info_internal(&self) {
         // Summary
         let msg = format!(
@@ -2307,15 +2395,25 @@ impl DomainEventHandler for WeakSession {
                 session.borrow_mut().captured_incoming_message(event);
             }
             UpdatedOnMappings(on_mappings) => {
+                let session = session.borrow();
+                session.emit_mapping_on_state_changes(&on_mappings);
                 session
-                    .borrow()
                     .instance_state
                     .borrow_mut()
                     .set_on_mappings(on_mappings);
             }
             UpdatedSingleMappingOnState(event) => {
+                let session = session.borrow();
+                if session.mapping_is_on(event.id) != event.is_on {
+                    if let Some((_, m)) = session.find_mapping_and_index_by_qualified_id(event.id) {
+                        session.emit_server_event(ServerEventPayload::MappingOnStateChanged {
+                            compartment: event.id.compartment,
+                            mapping_key: m.borrow().key().clone(),
+                            is_on: event.is_on,
+                        });
+                    }
+                }
                 session
-                    .borrow()
                     .instance_state
                     .borrow_mut()
                     .set_mapping_on(event.id, event.is_on);
@@ -2326,6 +2424,7 @@ impl DomainEventHandler for WeakSession {
                 // tempo notification, https://github.com/helgoboss/realearn/issues/199). If the
                 // target value slider is not updated then ... so what.
                 if let Ok(s) = session.try_borrow() {
+                    s.emit_target_value_change(&e);
                     s.ui.target_value_changed(e);
                 }
             }
@@ -2349,7 +2448,12 @@ impl DomainEventHandler for WeakSession {
             }
             MappingMatched(event) => {
                 if let Ok(s) = session.try_borrow() {
-                    s.ui.mapping_matched(event);
+                    s.ui.mapping_matched(&s, event);
+                }
+            }
+            ClipMatrixChanged(event) => {
+                if let Ok(s) = session.try_borrow() {
+                    s.ui.clip_matrix_changed(&s, event);
                 }
             }
             MappingEnabledChangeRequested(event) => {
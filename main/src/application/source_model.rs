@@ -2,10 +2,12 @@ use crate::application::{
     Affected, Change, GetProcessingRelevance, MappingProp, ProcessingRelevance,
 };
 use crate::domain::{
-    BackboneState, Compartment, CompoundMappingSource, EelMidiSourceScript,
-    ExtendedSourceCharacter, FlexibleMidiSourceScript, KeySource, Keystroke, LuaMidiSourceScript,
-    MidiSource, ReaperSource, TimerSource, VirtualControlElement, VirtualControlElementId,
-    VirtualSource, VirtualTarget,
+    BackboneState, BeatGrid, BeatSource, BeatSourceOutput, ChordMemberKind, ChordSource,
+    Compartment, CompartmentParamIndex, CompoundMappingSource, EelMidiSourceScript,
+    ExtendedSourceCharacter, FlexibleMidiSourceScript, KeySource, KeySourceMode, Keystroke,
+    LfoRate, LfoSource, LfoWaveform, LuaMidiControlScript, LuaMidiSourceScript,
+    MidiControlScriptSource, MidiSource, ReaperSource, SequenceSource, TimerSource,
+    VirtualControlElement, VirtualControlElementId, VirtualSource, VirtualTarget,
 };
 use derive_more::Display;
 use enum_iterator::IntoEnumIterator;
@@ -25,6 +27,9 @@ use std::fmt;
 use std::fmt::Display;
 use std::time::Duration;
 
+/// Steps per beat if the beat source is set to subdivisions (= 16th notes in 4/4).
+pub const DEFAULT_BEAT_SUBDIVISIONS: u32 = 4;
+
 #[allow(clippy::enum_variant_names)]
 pub enum SourceCommand {
     SetCategory(SourceCategory),
@@ -50,7 +55,21 @@ pub enum SourceCommand {
     SetOscFeedbackArgs(Vec<String>),
     SetReaperSourceType(ReaperSourceType),
     SetTimerMillis(u64),
+    SetLfoWaveform(LfoWaveform),
+    SetLfoRate(LfoRate),
+    SetLfoPhase(UnitValue),
+    SetLfoDepth(UnitValue),
+    SetBeatGrid(BeatGrid),
+    SetBeatSubdivisions(u32),
+    SetBeatOnlyWhilePlaying(bool),
+    SetBeatOutput(BeatSourceOutput),
+    SetParameterIndex(CompartmentParamIndex),
     SetKeystroke(Option<Keystroke>),
+    SetKeyMode(KeySourceMode),
+    SetChordMembers(Vec<SourceModel>),
+    SetChordWindowMillis(Option<u64>),
+    SetSequenceSteps(Vec<SourceModel>),
+    SetSequenceTimeoutMillis(Option<u64>),
     SetControlElementType(VirtualControlElementType),
     SetControlElementId(VirtualControlElementId),
 }
@@ -82,7 +101,21 @@ pub enum SourceProp {
     ControlElementType,
     ControlElementId,
     TimerMillis,
+    LfoWaveform,
+    LfoRate,
+    LfoPhase,
+    LfoDepth,
+    BeatGrid,
+    BeatSubdivisions,
+    BeatOnlyWhilePlaying,
+    BeatOutput,
+    ParameterIndex,
     Keystroke,
+    KeyMode,
+    ChordMembers,
+    ChordWindowMillis,
+    SequenceSteps,
+    SequenceTimeoutMillis,
 }
 
 impl GetProcessingRelevance for SourceProp {
@@ -201,10 +234,66 @@ impl<'a> Change<'a> for SourceModel {
                 self.timer_millis = v;
                 One(P::TimerMillis)
             }
+            C::SetLfoWaveform(v) => {
+                self.lfo_waveform = v;
+                One(P::LfoWaveform)
+            }
+            C::SetLfoRate(v) => {
+                self.lfo_rate = v;
+                One(P::LfoRate)
+            }
+            C::SetLfoPhase(v) => {
+                self.lfo_phase = v;
+                One(P::LfoPhase)
+            }
+            C::SetLfoDepth(v) => {
+                self.lfo_depth = v;
+                One(P::LfoDepth)
+            }
+            C::SetBeatGrid(v) => {
+                self.beat_grid = v;
+                One(P::BeatGrid)
+            }
+            C::SetBeatSubdivisions(v) => {
+                self.beat_subdivisions = v;
+                One(P::BeatSubdivisions)
+            }
+            C::SetBeatOnlyWhilePlaying(v) => {
+                self.beat_only_while_playing = v;
+                One(P::BeatOnlyWhilePlaying)
+            }
+            C::SetBeatOutput(v) => {
+                self.beat_output = v;
+                One(P::BeatOutput)
+            }
+            C::SetParameterIndex(v) => {
+                self.parameter_index = v;
+                One(P::ParameterIndex)
+            }
             C::SetKeystroke(v) => {
                 self.keystroke = v;
                 One(P::Keystroke)
             }
+            C::SetKeyMode(v) => {
+                self.key_mode = v;
+                One(P::KeyMode)
+            }
+            C::SetChordMembers(v) => {
+                self.chord_members = v;
+                One(P::ChordMembers)
+            }
+            C::SetChordWindowMillis(v) => {
+                self.chord_window_millis = v;
+                One(P::ChordWindowMillis)
+            }
+            C::SetSequenceSteps(v) => {
+                self.sequence_steps = v;
+                One(P::SequenceSteps)
+            }
+            C::SetSequenceTimeoutMillis(v) => {
+                self.sequence_timeout_millis = v;
+                One(P::SequenceTimeoutMillis)
+            }
         };
         Some(affected)
     }
@@ -239,8 +328,24 @@ pub struct SourceModel {
     // REAPER
     reaper_source_type: ReaperSourceType,
     timer_millis: u64,
+    lfo_waveform: LfoWaveform,
+    lfo_rate: LfoRate,
+    lfo_phase: UnitValue,
+    lfo_depth: UnitValue,
+    beat_grid: BeatGrid,
+    beat_subdivisions: u32,
+    beat_only_while_playing: bool,
+    beat_output: BeatSourceOutput,
+    parameter_index: CompartmentParamIndex,
     // Key
     keystroke: Option<Keystroke>,
+    key_mode: KeySourceMode,
+    // Chord
+    chord_members: Vec<SourceModel>,
+    chord_window_millis: Option<u64>,
+    // Sequence
+    sequence_steps: Vec<SourceModel>,
+    sequence_timeout_millis: Option<u64>,
     // Virtual
     control_element_type: VirtualControlElementType,
     control_element_id: VirtualControlElementId,
@@ -274,7 +379,21 @@ impl Default for SourceModel {
             osc_feedback_args: vec![],
             reaper_source_type: Default::default(),
             timer_millis: Default::default(),
+            lfo_waveform: Default::default(),
+            lfo_rate: Default::default(),
+            lfo_phase: UnitValue::MIN,
+            lfo_depth: UnitValue::MAX,
+            beat_grid: Default::default(),
+            beat_subdivisions: DEFAULT_BEAT_SUBDIVISIONS,
+            beat_only_while_playing: false,
+            beat_output: Default::default(),
+            parameter_index: Default::default(),
             keystroke: None,
+            key_mode: Default::default(),
+            chord_members: vec![],
+            chord_window_millis: None,
+            sequence_steps: vec![],
+            sequence_timeout_millis: None,
         }
     }
 }
@@ -368,6 +487,26 @@ impl SourceModel {
         self.keystroke
     }
 
+    pub fn key_mode(&self) -> KeySourceMode {
+        self.key_mode
+    }
+
+    pub fn chord_members(&self) -> &[SourceModel] {
+        &self.chord_members
+    }
+
+    pub fn chord_window_millis(&self) -> Option<u64> {
+        self.chord_window_millis
+    }
+
+    pub fn sequence_steps(&self) -> &[SourceModel] {
+        &self.sequence_steps
+    }
+
+    pub fn sequence_timeout_millis(&self) -> Option<u64> {
+        self.sequence_timeout_millis
+    }
+
     pub fn reaper_source_type(&self) -> ReaperSourceType {
         self.reaper_source_type
     }
@@ -376,6 +515,42 @@ impl SourceModel {
         self.timer_millis
     }
 
+    pub fn lfo_waveform(&self) -> LfoWaveform {
+        self.lfo_waveform
+    }
+
+    pub fn lfo_rate(&self) -> LfoRate {
+        self.lfo_rate
+    }
+
+    pub fn lfo_phase(&self) -> UnitValue {
+        self.lfo_phase
+    }
+
+    pub fn lfo_depth(&self) -> UnitValue {
+        self.lfo_depth
+    }
+
+    pub fn beat_grid(&self) -> BeatGrid {
+        self.beat_grid
+    }
+
+    pub fn beat_subdivisions(&self) -> u32 {
+        self.beat_subdivisions
+    }
+
+    pub fn beat_only_while_playing(&self) -> bool {
+        self.beat_only_while_playing
+    }
+
+    pub fn beat_output(&self) -> BeatSourceOutput {
+        self.beat_output
+    }
+
+    pub fn parameter_index(&self) -> CompartmentParamIndex {
+        self.parameter_index
+    }
+
     pub fn control_element_type(&self) -> VirtualControlElementType {
         self.control_element_type
     }
@@ -389,7 +564,7 @@ impl SourceModel {
         match self.category {
             Midi => self.midi_source_type.supports_control(),
             Osc => self.osc_arg_type_tag.supports_control(),
-            Virtual | Keyboard | Reaper => true,
+            Virtual | Keyboard | Reaper | Chord | Sequence => true,
             // Main use case: Group interaction (follow-only).
             Never => true,
         }
@@ -401,7 +576,7 @@ impl SourceModel {
             Midi => self.midi_source_type.supports_feedback(),
             Osc => self.osc_arg_type_tag.supports_feedback(),
             Virtual => true,
-            Reaper | Keyboard | Never => false,
+            Reaper | Keyboard | Chord | Sequence | Never => false,
         }
     }
 
@@ -411,6 +586,25 @@ impl SourceModel {
         source: &CompoundMappingSource,
     ) -> Option<Affected<MappingProp>> {
         use CompoundMappingSource::*;
+        if Self::can_be_compound_member(source) {
+            match self.category {
+                SourceCategory::Chord => {
+                    // Learning in chord mode adds the learned source as member or removes it if
+                    // it's already a member.
+                    if !self.toggle_chord_member(source) {
+                        return None;
+                    }
+                    return Some(Affected::Multiple);
+                }
+                SourceCategory::Sequence => {
+                    // Learning in sequence mode appends the learned source as next step.
+                    self.sequence_steps
+                        .push(Self::create_compound_member(source));
+                    return Some(Affected::Multiple);
+                }
+                _ => {}
+            }
+        }
         match source {
             Midi(s) => {
                 self.category = SourceCategory::Midi;
@@ -433,10 +627,12 @@ impl SourceModel {
                     }
                     ControlChange14BitValue {
                         msb_controller_number,
+                        custom_character,
                         ..
                     } => {
                         self.is_14_bit = Some(true);
                         self.midi_message_number = msb_controller_number.map(Into::into);
+                        self.custom_character = *custom_character;
                     }
                     ParameterNumberValue {
                         number,
@@ -482,6 +678,9 @@ impl SourceModel {
             Reaper(s) => {
                 self.category = SourceCategory::Reaper;
                 self.reaper_source_type = ReaperSourceType::from_source(s);
+                if let ReaperSource::CompartmentParameterValue(i) = s {
+                    self.parameter_index = *i;
+                }
             }
             Never => {
                 self.category = SourceCategory::Never;
@@ -489,11 +688,71 @@ impl SourceModel {
             Key(s) => {
                 self.category = SourceCategory::Keyboard;
                 self.keystroke = Some(s.stroke());
+                self.key_mode = s.mode();
+            }
+            Chord(s) => {
+                self.category = SourceCategory::Chord;
+                self.chord_members = s
+                    .members()
+                    .filter(|m| Self::can_be_compound_member(m))
+                    .map(Self::create_compound_member)
+                    .collect();
+                self.chord_window_millis = s.window().map(|w| w.as_millis() as u64);
+            }
+            MidiControlScript(s) => {
+                self.category = SourceCategory::Midi;
+                self.midi_source_type = MidiSourceType::ControlScript;
+                self.midi_script = s.script_code().unwrap_or_default().to_owned();
+                self.custom_character = s.character();
+            }
+            Sequence(s) => {
+                self.category = SourceCategory::Sequence;
+                self.sequence_steps = s
+                    .steps()
+                    .filter(|m| Self::can_be_compound_member(m))
+                    .map(Self::create_compound_member)
+                    .collect();
+                self.sequence_timeout_millis = s.timeout().map(|t| t.as_millis() as u64);
             }
         };
         Some(Affected::Multiple)
     }
 
+    fn can_be_compound_member(source: &CompoundMappingSource) -> bool {
+        use CompoundMappingSource::*;
+        matches!(source, Midi(_) | Osc(_) | Virtual(_) | Key(_))
+    }
+
+    fn create_compound_member(source: &CompoundMappingSource) -> SourceModel {
+        let mut model = SourceModel::default();
+        let _ = model.apply_from_source(source);
+        model
+    }
+
+    /// Returns `false` if the given source couldn't be added because it's of another kind than
+    /// the existing members.
+    fn toggle_chord_member(&mut self, source: &CompoundMappingSource) -> bool {
+        let existing_index = self
+            .chord_members
+            .iter()
+            .position(|m| m.create_source() == *source);
+        if let Some(i) = existing_index {
+            self.chord_members.remove(i);
+            return true;
+        }
+        let new_member = Self::create_compound_member(source);
+        let categories = self
+            .chord_members
+            .iter()
+            .chain(std::iter::once(&new_member))
+            .map(|m| m.category);
+        if !chord_members_are_of_same_kind(categories) {
+            return false;
+        }
+        self.chord_members.push(new_member);
+        true
+    }
+
     pub fn format_control_value(&self, value: ControlValue) -> Result<String, &'static str> {
         self.create_source().format_control_value(value)
     }
@@ -532,7 +791,31 @@ impl SourceModel {
                 DetailedSourceCharacter::RangeControl,
                 DetailedSourceCharacter::Relative,
             ],
-            CompoundMappingSource::Key(_) => vec![DetailedSourceCharacter::MomentaryOnOffButton],
+            CompoundMappingSource::Key(s) => match s.mode() {
+                KeySourceMode::Normal => vec![DetailedSourceCharacter::MomentaryOnOffButton],
+                KeySourceMode::Repeat => vec![
+                    DetailedSourceCharacter::MomentaryOnOffButton,
+                    DetailedSourceCharacter::PressOnlyButton,
+                ],
+                KeySourceMode::Accelerate => vec![DetailedSourceCharacter::Relative],
+                KeySourceMode::NumericEntry => vec![DetailedSourceCharacter::RangeControl],
+            },
+            CompoundMappingSource::Chord(_) => {
+                vec![DetailedSourceCharacter::MomentaryOnOffButton]
+            }
+            CompoundMappingSource::Sequence(_) => vec![DetailedSourceCharacter::PressOnlyButton],
+            CompoundMappingSource::MidiControlScript(s) => match s.character() {
+                SourceCharacter::RangeElement => vec![DetailedSourceCharacter::RangeControl],
+                SourceCharacter::MomentaryButton | SourceCharacter::ToggleButton => vec![
+                    DetailedSourceCharacter::MomentaryOnOffButton,
+                    DetailedSourceCharacter::PressOnlyButton,
+                ],
+                SourceCharacter::Encoder1
+                | SourceCharacter::Encoder2
+                | SourceCharacter::Encoder3 => {
+                    vec![DetailedSourceCharacter::Relative]
+                }
+            },
         }
     }
 
@@ -617,6 +900,10 @@ impl SourceModel {
                     Display => MidiSource::Display {
                         spec: self.display_spec(),
                     },
+                    ControlScript => {
+                        let source = self.create_midi_control_script_source();
+                        return Some(CompoundMappingSource::MidiControlScript(source));
+                    }
                 };
                 CompoundMappingSource::Midi(midi_source)
             }
@@ -640,24 +927,81 @@ impl SourceModel {
                 let reaper_source = match self.reaper_source_type {
                     MidiDeviceChanges => ReaperSource::MidiDeviceChanges,
                     RealearnInstanceStart => ReaperSource::RealearnInstanceStart,
+                    TrackSelectionChanges => ReaperSource::TrackSelectionChanges,
+                    PlayStateChanges => ReaperSource::PlayStateChanges,
+                    RecordStateChanges => ReaperSource::RecordStateChanges,
+                    ProjectSwitch => ReaperSource::ProjectSwitch,
+                    FxFocusChanges => ReaperSource::FxFocusChanges,
+                    MarkerPassed => ReaperSource::MarkerPassed,
+                    CompartmentParameterValue => {
+                        ReaperSource::CompartmentParameterValue(self.parameter_index)
+                    }
                     Timer => ReaperSource::Timer(self.create_timer_source()),
+                    Lfo => ReaperSource::Lfo(self.create_lfo_source()),
+                    Beat => ReaperSource::Beat(self.create_beat_source()),
                 };
                 CompoundMappingSource::Reaper(reaper_source)
             }
             Never => CompoundMappingSource::Never,
             Keyboard => CompoundMappingSource::Key(self.create_key_source()?),
+            Chord => CompoundMappingSource::Chord(self.create_chord_source()),
+            Sequence => CompoundMappingSource::Sequence(self.create_sequence_source()),
         };
         Some(source)
     }
 
     pub fn create_key_source(&self) -> Option<KeySource> {
-        Some(KeySource::new(self.keystroke?))
+        Some(KeySource::new(self.keystroke?, self.key_mode))
+    }
+
+    fn create_midi_control_script_source(&self) -> MidiControlScriptSource {
+        let lua = unsafe { BackboneState::main_thread_lua() };
+        let script = LuaMidiControlScript::compile(lua, &self.midi_script).ok();
+        MidiControlScriptSource::new(script, self.custom_character)
+    }
+
+    fn create_chord_source(&self) -> ChordSource {
+        ChordSource::new(
+            self.chord_members
+                .iter()
+                .map(|m| m.create_source())
+                .collect(),
+            self.chord_window_millis.map(Duration::from_millis),
+        )
+    }
+
+    fn create_sequence_source(&self) -> SequenceSource {
+        SequenceSource::new(
+            self.sequence_steps
+                .iter()
+                .map(|m| m.create_source())
+                .collect(),
+            self.sequence_timeout_millis.map(Duration::from_millis),
+        )
     }
 
     fn create_timer_source(&self) -> TimerSource {
         TimerSource::new(Duration::from_millis(self.timer_millis))
     }
 
+    fn create_beat_source(&self) -> BeatSource {
+        BeatSource::new(
+            self.beat_grid,
+            self.beat_subdivisions,
+            self.beat_only_while_playing,
+            self.beat_output,
+        )
+    }
+
+    fn create_lfo_source(&self) -> LfoSource {
+        LfoSource::new(
+            self.lfo_waveform,
+            self.lfo_rate,
+            self.lfo_phase,
+            self.lfo_depth,
+        )
+    }
+
     fn display_spec(&self) -> DisplaySpec {
         use DisplayType::*;
         match self.display_type {
@@ -728,7 +1072,11 @@ impl SourceModel {
     }
 
     pub fn is_midi_script(&self) -> bool {
-        self.category == SourceCategory::Midi && self.midi_source_type == MidiSourceType::Script
+        self.category == SourceCategory::Midi
+            && matches!(
+                self.midi_source_type,
+                MidiSourceType::Script | MidiSourceType::ControlScript
+            )
     }
 
     fn channel_label(&self) -> Cow<str> {
@@ -822,6 +1170,7 @@ impl Display for SourceModel {
                     vec![t.to_string().into(), self.channel_label(), line_3, line_4]
                 }
                 t @ MidiSourceType::Display => vec![t.to_string().into()],
+                t @ MidiSourceType::ControlScript => vec![t.to_string().into()],
                 t => vec![t.to_string().into(), self.channel_label()],
             },
             Virtual => vec![
@@ -829,16 +1178,57 @@ impl Display for SourceModel {
                 self.create_control_element().to_string().into(),
             ],
             Osc => vec!["OSC".into(), (&self.osc_address_pattern).into()],
-            Reaper => {
-                vec![self.reaper_source_type.to_string().into()]
-            }
+            Reaper => match self.reaper_source_type {
+                ReaperSourceType::Lfo => vec![
+                    "LFO".into(),
+                    self.lfo_waveform.to_string().into(),
+                    self.lfo_rate.to_string().into(),
+                ],
+                ReaperSourceType::Beat => {
+                    let grid = match self.beat_grid {
+                        BeatGrid::Subdivision => format!("1/{} beat", self.beat_subdivisions),
+                        g => g.to_string(),
+                    };
+                    vec![
+                        "Beat".into(),
+                        grid.into(),
+                        self.beat_output.to_string().into(),
+                    ]
+                }
+                ReaperSourceType::CompartmentParameterValue => vec![
+                    "Parameter".into(),
+                    (self.parameter_index.get() + 1).to_string().into(),
+                ],
+                t => vec![t.to_string().into()],
+            },
             Never => vec!["None".into()],
             Keyboard => {
                 let text = self
                     .create_key_source()
                     .map(|s| Cow::Owned(s.to_string()))
                     .unwrap_or_else(|| Cow::Borrowed(KEY_UNDEFINED_LABEL));
-                vec![text]
+                match self.key_mode {
+                    KeySourceMode::Normal => vec![text],
+                    mode => vec![text, mode.to_string().into()],
+                }
+            }
+            Chord => {
+                let members = itertools::join(
+                    self.chord_members
+                        .iter()
+                        .map(|m| m.to_string().replace('\n', " ")),
+                    " + ",
+                );
+                vec!["Chord".into(), members.into()]
+            }
+            Sequence => {
+                let steps = itertools::join(
+                    self.sequence_steps
+                        .iter()
+                        .map(|m| m.to_string().replace('\n', " ")),
+                    ", then ",
+                );
+                vec!["Sequence".into(), steps.into()]
             }
         };
         let non_empty_lines: Vec<_> = lines.into_iter().filter(|l| !l.is_empty()).collect();
@@ -881,6 +1271,12 @@ pub enum SourceCategory {
     #[serde(rename = "virtual")]
     #[display(fmt = "Virtual")]
     Virtual,
+    #[serde(rename = "chord")]
+    #[display(fmt = "Chord")]
+    Chord,
+    #[serde(rename = "sequence")]
+    #[display(fmt = "Sequence")]
+    Sequence,
 }
 
 impl SourceCategory {
@@ -902,10 +1298,33 @@ impl SourceCategory {
                 Reaper => true,
                 Keyboard => true,
                 Virtual => false,
+                Chord => true,
+                Sequence => true,
             },
             Compartment::Main => true,
         }
     }
+
+    pub fn chord_member_kind(self) -> Option<ChordMemberKind> {
+        use SourceCategory::*;
+        match self {
+            Midi => Some(ChordMemberKind::Midi),
+            Osc | Keyboard => Some(ChordMemberKind::OscOrKeyboard),
+            Virtual => Some(ChordMemberKind::Virtual),
+            Never | Reaper | Chord | Sequence => None,
+        }
+    }
+}
+
+/// Returns whether members with the given categories can be combined into one chord.
+pub fn chord_members_are_of_same_kind(
+    mut categories: impl Iterator<Item = SourceCategory>,
+) -> bool {
+    let first_kind = match categories.next() {
+        None => return true,
+        Some(c) => c.chord_member_kind(),
+    };
+    categories.all(|c| c.chord_member_kind() == first_kind)
 }
 
 impl Default for SourceCategory {
@@ -956,6 +1375,8 @@ pub enum MidiSourceType {
     Script = 11,
     #[display(fmt = "Display (feedback only)")]
     Display = 12,
+    #[display(fmt = "MIDI control script (Lua)")]
+    ControlScript = 13,
 }
 
 impl Default for MidiSourceType {
@@ -1033,7 +1454,10 @@ impl MidiSourceType {
 
     pub fn supports_custom_character(self) -> bool {
         use MidiSourceType::*;
-        matches!(self, ControlChangeValue | ParameterNumberValue | Raw)
+        matches!(
+            self,
+            ControlChangeValue | ParameterNumberValue | Raw | ControlScript
+        )
     }
 
     fn supports_parameter_number_message_props(self) -> bool {
@@ -1047,7 +1471,7 @@ impl MidiSourceType {
 
     pub fn supports_feedback(self) -> bool {
         use MidiSourceType::*;
-        !matches!(self, ClockTempo | ClockTransport)
+        !matches!(self, ClockTempo | ClockTransport | ControlScript)
     }
 }
 
@@ -1129,9 +1553,36 @@ pub enum ReaperSourceType {
     #[serde(rename = "realearn-instance-start")]
     #[display(fmt = "ReaLearn instance start")]
     RealearnInstanceStart,
+    #[serde(rename = "track-selection-changes")]
+    #[display(fmt = "Track selection changes")]
+    TrackSelectionChanges,
+    #[serde(rename = "play-state-changes")]
+    #[display(fmt = "Play state changes")]
+    PlayStateChanges,
+    #[serde(rename = "record-state-changes")]
+    #[display(fmt = "Record state changes")]
+    RecordStateChanges,
+    #[serde(rename = "project-switch")]
+    #[display(fmt = "Project switch")]
+    ProjectSwitch,
+    #[serde(rename = "fx-focus-changes")]
+    #[display(fmt = "FX focus changes")]
+    FxFocusChanges,
+    #[serde(rename = "marker-passed")]
+    #[display(fmt = "Marker passed")]
+    MarkerPassed,
+    #[serde(rename = "compartment-parameter-value")]
+    #[display(fmt = "Compartment parameter value")]
+    CompartmentParameterValue,
     #[serde(rename = "timer")]
     #[display(fmt = "Timer")]
     Timer,
+    #[serde(rename = "lfo")]
+    #[display(fmt = "LFO")]
+    Lfo,
+    #[serde(rename = "beat")]
+    #[display(fmt = "Beat")]
+    Beat,
 }
 
 impl Default for ReaperSourceType {
@@ -1146,7 +1597,16 @@ impl ReaperSourceType {
         match source {
             MidiDeviceChanges => Self::MidiDeviceChanges,
             RealearnInstanceStart => Self::RealearnInstanceStart,
+            TrackSelectionChanges => Self::TrackSelectionChanges,
+            PlayStateChanges => Self::PlayStateChanges,
+            RecordStateChanges => Self::RecordStateChanges,
+            ProjectSwitch => Self::ProjectSwitch,
+            FxFocusChanges => Self::FxFocusChanges,
+            MarkerPassed => Self::MarkerPassed,
+            CompartmentParameterValue(_) => Self::CompartmentParameterValue,
             Timer(_) => Self::Timer,
+            Lfo(_) => Self::Lfo,
+            Beat(_) => Self::Beat,
         }
     }
 }
@@ -1179,4 +1639,20 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn chord_member_kinds() {
+        // Given
+        use SourceCategory::*;
+        // When
+        // Then
+        assert!(chord_members_are_of_same_kind([].into_iter()));
+        assert!(chord_members_are_of_same_kind([Midi, Midi].into_iter()));
+        assert!(chord_members_are_of_same_kind([Osc, Keyboard].into_iter()));
+        assert!(!chord_members_are_of_same_kind([Midi, Osc].into_iter()));
+        assert!(!chord_members_are_of_same_kind(
+            [Virtual, Keyboard].into_iter()
+        ));
+        assert!(!chord_members_are_of_same_kind([Virtual, Midi].into_iter()));
+    }
 }
@@ -1,8 +1,12 @@
+#[cfg(feature = "realearn-metrics")]
+use crate::base::ChannelMetrics;
 use crossbeam_channel::{Receiver, Sender, TrySendError};
 use reaper_high::Reaper;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "realearn-metrics")]
+use std::sync::Arc;
 
 pub trait NamedChannelSender {
     type Msg;
@@ -18,6 +22,8 @@ pub trait NamedChannelSender {
 pub struct SenderToNormalThread<T> {
     channel_name: &'static str,
     sender: Sender<T>,
+    #[cfg(feature = "realearn-metrics")]
+    metrics: Arc<ChannelMetrics>,
 }
 
 impl<T> Debug for SenderToNormalThread<T> {
@@ -44,28 +50,28 @@ impl<T> NamedChannelSender for SenderToNormalThread<T> {
 impl<T> SenderToNormalThread<T> {
     pub fn new_bounded_channel(name: &'static str, capacity: usize) -> (Self, Receiver<T>) {
         let (sender, receiver) = crossbeam_channel::bounded(capacity);
-        (
-            Self {
-                channel_name: name,
-                sender,
-            },
-            receiver,
-        )
+        (Self::new(name, sender), receiver)
     }
 
     pub fn new_unbounded_channel(name: &'static str) -> (Self, Receiver<T>) {
         let (sender, receiver) = crossbeam_channel::unbounded();
-        (
-            Self {
-                channel_name: name,
-                sender,
-            },
-            receiver,
-        )
+        (Self::new(name, sender), receiver)
+    }
+
+    fn new(channel_name: &'static str, sender: Sender<T>) -> Self {
+        Self {
+            channel_name,
+            #[cfg(feature = "realearn-metrics")]
+            metrics: ChannelMetrics::register(channel_name, sender.capacity()),
+            sender,
+        }
     }
 
     pub fn try_to_send(&self, msg: T) -> bool {
-        self.sender.try_send(msg).is_ok()
+        let result = self.sender.try_send(msg);
+        #[cfg(feature = "realearn-metrics")]
+        self.record_send_result(&result);
+        result.is_ok()
     }
 
     pub fn is_bounded(&self) -> bool {
@@ -73,7 +79,19 @@ impl<T> SenderToNormalThread<T> {
     }
 
     fn send_internal(&self, msg: T) -> Result<(), NamedChannelTrySendError<T>> {
-        try_send_on_named_channel(&self.sender, self.channel_name, msg)
+        let result = try_send_on_named_channel(&self.sender, self.channel_name, msg);
+        #[cfg(feature = "realearn-metrics")]
+        self.record_send_result(&result);
+        result
+    }
+
+    #[cfg(feature = "realearn-metrics")]
+    fn record_send_result<R, E>(&self, result: &Result<R, E>) {
+        if result.is_ok() {
+            self.metrics.record_len(self.sender.len());
+        } else {
+            self.metrics.record_drop();
+        }
     }
 }
 
@@ -82,6 +100,8 @@ impl<T> Clone for SenderToNormalThread<T> {
         Self {
             channel_name: self.channel_name,
             sender: self.sender.clone(),
+            #[cfg(feature = "realearn-metrics")]
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -0,0 +1,150 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Statistics of one channel.
+///
+/// Updated lock-free and without allocation, so it's okay to record them in real-time threads.
+/// Published as Prometheus metrics by [`export_channel_metrics`] whenever metrics are scraped.
+#[derive(Debug)]
+pub struct ChannelMetrics {
+    channel_name: &'static str,
+    capacity: Option<usize>,
+    len: AtomicUsize,
+    max_len: AtomicUsize,
+    dropped_count: AtomicU64,
+}
+
+impl ChannelMetrics {
+    /// Creates statistics for a new channel and registers them for export.
+    pub fn register(channel_name: &'static str, capacity: Option<usize>) -> Arc<Self> {
+        let metrics = Arc::new(Self {
+            channel_name,
+            capacity,
+            len: AtomicUsize::new(0),
+            max_len: AtomicUsize::new(0),
+            dropped_count: AtomicU64::new(0),
+        });
+        CHANNEL_METRICS_REGISTRY
+            .lock()
+            .unwrap()
+            .channels
+            .push(metrics.clone());
+        metrics
+    }
+
+    /// Records the number of messages in the queue right after sending a message.
+    pub fn record_len(&self, len: usize) {
+        self.len.store(len, Ordering::Relaxed);
+        self.max_len.fetch_max(len, Ordering::Relaxed);
+    }
+
+    /// Records that a message couldn't be sent because the channel was full or disconnected.
+    pub fn record_drop(&self) {
+        self.dropped_count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct ChannelMetricsRegistry {
+    channels: Vec<Arc<ChannelMetrics>>,
+    /// Dropped message counts of channels which don't exist anymore, so the exported counter
+    /// doesn't decrease when an instance is removed.
+    retired_dropped_counts: HashMap<&'static str, u64>,
+}
+
+static CHANNEL_METRICS_REGISTRY: Lazy<Mutex<ChannelMetricsRegistry>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct AggregatedChannelMetrics {
+    len: usize,
+    max_len: usize,
+    capacity: usize,
+    dropped_count: u64,
+}
+
+/// Publishes the current statistics of all channels to the installed metrics recorder.
+///
+/// There are usually multiple channels with the same name (one per instance), so statistics are
+/// aggregated by channel name.
+pub fn export_channel_metrics() {
+    let mut registry = CHANNEL_METRICS_REGISTRY.lock().unwrap();
+    let ChannelMetricsRegistry {
+        channels,
+        retired_dropped_counts,
+    } = &mut *registry;
+    let mut aggregated: HashMap<&'static str, AggregatedChannelMetrics> = HashMap::new();
+    channels.retain(|m| {
+        let dropped_count = m.dropped_count.load(Ordering::Relaxed);
+        if Arc::strong_count(m) == 1 {
+            // The registry holds the last reference, so all senders are gone.
+            *retired_dropped_counts.entry(m.channel_name).or_default() += dropped_count;
+            return false;
+        }
+        let a = aggregated.entry(m.channel_name).or_default();
+        a.len += m.len.load(Ordering::Relaxed);
+        a.max_len = a.max_len.max(m.max_len.load(Ordering::Relaxed));
+        a.capacity += m.capacity.unwrap_or(0);
+        a.dropped_count += dropped_count;
+        true
+    });
+    for (name, count) in retired_dropped_counts.iter() {
+        aggregated.entry(name).or_default().dropped_count += *count;
+    }
+    for (name, a) in aggregated {
+        metrics::gauge!("realearn_channel_queue_length", a.len as f64, "channel" => name);
+        metrics::gauge!(
+            "realearn_channel_queue_max_length",
+            a.max_len as f64,
+            "channel" => name
+        );
+        if a.capacity > 0 {
+            metrics::gauge!("realearn_channel_capacity", a.capacity as f64, "channel" => name);
+        }
+        metrics::absolute_counter!(
+            "realearn_channel_dropped_messages_total",
+            a.dropped_count,
+            "channel" => name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_channel_metrics() {
+        // Given
+        let metrics = ChannelMetrics::register("test channel", Some(10));
+        // When
+        metrics.record_len(5);
+        metrics.record_len(2);
+        metrics.record_drop();
+        // Then
+        assert_eq!(metrics.len.load(Ordering::Relaxed), 2);
+        assert_eq!(metrics.max_len.load(Ordering::Relaxed), 5);
+        assert_eq!(metrics.dropped_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn keep_dropped_count_of_retired_channels() {
+        // Given
+        let metrics = ChannelMetrics::register("retired test channel", None);
+        metrics.record_drop();
+        // When
+        drop(metrics);
+        export_channel_metrics();
+        // Then
+        let registry = CHANNEL_METRICS_REGISTRY.lock().unwrap();
+        assert!(!registry
+            .channels
+            .iter()
+            .any(|m| m.channel_name == "retired test channel"));
+        assert_eq!(
+            registry.retired_dropped_counts.get("retired test channel"),
+            Some(&1)
+        );
+    }
+}
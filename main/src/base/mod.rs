@@ -37,3 +37,8 @@ pub use channels::*;
 
 mod mutex_util;
 pub use mutex_util::*;
+
+#[cfg(feature = "realearn-metrics")]
+mod metrics_util;
+#[cfg(feature = "realearn-metrics")]
+pub use metrics_util::*;
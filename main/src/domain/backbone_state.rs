@@ -1,10 +1,12 @@
 use crate::base::{SenderToNormalThread, SenderToRealTimeThread};
 use crate::domain::{
-    ClipMatrixRef, ControlInput, DeviceControlInput, DeviceFeedbackOutput, FeedbackOutput,
-    InstanceId, InstanceState, InstanceStateChanged, NormalAudioHookTask, NormalRealTimeTask,
-    QualifiedClipMatrixEvent, RealearnClipMatrix, RealearnTargetContext, ReaperTarget, SafeLua,
-    SharedInstanceState, WeakInstanceState,
+    ClipMatrixRef, Compartment, ControlInput, DeviceControlInput, DeviceFeedbackOutput,
+    FeedbackOutput, InstanceId, InstanceState, InstanceStateChanged, MappingKey,
+    NormalAudioHookTask, NormalRealTimeTask, QualifiedClipMatrixEvent, RealearnClipMatrix,
+    RealearnTargetContext, ReaperTarget, SafeLua, SharedInstanceState, SourceFeedbackValue,
+    WeakInstanceState,
 };
+use helgoboss_learn::UnitValue;
 use playtime_clip_engine::rt::WeakMatrix;
 use reaper_high::{Reaper, Track};
 use std::cell::RefCell;
@@ -28,10 +30,44 @@ pub struct BackboneState {
     /// borrow a clip matrix which is owned by instance A. This is great because it allows us to
     /// control the same clip matrix from different controllers.
     instance_states: RefCell<HashMap<InstanceId, WeakInstanceState>>,
-    server_event_sender: tokio::sync::broadcast::Sender<ServerEventType>,
+    server_event_sender: tokio::sync::broadcast::Sender<ServerEvent>,
+    /// Separate from server events because feedback can come in floods, which shouldn't make
+    /// subscribers of the (much rarer) server events miss anything.
+    source_feedback_event_sender: tokio::sync::broadcast::Sender<SourceFeedbackEvent>,
 }
 
-pub type ServerEventType = f64;
+/// An event which is broadcast to interested server clients (e.g. gRPC streams).
+#[derive(Clone, Debug)]
+pub struct ServerEvent {
+    pub instance_id: InstanceId,
+    pub payload: ServerEventPayload,
+}
+
+#[derive(Clone, Debug)]
+pub enum ServerEventPayload {
+    MappingOnStateChanged {
+        compartment: Compartment,
+        mapping_key: MappingKey,
+        is_on: bool,
+    },
+    TargetValueChanged {
+        compartment: Compartment,
+        mapping_key: MappingKey,
+        value: UnitValue,
+    },
+}
+
+/// Feedback which has just been sent to the controller.
+#[derive(Clone, Debug)]
+pub struct SourceFeedbackEvent {
+    pub instance_id: InstanceId,
+    /// `None` if the feedback is not related to a particular mapping (e.g. when switching off
+    /// a source which has been released).
+    pub origin: Option<(Compartment, MappingKey)>,
+    pub text: Option<String>,
+    pub output: FeedbackOutput,
+    pub value: SourceFeedbackValue,
+}
 
 impl BackboneState {
     pub fn new(target_context: RealearnTargetContext) -> Self {
@@ -43,6 +79,7 @@ impl BackboneState {
             upper_floor_instances: Default::default(),
             instance_states: Default::default(),
             server_event_sender: tokio::sync::broadcast::channel(1000).0,
+            source_feedback_event_sender: tokio::sync::broadcast::channel(1000).0,
         }
     }
 
@@ -69,10 +106,27 @@ impl BackboneState {
         &LUA.0
     }
 
-    pub fn server_event_sender() -> &'static tokio::sync::broadcast::Sender<ServerEventType> {
+    pub fn server_event_sender() -> &'static tokio::sync::broadcast::Sender<ServerEvent> {
         &BackboneState::get().server_event_sender
     }
 
+    /// Returns `true` if at least one server client is interested in server events.
+    ///
+    /// Can be used to avoid building events which nobody is going to receive anyway.
+    pub fn server_events_have_subscribers() -> bool {
+        BackboneState::server_event_sender().receiver_count() > 0
+    }
+
+    pub fn source_feedback_event_sender(
+    ) -> &'static tokio::sync::broadcast::Sender<SourceFeedbackEvent> {
+        &BackboneState::get().source_feedback_event_sender
+    }
+
+    /// Returns `true` if at least one server client is interested in source feedback events.
+    pub fn source_feedback_events_have_subscribers() -> bool {
+        BackboneState::source_feedback_event_sender().receiver_count() > 0
+    }
+
     pub fn target_context() -> &'static RefCell<RealearnTargetContext> {
         &BackboneState::get().target_context
     }
@@ -0,0 +1,164 @@
+use crate::domain::{CompoundMappingSource, ControlEventTimestamp, ControlOutcome};
+use helgoboss_learn::{ControlValue, UnitValue};
+use std::time::Duration;
+
+/// A source which combines several button-like sources into one, e.g. "Shift + Pad 1".
+///
+/// Emits 100% as soon as all members are held together and 0% as soon as one of them is released.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChordSource {
+    members: Vec<ChordMember>,
+    /// If set, all members must be pressed within this time span (counted from the first press).
+    window: Option<Duration>,
+    first_press: Option<ControlEventTimestamp>,
+    is_on: bool,
+}
+
+/// Kind of a chord member with regard to where it gets processed.
+///
+/// The real-time processor and the main processor each keep their own chord state, so a chord can
+/// only fire if all of its members are of the same kind.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ChordMemberKind {
+    /// Processed in the real-time processor.
+    Midi,
+    /// Processed in the main processor.
+    OscOrKeyboard,
+    /// Processed wherever the virtual sources of the controller are processed.
+    Virtual,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct ChordMember {
+    source: CompoundMappingSource,
+    is_held: bool,
+}
+
+impl ChordSource {
+    pub fn new(members: Vec<CompoundMappingSource>, window: Option<Duration>) -> Self {
+        Self {
+            members: members
+                .into_iter()
+                .map(|source| ChordMember {
+                    source,
+                    is_held: false,
+                })
+                .collect(),
+            window,
+            first_press: None,
+            is_on: false,
+        }
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &CompoundMappingSource> {
+        self.members.iter().map(|m| &m.source)
+    }
+
+    pub fn window(&self) -> Option<Duration> {
+        self.window
+    }
+
+    /// Lets each member react to the incoming message using the given function.
+    ///
+    /// Returns `None` if no member reacted at all and `Consumed` if at least one member reacted
+    /// but the chord didn't change its on/off state.
+    pub fn control(
+        &mut self,
+        timestamp: ControlEventTimestamp,
+        mut control_member: impl FnMut(&mut CompoundMappingSource) -> Option<ControlValue>,
+    ) -> Option<ControlOutcome<ControlValue>> {
+        let mut matched = false;
+        for m in &mut self.members {
+            if let Some(v) = control_member(&mut m.source) {
+                m.is_held = v.to_unit_value().map(|v| !v.is_zero()).unwrap_or(false);
+                matched = true;
+            }
+        }
+        if !matched {
+            return None;
+        }
+        Some(self.update(timestamp))
+    }
+
+    fn update(&mut self, timestamp: ControlEventTimestamp) -> ControlOutcome<ControlValue> {
+        let held_count = self.members.iter().filter(|m| m.is_held).count();
+        if held_count == 0 {
+            self.first_press = None;
+        } else if self.first_press.is_none() {
+            self.first_press = Some(timestamp);
+        }
+        let all_held = held_count == self.members.len();
+        let new_is_on = if self.is_on {
+            all_held
+        } else {
+            all_held && self.is_within_window(timestamp)
+        };
+        if new_is_on == self.is_on {
+            return ControlOutcome::Consumed;
+        }
+        self.is_on = new_is_on;
+        let value = if new_is_on {
+            UnitValue::MAX
+        } else {
+            UnitValue::MIN
+        };
+        ControlOutcome::Matched(ControlValue::AbsoluteContinuous(value))
+    }
+
+    fn is_within_window(&self, timestamp: ControlEventTimestamp) -> bool {
+        match (self.window, self.first_press) {
+            (Some(window), Some(first_press)) => timestamp - first_press <= window,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::compound_source_test_util::{abs, button, press};
+
+    #[test]
+    fn shift_and_pad() {
+        // Given
+        let mut chord =
+            CompoundMappingSource::Chord(ChordSource::new(vec![button(0), button(1)], None));
+        // When
+        // Then
+        assert!(matches!(
+            press(&mut chord, 0, true),
+            Some(ControlOutcome::Consumed)
+        ));
+        assert!(matches!(
+            press(&mut chord, 1, true),
+            Some(ControlOutcome::Matched(v)) if v == abs(1.0)
+        ));
+        assert!(matches!(
+            press(&mut chord, 1, false),
+            Some(ControlOutcome::Matched(v)) if v == abs(0.0)
+        ));
+        assert!(matches!(
+            press(&mut chord, 1, true),
+            Some(ControlOutcome::Matched(v)) if v == abs(1.0)
+        ));
+        assert!(matches!(
+            press(&mut chord, 0, false),
+            Some(ControlOutcome::Matched(v)) if v == abs(0.0)
+        ));
+        assert!(press(&mut chord, 2, true).is_none());
+    }
+
+    #[test]
+    fn within_window() {
+        // Given
+        let mut chord = CompoundMappingSource::Chord(ChordSource::new(
+            vec![button(0), button(1)],
+            Some(Duration::from_secs(3600)),
+        ));
+        // When
+        press(&mut chord, 0, true);
+        let outcome = press(&mut chord, 1, true);
+        // Then
+        assert!(matches!(outcome, Some(ControlOutcome::Matched(v)) if v == abs(1.0)));
+    }
+}
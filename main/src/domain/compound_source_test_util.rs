@@ -0,0 +1,30 @@
+//! Fixtures for testing compound sources (chords and sequences) with virtual buttons.
+
+use crate::domain::{
+    CompoundMappingSource, ControlEventTimestamp, ControlOutcome, VirtualControlElement,
+    VirtualControlElementId, VirtualSource, VirtualSourceValue,
+};
+use helgoboss_learn::{ControlValue, UnitValue};
+
+pub fn button(index: u32) -> CompoundMappingSource {
+    CompoundMappingSource::Virtual(VirtualSource::new(button_element(index)))
+}
+
+/// Lets the given compound source react to pressing (`on` = `true`) or releasing the virtual
+/// button with the given index.
+pub fn press(
+    source: &mut CompoundMappingSource,
+    index: u32,
+    on: bool,
+) -> Option<ControlOutcome<ControlValue>> {
+    let value = VirtualSourceValue::new(button_element(index), abs(if on { 1.0 } else { 0.0 }));
+    source.control_virtual(ControlEventTimestamp::now(), &value)
+}
+
+pub fn abs(v: f64) -> ControlValue {
+    ControlValue::AbsoluteContinuous(UnitValue::new(v))
+}
+
+fn button_element(index: u32) -> VirtualControlElement {
+    VirtualControlElement::Button(VirtualControlElementId::Indexed(index))
+}
@@ -2,7 +2,7 @@ use crate::base::{Global, NamedChannelSender, SenderToNormalThread};
 use crate::domain::{
     BackboneState, CompoundMappingSource, ControlEvent, ControlEventTimestamp,
     DeviceChangeDetector, DeviceControlInput, DeviceFeedbackOutput, DomainEventHandler,
-    EelTransformation, FeedbackOutput, FeedbackRealTimeTask, InstanceId, LifecycleMidiData,
+    FeedbackOutput, FeedbackRealTimeTask, GlueTransformation, InstanceId, LifecycleMidiData,
     MainProcessor, MidiCaptureSender, MidiDeviceChangePayload, NormalRealTimeTask, OscDeviceId,
     OscInputDevice, OscScanResult, QualifiedClipMatrixEvent, RealTimeCompoundMappingTarget,
     RealTimeMapping, RealTimeMappingUpdate, RealTimeTargetUpdate, ReaperMessage, ReaperTarget,
@@ -11,8 +11,8 @@ use crate::domain::{
 use crossbeam_channel::Receiver;
 use helgoboss_learn::{ModeGarbage, RawMidiEvents};
 use reaper_high::{
-    ChangeDetectionMiddleware, ControlSurfaceEvent, ControlSurfaceMiddleware, FutureMiddleware, Fx,
-    FxParameter, MainTaskMiddleware, Project, Reaper,
+    ChangeDetectionMiddleware, ChangeEvent, ControlSurfaceEvent, ControlSurfaceMiddleware,
+    FutureMiddleware, Fx, FxParameter, MainTaskMiddleware, Project, Reaper,
 };
 use reaper_rx::ControlSurfaceRxMiddleware;
 use rosc::{OscMessage, OscPacket};
@@ -26,6 +26,7 @@ use reaper_medium::{
 use rxrust::prelude::*;
 use slog::debug;
 use smallvec::SmallVec;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 type OscCaptureSender = async_channel::Sender<OscScanResult>;
@@ -55,6 +56,13 @@ pub struct RealearnControlSurfaceMiddleware<EH: DomainEventHandler> {
     future_middleware: FutureMiddleware,
     counter: u64,
     full_beats: HashMap<ReaProject, u32>,
+    /// Index of the marker at the play position, per project.
+    current_markers: HashMap<ReaProject, Option<u32>>,
+    /// Messages for REAPER sources derived from change events.
+    ///
+    /// Change events are detected while the main processors are borrowed immutably, so we
+    /// queue them and let the main processors process them in the next main loop cycle.
+    pending_reaper_messages: RefCell<Vec<ReaperMessage>>,
     metrics_enabled: bool,
     state: State,
     osc_input_devices: Vec<OscInputDevice>,
@@ -69,7 +77,7 @@ pub enum Garbage {
     RealTimeProcessor(SharedRealTimeProcessor),
     LifecycleMidiData(LifecycleMidiData),
     ResolvedTarget(Option<RealTimeCompoundMappingTarget>),
-    Mode(ModeGarbage<EelTransformation>),
+    Mode(ModeGarbage<GlueTransformation>),
     MappingSource(CompoundMappingSource),
     RealTimeMappings(Vec<RealTimeMapping>),
     BoxedRealTimeMapping(Box<Option<RealTimeMapping>>),
@@ -221,6 +229,8 @@ impl<EH: DomainEventHandler> RealearnControlSurfaceMiddleware<EH> {
             ),
             counter: 0,
             full_beats: Default::default(),
+            current_markers: Default::default(),
+            pending_reaper_messages: Default::default(),
             metrics_enabled: control_surface_metrics_enabled,
             state: State::Normal,
             osc_input_devices: vec![],
@@ -270,15 +280,11 @@ impl<EH: DomainEventHandler> RealearnControlSurfaceMiddleware<EH> {
         self.process_instance_orchestration_events();
         self.emit_beats_as_feedback_events();
         self.emit_device_changes_as_reaper_source_messages(timestamp);
+        self.emit_project_changes_as_reaper_source_messages(timestamp);
         self.process_incoming_osc_messages(timestamp);
         self.poll_clip_matrixes();
         self.process_incoming_clip_matrix_events();
         self.run_main_processors(timestamp);
-        // // TODO-high-grpc Just an experiment
-        // if let Some(t) = Reaper::get().current_project().first_track() {
-        //     let vol = t.volume();
-        //     let _ = BackboneState::server_event_sender().send(vol.soft_normalized_value());
-        // }
         #[cfg(feature = "realearn-metrics")]
         if self.metrics_enabled {
             self.process_metrics();
@@ -557,6 +563,55 @@ impl<EH: DomainEventHandler> RealearnControlSurfaceMiddleware<EH> {
         }
     }
 
+    fn emit_project_changes_as_reaper_source_messages(&mut self, timestamp: ControlEventTimestamp) {
+        let mut msgs = coalesce_track_selection_changes(self.pending_reaper_messages.take());
+        let marker_detection_is_needed = self
+            .main_processors
+            .borrow()
+            .iter()
+            .any(|p| p.wants_passed_markers());
+        if marker_detection_is_needed {
+            // Forget about closed projects
+            self.current_markers
+                .retain(|p, _| Reaper::get().projects().any(|open| open.raw() == *p));
+            for project in Reaper::get().projects() {
+                if let Some(marker_index) = self.detect_passed_marker(project) {
+                    msgs.push(ReaperMessage::MarkerPassed {
+                        project,
+                        marker_index,
+                    });
+                }
+            }
+        } else if !self.current_markers.is_empty() {
+            self.current_markers.clear();
+        }
+        if msgs.is_empty() {
+            return;
+        }
+        for p in &mut *self.main_processors.borrow_mut() {
+            for msg in &msgs {
+                let evt = ControlEvent::new(msg, timestamp);
+                p.process_reaper_message(evt);
+            }
+        }
+    }
+
+    /// Returns the index of the marker which has just been passed by the play cursor.
+    fn detect_passed_marker(&mut self, project: Project) -> Option<u32> {
+        if !project.play_state().is_playing {
+            self.current_markers.remove(&project.raw());
+            return None;
+        }
+        let pos = project.play_position_next_audio_block();
+        let new_marker_index = project.current_bookmark_at(pos).marker_index;
+        let marker_index = self.current_markers.entry(project.raw()).or_insert(None);
+        let previous_marker_index = std::mem::replace(marker_index, new_marker_index);
+        if new_marker_index == previous_marker_index {
+            return None;
+        }
+        new_marker_index
+    }
+
     fn log_debug_info(&self) {
         // Summary
         let msg = format!(
@@ -634,6 +689,9 @@ impl<EH: DomainEventHandler> RealearnControlSurfaceMiddleware<EH> {
                     }
                     // The rest is only for upper layers (e.g. UI), not for processing.
                     self.rx_middleware.handle_change(e.clone());
+                    if let Some(msg) = reaper_message_from_change_event(&e) {
+                        self.pending_reaper_messages.borrow_mut().push(msg);
+                    }
                     if let Some(target) = ReaperTarget::touched_from_change_event(e) {
                         // TODO-medium Now we have the necessary framework (AdditionalFeedbackEvent)
                         //  to also support action, FX snapshot and ReaLearn monitoring FX parameter
@@ -782,6 +840,54 @@ impl GarbageBin {
     }
 }
 
+/// Translates change events which are interesting for REAPER sources.
+fn reaper_message_from_change_event(evt: &ChangeEvent) -> Option<ReaperMessage> {
+    let msg = match evt {
+        ChangeEvent::TrackSelectedChanged(e) => ReaperMessage::TrackSelectionChanged {
+            project: e.track.project(),
+            is_selected: e.new_value,
+        },
+        ChangeEvent::PlayStateChanged(e) => ReaperMessage::PlayStateChanged {
+            project: e.project,
+            new_value: e.new_value,
+        },
+        ChangeEvent::ProjectSwitched(_) => ReaperMessage::ProjectSwitched,
+        ChangeEvent::FxFocused(e) => ReaperMessage::FxFocusChanged {
+            is_focused: e.fx.is_some(),
+        },
+        _ => return None,
+    };
+    Some(msg)
+}
+
+/// Selecting a track usually unselects other tracks at the same time, in no particular order.
+/// This merges all track selection changes of one project into one message which tells whether
+/// at least one track got selected.
+fn coalesce_track_selection_changes(msgs: Vec<ReaperMessage>) -> Vec<ReaperMessage> {
+    let mut coalesced_msgs: Vec<ReaperMessage> = Vec::with_capacity(msgs.len());
+    for msg in msgs {
+        if let ReaperMessage::TrackSelectionChanged {
+            project,
+            is_selected,
+        } = msg
+        {
+            let existing_msg = coalesced_msgs.iter_mut().find_map(|m| match m {
+                ReaperMessage::TrackSelectionChanged {
+                    project: p,
+                    is_selected: s,
+                } if *p == project => Some(s),
+                _ => None,
+            });
+            if let Some(existing_is_selected) = existing_msg {
+                *existing_is_selected |= is_selected;
+                continue;
+            }
+        }
+        coalesced_msgs.push(msg);
+    }
+    coalesced_msgs
+}
+
 fn reset_midi_devices(
     in_devs: impl Iterator<Item = MidiInputDeviceId>,
     out_devs: impl Iterator<Item = MidiOutputDeviceId>,
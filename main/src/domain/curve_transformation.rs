@@ -0,0 +1,282 @@
+use crate::domain::AdditionalTransformationInput;
+use helgoboss_learn::Transformation;
+
+/// Number of bisection steps when evaluating the inverse of a segment. Enough for f64 precision.
+const INVERSE_ITERATIONS: u32 = 50;
+
+/// Curvatures beyond this are visually indistinguishable from a step and would make `exp_m1`
+/// overflow at some point.
+const MAX_CURVATURE: f64 = 50.0;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CurvePoint {
+    pub x: f64,
+    pub y: f64,
+    /// Shape of the segment leading from this point to the next one.
+    pub segment: CurveSegment,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CurveSegment {
+    Linear,
+    /// Positive curvature starts slowly and ends steeply, negative curvature the other way around.
+    Exponential {
+        curvature: f64,
+    },
+    SCurve,
+}
+
+impl CurveSegment {
+    /// Returns a segment which is safe to evaluate, e.g. with a curvature in a sane range.
+    pub fn sanitized(self) -> Self {
+        match self {
+            CurveSegment::Exponential { curvature } if curvature.is_nan() => CurveSegment::Linear,
+            CurveSegment::Exponential { curvature } => CurveSegment::Exponential {
+                curvature: curvature.clamp(-MAX_CURVATURE, MAX_CURVATURE),
+            },
+            s => s,
+        }
+    }
+
+    /// Maps a position within the segment (0.0 to 1.0) to the progress of the value (0.0 to 1.0).
+    pub fn shape(&self, t: f64) -> f64 {
+        match *self {
+            CurveSegment::Linear => t,
+            CurveSegment::Exponential { curvature } => {
+                if curvature.abs() < f64::EPSILON {
+                    t
+                } else {
+                    (curvature * t).exp_m1() / curvature.exp_m1()
+                }
+            }
+            CurveSegment::SCurve => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Represents a value transformation along a curve defined by breakpoints.
+///
+/// Between two points, the value follows the shape of the segment. Outside of the points, the
+/// value of the nearest point is used.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CurveTransformation {
+    /// Sorted by x.
+    points: Vec<CurvePoint>,
+    is_inverse: bool,
+}
+
+impl CurveTransformation {
+    pub fn new(mut points: Vec<CurvePoint>) -> Result<Self, &'static str> {
+        if points.is_empty() {
+            return Err("curve needs at least one point");
+        }
+        if points.iter().any(|p| !p.x.is_finite() || !p.y.is_finite()) {
+            return Err("curve points must be finite");
+        }
+        for p in &mut points {
+            p.x = p.x.clamp(0.0, 1.0);
+            p.y = p.y.clamp(0.0, 1.0);
+            p.segment = p.segment.sanitized();
+        }
+        points.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let t = Self {
+            points,
+            is_inverse: false,
+        };
+        Ok(t)
+    }
+
+    /// Returns the inverse curve (e.g. for feedback) if the curve is monotonic.
+    pub fn inverse(&self) -> Option<Self> {
+        let ys = self.points.windows(2).map(|w| w[1].y - w[0].y);
+        let is_monotonic = ys.clone().all(|d| d >= 0.0) || ys.clone().all(|d| d <= 0.0);
+        if !is_monotonic {
+            return None;
+        }
+        let inverse = Self {
+            points: self.points.clone(),
+            is_inverse: !self.is_inverse,
+        };
+        Some(inverse)
+    }
+
+    pub fn evaluate(&self, input: f64) -> f64 {
+        if self.is_inverse {
+            self.evaluate_inverse(input)
+        } else {
+            self.evaluate_forward(input)
+        }
+    }
+
+    fn evaluate_forward(&self, x: f64) -> f64 {
+        let first = self.points[0];
+        if x <= first.x {
+            return first.y;
+        }
+        for w in self.points.windows(2) {
+            let (a, b) = (w[0], w[1]);
+            if x <= b.x {
+                return interpolate(a, b, x);
+            }
+        }
+        self.points[self.points.len() - 1].y
+    }
+
+    fn evaluate_inverse(&self, y: f64) -> f64 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        let is_ascending = last.y >= first.y;
+        let (low, high) = if is_ascending {
+            (first, last)
+        } else {
+            (last, first)
+        };
+        if y <= low.y {
+            return low.x;
+        }
+        if y >= high.y {
+            return high.x;
+        }
+        for w in self.points.windows(2) {
+            let (a, b) = (w[0], w[1]);
+            let contains_y = if is_ascending {
+                a.y <= y && y <= b.y
+            } else {
+                b.y <= y && y <= a.y
+            };
+            if !contains_y {
+                continue;
+            }
+            // Bisection works for all segment shapes because they are monotonic.
+            let (mut lo, mut hi) = (a.x, b.x);
+            for _ in 0..INVERSE_ITERATIONS {
+                let mid = (lo + hi) / 2.0;
+                let mid_y = interpolate(a, b, mid);
+                if (mid_y < y) == is_ascending {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            return (lo + hi) / 2.0;
+        }
+        last.x
+    }
+}
+
+fn interpolate(a: CurvePoint, b: CurvePoint, x: f64) -> f64 {
+    let width = b.x - a.x;
+    if width <= 0.0 {
+        return b.y;
+    }
+    let t = (x - a.x) / width;
+    a.y + (b.y - a.y) * a.segment.shape(t)
+}
+
+impl Transformation for CurveTransformation {
+    type AdditionalInput = AdditionalTransformationInput;
+
+    fn transform(
+        &self,
+        input_value: f64,
+        _output_value: f64,
+        _additional_input: AdditionalTransformationInput,
+    ) -> Result<f64, &'static str> {
+        Ok(self.evaluate(input_value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn linear() {
+        // Given
+        let curve = CurveTransformation::new(vec![
+            point(0.0, 0.0, CurveSegment::Linear),
+            point(0.5, 0.8, CurveSegment::Linear),
+            point(1.0, 1.0, CurveSegment::Linear),
+        ])
+        .unwrap();
+        // When
+        // Then
+        assert_abs_diff_eq!(curve.evaluate(0.25), 0.4, epsilon = 1e-6);
+        assert_abs_diff_eq!(curve.evaluate(0.75), 0.9, epsilon = 1e-6);
+        assert_abs_diff_eq!(curve.evaluate(1.0), 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn shapes() {
+        // Given
+        let exponential = CurveTransformation::new(vec![
+            point(0.0, 0.0, CurveSegment::Exponential { curvature: 3.0 }),
+            point(1.0, 1.0, CurveSegment::Linear),
+        ])
+        .unwrap();
+        let s_curve = CurveTransformation::new(vec![
+            point(0.0, 0.0, CurveSegment::SCurve),
+            point(1.0, 1.0, CurveSegment::Linear),
+        ])
+        .unwrap();
+        // When
+        // Then
+        assert!(exponential.evaluate(0.5) < 0.5);
+        assert_abs_diff_eq!(s_curve.evaluate(0.5), 0.5, epsilon = 1e-6);
+        assert!(s_curve.evaluate(0.25) < 0.25);
+        assert!(s_curve.evaluate(0.75) > 0.75);
+    }
+
+    #[test]
+    fn extreme_curvature() {
+        // Given
+        let curve = CurveTransformation::new(vec![
+            point(0.0, 0.0, CurveSegment::Exponential { curvature: 1e6 }),
+            point(0.5, 0.5, CurveSegment::Exponential { curvature: -1e6 }),
+            point(1.0, 1.0, CurveSegment::Linear),
+        ])
+        .unwrap();
+        // When
+        // Then
+        for x in [0.1, 0.25, 0.4, 0.6, 0.75, 0.9] {
+            let y = curve.evaluate(x);
+            assert!((0.0..=1.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn inverse() {
+        // Given
+        let curve = CurveTransformation::new(vec![
+            point(1.0, 0.2, CurveSegment::Linear),
+            point(0.0, 1.0, CurveSegment::Exponential { curvature: -2.0 }),
+        ])
+        .unwrap();
+        let inverse = curve.inverse().unwrap();
+        // When
+        // Then
+        for x in [0.0, 0.1, 0.5, 0.9, 1.0] {
+            assert_abs_diff_eq!(inverse.evaluate(curve.evaluate(x)), x, epsilon = 1e-6);
+        }
+        assert_abs_diff_eq!(inverse.evaluate(0.0), 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn not_invertible() {
+        // Given
+        let curve = CurveTransformation::new(vec![
+            point(0.0, 0.0, CurveSegment::Linear),
+            point(0.5, 1.0, CurveSegment::Linear),
+            point(1.0, 0.0, CurveSegment::Linear),
+        ])
+        .unwrap();
+        // When
+        // Then
+        assert!(curve.inverse().is_none());
+    }
+
+    fn point(x: f64, y: f64, segment: CurveSegment) -> CurvePoint {
+        CurvePoint { x, y, segment }
+    }
+}
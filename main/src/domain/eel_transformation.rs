@@ -1,13 +1,9 @@
 use crate::base::eel;
+use crate::domain::AdditionalTransformationInput;
 use helgoboss_learn::Transformation;
 
 use std::sync::Arc;
 
-#[derive(Default)]
-pub struct AdditionalEelTransformationInput {
-    pub y_last: f64,
-}
-
 #[derive(Debug)]
 struct EelUnit {
     // Declared above VM in order to be dropped before VM is dropped.
@@ -62,13 +58,13 @@ impl EelTransformation {
 }
 
 impl Transformation for EelTransformation {
-    type AdditionalInput = AdditionalEelTransformationInput;
+    type AdditionalInput = AdditionalTransformationInput;
 
     fn transform(
         &self,
         input_value: f64,
         output_value: f64,
-        additional_input: AdditionalEelTransformationInput,
+        additional_input: AdditionalTransformationInput,
     ) -> Result<f64, &'static str> {
         let result = unsafe {
             use OutputVariable::*;
@@ -0,0 +1,130 @@
+use helgoboss_learn::{Interval, RgbColor, UnitValue};
+
+/// Derives the feedback color from the numeric feedback value.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FeedbackColorMap {
+    /// Sorted by position.
+    Gradient(Vec<ColorStop>),
+    Ranges(Vec<ColorRange>),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ColorStop {
+    pub position: f64,
+    pub color: RgbColor,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ColorRange {
+    pub interval: Interval<UnitValue>,
+    pub color: RgbColor,
+}
+
+impl FeedbackColorMap {
+    pub fn gradient(mut stops: Vec<ColorStop>) -> Result<Self, &'static str> {
+        if stops.is_empty() {
+            return Err("gradient needs at least one stop");
+        }
+        if stops.iter().any(|s| !s.position.is_finite()) {
+            return Err("gradient stop positions must be finite");
+        }
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Ok(Self::Gradient(stops))
+    }
+
+    pub fn ranges(ranges: Vec<ColorRange>) -> Self {
+        Self::Ranges(ranges)
+    }
+
+    /// Returns the color for the given feedback value.
+    ///
+    /// Returns `None` if no range contains the value.
+    pub fn color_at(&self, value: UnitValue) -> Option<RgbColor> {
+        match self {
+            FeedbackColorMap::Gradient(stops) => Some(gradient_color_at(stops, value.get())),
+            FeedbackColorMap::Ranges(ranges) => ranges
+                .iter()
+                .find(|r| r.interval.min_val() <= value && value <= r.interval.max_val())
+                .map(|r| r.color),
+        }
+    }
+}
+
+fn gradient_color_at(stops: &[ColorStop], value: f64) -> RgbColor {
+    let first = stops.first().expect("gradient without stops");
+    let last = stops.last().expect("gradient without stops");
+    if value <= first.position {
+        return first.color;
+    }
+    if value >= last.position {
+        return last.color;
+    }
+    let i = stops.partition_point(|s| s.position <= value);
+    let (a, b) = (stops[i - 1], stops[i]);
+    let t = (value - a.position) / (b.position - a.position);
+    let blend = |x: u8, y: u8| (x as f64 + (y as f64 - x as f64) * t).round() as u8;
+    RgbColor::new(
+        blend(a.color.r(), b.color.r()),
+        blend(a.color.g(), b.color.g()),
+        blend(a.color.b(), b.color.b()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient() {
+        // Given
+        let green = RgbColor::new(0, 255, 0);
+        let yellow = RgbColor::new(255, 255, 0);
+        let red = RgbColor::new(255, 0, 0);
+        let map = FeedbackColorMap::gradient(vec![
+            ColorStop {
+                position: 1.0,
+                color: red,
+            },
+            ColorStop {
+                position: 0.0,
+                color: green,
+            },
+            ColorStop {
+                position: 0.5,
+                color: yellow,
+            },
+        ])
+        .unwrap();
+        // When
+        // Then
+        assert_eq!(map.color_at(UnitValue::MIN), Some(green));
+        assert_eq!(
+            map.color_at(UnitValue::new(0.25)),
+            Some(RgbColor::new(128, 255, 0))
+        );
+        assert_eq!(map.color_at(UnitValue::new(0.5)), Some(yellow));
+        assert_eq!(map.color_at(UnitValue::MAX), Some(red));
+    }
+
+    #[test]
+    fn ranges() {
+        // Given
+        let green = RgbColor::new(0, 255, 0);
+        let red = RgbColor::new(255, 0, 0);
+        let map = FeedbackColorMap::ranges(vec![
+            ColorRange {
+                interval: Interval::new(UnitValue::new(0.0), UnitValue::new(0.7)),
+                color: green,
+            },
+            ColorRange {
+                interval: Interval::new(UnitValue::new(0.9), UnitValue::new(1.0)),
+                color: red,
+            },
+        ]);
+        // When
+        // Then
+        assert_eq!(map.color_at(UnitValue::new(0.3)), Some(green));
+        assert_eq!(map.color_at(UnitValue::new(0.8)), None);
+        assert_eq!(map.color_at(UnitValue::MAX), Some(red));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::domain::CurveSegment;
+use std::time::{Duration, Instant};
+
+/// Makes the target value move smoothly to the desired value instead of jumping to it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GlideSettings {
+    pub time: GlideTime,
+    /// Shape of the movement.
+    pub shape: CurveSegment,
+}
+
+/// Duration of a glide, either absolute or relative to the project tempo.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GlideTime {
+    Millis(u32),
+    /// The glide takes the given fraction of a whole note, e.g. 1/4 = one beat in 4/4.
+    TempoSynced {
+        numerator: u32,
+        denominator: u32,
+    },
+}
+
+impl GlideTime {
+    /// Returns the absolute duration. The tempo is only queried if the time is tempo-synced.
+    pub fn duration(&self, get_tempo_bpm: impl FnOnce() -> f64) -> Duration {
+        match *self {
+            GlideTime::Millis(millis) => Duration::from_millis(millis as u64),
+            GlideTime::TempoSynced {
+                numerator,
+                denominator,
+            } => {
+                let bpm = get_tempo_bpm();
+                if denominator == 0 || bpm <= 0.0 {
+                    return Duration::ZERO;
+                }
+                let quarter_notes = 4.0 * numerator as f64 / denominator as f64;
+                Duration::from_secs_f64(quarter_notes * 60.0 / bpm)
+            }
+        }
+    }
+}
+
+/// A glide which is in progress.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Glide {
+    from: f64,
+    to: f64,
+    start: Instant,
+    duration: Duration,
+    shape: CurveSegment,
+}
+
+impl Glide {
+    pub fn new(
+        from: f64,
+        to: f64,
+        start: Instant,
+        duration: Duration,
+        shape: CurveSegment,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            start,
+            duration,
+            shape,
+        }
+    }
+
+    /// Returns the value at the given point in time and whether the destination has been reached.
+    pub fn value_at(&self, now: Instant) -> (f64, bool) {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return (self.to, true);
+        }
+        let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        let progress = self.shape.shape(t);
+        (self.from + (self.to - self.from) * progress, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn linear_glide() {
+        // Given
+        let start = Instant::now();
+        let glide = Glide::new(
+            0.2,
+            0.6,
+            start,
+            Duration::from_millis(100),
+            CurveSegment::Linear,
+        );
+        // When
+        let (half, half_done) = glide.value_at(start + Duration::from_millis(50));
+        let (end, end_done) = glide.value_at(start + Duration::from_millis(150));
+        // Then
+        assert_abs_diff_eq!(half, 0.4, epsilon = 1e-6);
+        assert!(!half_done);
+        assert_abs_diff_eq!(end, 0.6, epsilon = 1e-6);
+        assert!(end_done);
+    }
+
+    #[test]
+    fn tempo_synced_time() {
+        // Given
+        let time = GlideTime::TempoSynced {
+            numerator: 1,
+            denominator: 4,
+        };
+        // When
+        let duration = time.duration(|| 120.0);
+        // Then
+        assert_eq!(duration, Duration::from_millis(500));
+    }
+}
@@ -1,24 +1,81 @@
 use crate::domain::ControlOutcome;
+use derive_more::Display;
+use enum_iterator::IntoEnumIterator;
 use enumflags2::BitFlags;
-use helgoboss_learn::{ControlValue, UnitValue};
+use helgoboss_learn::{ControlValue, DiscreteIncrement, SourceCharacter, UnitValue};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use reaper_high::{AcceleratorKey, Reaper};
 use reaper_medium::{
     virt_keys, Accel, AccelMsgKind, AcceleratorBehavior, AcceleratorKeyCode, ReaperString, VirtKey,
 };
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 
+/// In accelerate mode, the increment grows by one with each of this number of key repeats.
+const KEY_REPEATS_PER_ACCELERATION_STEP: u32 = 4;
+const MAX_ACCELERATED_INCREMENT: u32 = 10;
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct KeySource {
     currently_pressed: bool,
     stroke: Keystroke,
+    mode: KeySourceMode,
+    /// Number of OS-triggered key repeats since the key has been pressed.
+    repeat_count: u32,
+    /// Digits typed so far if numeric entry is in progress.
+    numeric_entry: Option<NumericEntry>,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    IntoEnumIterator,
+    TryFromPrimitive,
+    IntoPrimitive,
+    Display,
+)]
+#[repr(usize)]
+pub enum KeySourceMode {
+    /// Emits 100% on press and 0% on release. Ignores OS-triggered key repeats.
+    #[serde(rename = "normal")]
+    #[display(fmt = "Normal")]
+    Normal,
+    /// Like normal but emits 100% again on each OS-triggered key repeat.
+    #[serde(rename = "repeat")]
+    #[display(fmt = "Repeat")]
+    Repeat,
+    /// Emits an increment on press and on each OS-triggered key repeat. The increment gets
+    /// larger the longer the key is held.
+    #[serde(rename = "accelerate")]
+    #[display(fmt = "Accelerate")]
+    Accelerate,
+    /// Pressing the key starts numeric entry. Typed digits followed by Enter emit the typed
+    /// percentage as absolute value. Escape cancels.
+    #[serde(rename = "numeric-entry")]
+    #[display(fmt = "Numeric entry")]
+    NumericEntry,
+}
+
+impl Default for KeySourceMode {
+    fn default() -> Self {
+        Self::Normal
+    }
 }
 
 impl KeySource {
-    pub fn new(stroke: Keystroke) -> Self {
+    pub fn new(stroke: Keystroke, mode: KeySourceMode) -> Self {
         Self {
             currently_pressed: false,
             stroke,
+            mode,
+            repeat_count: 0,
+            numeric_entry: None,
         }
     }
 
@@ -26,7 +83,33 @@ impl KeySource {
         self.stroke
     }
 
+    pub fn mode(&self) -> KeySourceMode {
+        self.mode
+    }
+
+    pub fn character(&self) -> SourceCharacter {
+        match self.mode {
+            KeySourceMode::Normal | KeySourceMode::Repeat => SourceCharacter::MomentaryButton,
+            KeySourceMode::Accelerate => SourceCharacter::Encoder1,
+            KeySourceMode::NumericEntry => SourceCharacter::RangeElement,
+        }
+    }
+
     pub fn control(&mut self, msg: KeyMessage) -> Option<ControlOutcome<ControlValue>> {
+        if let Some(entry) = &mut self.numeric_entry {
+            // Numeric entry in progress. All keys involved in numeric entry are ours.
+            use NumericEntryOutcome::*;
+            let outcome = match entry.process(msg) {
+                InProgress => return Some(ControlOutcome::Consumed),
+                Finished(Some(v)) => {
+                    Some(ControlOutcome::Matched(ControlValue::AbsoluteContinuous(v)))
+                }
+                Finished(None) | Cancelled => Some(ControlOutcome::Consumed),
+                Interrupted => None,
+            };
+            self.numeric_entry = None;
+            return outcome;
+        }
         if !msg.interaction_kind().is_press_or_release() && msg.stroke() == self.stroke {
             // On Windows, there's not just press and release but also something like "key is being
             // hold", which fires continuously. We neither want to react to it (because we have our
@@ -41,12 +124,43 @@ impl KeySource {
         }
         let is_press = msg.interaction_kind().is_press();
         if is_press && self.currently_pressed {
-            // We don't want OS-triggered repeated key firing (macOS). We have our own fire modes.
-            return Some(ControlOutcome::Consumed);
+            // OS-triggered repeated key firing (macOS). By default, we don't want it because we
+            // have our own fire modes.
+            if msg.stroke != self.stroke {
+                return None;
+            }
+            self.repeat_count += 1;
+            let outcome = match self.mode {
+                KeySourceMode::Repeat => {
+                    ControlOutcome::Matched(ControlValue::AbsoluteContinuous(UnitValue::MAX))
+                }
+                KeySourceMode::Accelerate => ControlOutcome::Matched(self.accelerated_increment()),
+                KeySourceMode::Normal | KeySourceMode::NumericEntry => ControlOutcome::Consumed,
+            };
+            return Some(outcome);
         }
         let control_value = self.get_control_value(msg)?;
         self.currently_pressed = is_press;
-        Some(ControlOutcome::Matched(control_value))
+        self.repeat_count = 0;
+        let outcome = match self.mode {
+            KeySourceMode::Normal | KeySourceMode::Repeat => ControlOutcome::Matched(control_value),
+            KeySourceMode::Accelerate => {
+                if is_press {
+                    ControlOutcome::Matched(self.accelerated_increment())
+                } else {
+                    ControlOutcome::Consumed
+                }
+            }
+            KeySourceMode::NumericEntry => {
+                if is_press {
+                    self.numeric_entry = Some(NumericEntry::default());
+                    // The release of the key will be swallowed by the entry.
+                    self.currently_pressed = false;
+                }
+                ControlOutcome::Consumed
+            }
+        };
+        Some(outcome)
     }
 
     /// Non-mutating! Used for checks.
@@ -54,7 +168,11 @@ impl KeySource {
         if !msg.interaction_kind().is_press_or_release() {
             return None;
         }
-        self.get_control_value(msg)
+        let control_value = self.get_control_value(msg)?;
+        match self.mode {
+            KeySourceMode::Accelerate => Some(ControlValue::Relative(DiscreteIncrement::new(1))),
+            _ => Some(control_value),
+        }
     }
 
     /// Assumes that relevance has been checked already.
@@ -69,6 +187,76 @@ impl KeySource {
         };
         Some(ControlValue::AbsoluteContinuous(value))
     }
+
+    fn accelerated_increment(&self) -> ControlValue {
+        let amount = (1 + self.repeat_count / KEY_REPEATS_PER_ACCELERATION_STEP)
+            .min(MAX_ACCELERATED_INCREMENT);
+        ControlValue::Relative(DiscreteIncrement::new(amount as i32))
+    }
+}
+
+enum NumericEntryOutcome {
+    InProgress,
+    /// Enter pressed. Contains the value unless nothing valid has been typed.
+    Finished(Option<UnitValue>),
+    /// Escape pressed.
+    Cancelled,
+    /// Unrelated key pressed.
+    Interrupted,
+}
+
+/// Collects typed digits until Enter is pressed.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+struct NumericEntry {
+    chars: [u8; NumericEntry::MAX_LEN],
+    len: usize,
+}
+
+impl NumericEntry {
+    const MAX_LEN: usize = 8;
+
+    fn process(&mut self, msg: KeyMessage) -> NumericEntryOutcome {
+        use NumericEntryOutcome::*;
+        if !msg.interaction_kind().is_press() {
+            // Releases, "Char" and "key is being held" interactions don't matter.
+            return InProgress;
+        }
+        use virt_keys::{BACK, ESCAPE, RETURN};
+        let key = AcceleratorKey::from_behavior_and_key_code(msg.stroke.modifiers, msg.stroke.key);
+        let ch = match key {
+            AcceleratorKey::VirtKey(RETURN) => return Finished(self.value()),
+            AcceleratorKey::VirtKey(ESCAPE) => return Cancelled,
+            AcceleratorKey::VirtKey(BACK) => {
+                self.len = self.len.saturating_sub(1);
+                return InProgress;
+            }
+            AcceleratorKey::VirtKey(k) => match k.get() as u32 {
+                d @ 0x30..=0x39 => d as u8,
+                // Numpad digits
+                d @ 0x60..=0x69 => b'0' + (d - 0x60) as u8,
+                // Numpad decimal separator, period and comma
+                0x6e | 0xbe | 0xbc => b'.',
+                _ => return Interrupted,
+            },
+            AcceleratorKey::Character(c) => match c {
+                0x30..=0x39 => c as u8,
+                0x2e | 0x2c => b'.',
+                _ => return Interrupted,
+            },
+        };
+        if self.len < Self::MAX_LEN {
+            self.chars[self.len] = ch;
+            self.len += 1;
+        }
+        InProgress
+    }
+
+    /// Interprets the typed digits as percentage.
+    fn value(&self) -> Option<UnitValue> {
+        let text = std::str::from_utf8(&self.chars[..self.len]).ok()?;
+        let percentage: f64 = text.parse().ok()?;
+        Some(UnitValue::new_clamped(percentage / 100.0))
+    }
 }
 
 impl Display for KeySource {
@@ -315,3 +503,147 @@ impl Display for Keystroke {
         f.write_str(label.as_ref())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_entry() {
+        // Given
+        let trigger = Keystroke::new(AcceleratorBehavior::VirtKey.into(), key(b'V'));
+        let mut source = KeySource::new(trigger, KeySourceMode::NumericEntry);
+        // When
+        let outcomes: Vec<_> = [
+            KeyMessage::new(AccelMsgKind::KeyDown, trigger),
+            KeyMessage::new(AccelMsgKind::KeyUp, trigger),
+            virt_key_down(b'7'),
+            virt_key_down(b'5'),
+            virt_key_down(virt_keys::RETURN.get() as u8),
+        ]
+        .into_iter()
+        .map(|msg| source.control(msg))
+        .collect();
+        // Then
+        assert!(outcomes[..4]
+            .iter()
+            .all(|o| matches!(o, Some(ControlOutcome::Consumed))));
+        assert!(matches!(
+            outcomes[4],
+            Some(ControlOutcome::Matched(ControlValue::AbsoluteContinuous(v))) if v == UnitValue::new(0.75)
+        ));
+        assert!(source.control(virt_key_down(b'1')).is_none());
+    }
+
+    #[test]
+    fn numeric_entry_interrupted() {
+        // Given
+        let trigger = Keystroke::new(AcceleratorBehavior::VirtKey.into(), key(b'V'));
+        let mut source = KeySource::new(trigger, KeySourceMode::NumericEntry);
+        // When
+        source.control(KeyMessage::new(AccelMsgKind::KeyDown, trigger));
+        source.control(virt_key_down(b'7'));
+        let outcome = source.control(virt_key_down(b'X'));
+        // Then
+        assert!(outcome.is_none());
+        assert!(source.control(virt_key_down(b'5')).is_none());
+    }
+
+    #[test]
+    fn repeat() {
+        // Given
+        let trigger = Keystroke::new(AcceleratorBehavior::VirtKey.into(), key(b'V'));
+        let mut normal_source = KeySource::new(trigger, KeySourceMode::Normal);
+        let mut repeat_source = KeySource::new(trigger, KeySourceMode::Repeat);
+        let press = KeyMessage::new(AccelMsgKind::KeyDown, trigger);
+        let release = KeyMessage::new(AccelMsgKind::KeyUp, trigger);
+        // When
+        let normal_outcomes: Vec<_> = [press, press, press, release]
+            .into_iter()
+            .map(|msg| normal_source.control(msg))
+            .collect();
+        let repeat_outcomes: Vec<_> = [press, press, press, release]
+            .into_iter()
+            .map(|msg| repeat_source.control(msg))
+            .collect();
+        // Then
+        assert_eq!(matched_value(&normal_outcomes[0]), Some(abs(1.0)));
+        assert!(is_consumed(&normal_outcomes[1]));
+        assert!(is_consumed(&normal_outcomes[2]));
+        assert_eq!(matched_value(&normal_outcomes[3]), Some(abs(0.0)));
+        assert_eq!(matched_value(&repeat_outcomes[0]), Some(abs(1.0)));
+        assert_eq!(matched_value(&repeat_outcomes[1]), Some(abs(1.0)));
+        assert_eq!(matched_value(&repeat_outcomes[2]), Some(abs(1.0)));
+        assert_eq!(matched_value(&repeat_outcomes[3]), Some(abs(0.0)));
+    }
+
+    #[test]
+    fn accelerate() {
+        // Given
+        let trigger = Keystroke::new(AcceleratorBehavior::VirtKey.into(), key(b'V'));
+        let mut source = KeySource::new(trigger, KeySourceMode::Accelerate);
+        let press = KeyMessage::new(AccelMsgKind::KeyDown, trigger);
+        let release = KeyMessage::new(AccelMsgKind::KeyUp, trigger);
+        // When
+        let increments: Vec<_> = (0..50)
+            .map(|_| matched_value(&source.control(press)))
+            .collect();
+        let release_outcome = source.control(release);
+        let increment_after_release = matched_value(&source.control(press));
+        // Then
+        assert_eq!(increments[0], Some(rel(1)));
+        assert_eq!(increments[3], Some(rel(1)));
+        assert_eq!(increments[4], Some(rel(2)));
+        assert_eq!(increments[8], Some(rel(3)));
+        assert_eq!(increments[36], Some(rel(10)));
+        assert_eq!(increments[49], Some(rel(10)));
+        assert!(is_consumed(&release_outcome));
+        assert_eq!(increment_after_release, Some(rel(1)));
+    }
+
+    #[test]
+    fn other_key_while_pressed() {
+        // Given
+        let trigger = Keystroke::new(AcceleratorBehavior::VirtKey.into(), key(b'V'));
+        let mut source = KeySource::new(trigger, KeySourceMode::Accelerate);
+        let press = KeyMessage::new(AccelMsgKind::KeyDown, trigger);
+        // When
+        source.control(press);
+        let other_key_outcomes: Vec<_> = (0..10)
+            .map(|_| source.control(virt_key_down(b'X')))
+            .collect();
+        let repeat_outcome = source.control(press);
+        // Then
+        assert!(other_key_outcomes.iter().all(|o| o.is_none()));
+        // Other keys don't count as repeats of our key.
+        assert_eq!(matched_value(&repeat_outcome), Some(rel(1)));
+    }
+
+    fn matched_value(outcome: &Option<ControlOutcome<ControlValue>>) -> Option<ControlValue> {
+        match outcome {
+            Some(ControlOutcome::Matched(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn is_consumed(outcome: &Option<ControlOutcome<ControlValue>>) -> bool {
+        matches!(outcome, Some(ControlOutcome::Consumed))
+    }
+
+    fn abs(v: f64) -> ControlValue {
+        ControlValue::AbsoluteContinuous(UnitValue::new(v))
+    }
+
+    fn rel(increment: i32) -> ControlValue {
+        ControlValue::Relative(DiscreteIncrement::new(increment))
+    }
+
+    fn key(code: u8) -> AcceleratorKeyCode {
+        AcceleratorKeyCode::new(code as u16)
+    }
+
+    fn virt_key_down(code: u8) -> KeyMessage {
+        let stroke = Keystroke::new(AcceleratorBehavior::VirtKey.into(), key(code));
+        KeyMessage::new(AccelMsgKind::KeyDown, stroke)
+    }
+}
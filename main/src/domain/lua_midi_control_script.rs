@@ -0,0 +1,138 @@
+use crate::domain::{SafeLua, LUA_EXECUTION_TIME_LIMIT};
+use helgoboss_learn::{ControlValue, DiscreteIncrement, UnitValue};
+use mlua::{Function, LuaSerdeExt, Table, ToLua, Value};
+use std::error::Error;
+
+/// The inverse of a [`crate::domain::LuaMidiSourceScript`]: Turns incoming MIDI messages into
+/// control values.
+#[derive(Clone, Debug)]
+pub struct LuaMidiControlScript<'lua> {
+    lua: &'lua SafeLua,
+    code: String,
+    function: Function<'lua>,
+    env: Table<'lua>,
+    msg_key: Value<'lua>,
+}
+
+unsafe impl<'a> Send for LuaMidiControlScript<'a> {}
+
+impl<'lua> LuaMidiControlScript<'lua> {
+    pub fn compile(lua: &'lua SafeLua, lua_script: &str) -> Result<Self, Box<dyn Error>> {
+        if lua_script.trim().is_empty() {
+            return Err("script empty".into());
+        }
+        let env = lua.create_fresh_environment(false)?;
+        let function = lua.compile_as_function("MIDI control script", lua_script, env.clone())?;
+        let script = Self {
+            lua,
+            code: lua_script.to_owned(),
+            env,
+            function,
+            msg_key: "msg".to_lua(lua.as_ref())?,
+        };
+        Ok(script)
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Executes the script with the given MIDI message bytes (short message or complete SysEx).
+    ///
+    /// Returns `None` if the script isn't interested in this message.
+    pub fn execute(&self, msg: &[u8]) -> Result<Option<ControlValue>, &'static str> {
+        let lua = self.lua.as_ref();
+        let msg_table = lua
+            .create_sequence_from(msg.iter().copied())
+            .map_err(|_| "couldn't create msg table")?;
+        self.env
+            .raw_set(self.msg_key.clone(), msg_table)
+            .map_err(|_| "couldn't set msg variable")?;
+        // The script is executed for each incoming MIDI message, so an endless loop must not
+        // freeze REAPER.
+        let value: Value = self
+            .lua
+            .execute_with_time_limit(LUA_EXECUTION_TIME_LIMIT, || self.function.call(()))
+            .map_err(|_| "failed to invoke Lua script")?;
+        let outcome: Option<LuaControlOutcome> = lua
+            .from_value(value)
+            .map_err(|_| "Lua script result has wrong type")?;
+        let control_value = match outcome {
+            None | Some(LuaControlOutcome::Relative(0)) => None,
+            Some(LuaControlOutcome::Absolute(v)) => {
+                Some(ControlValue::AbsoluteContinuous(UnitValue::new_clamped(v)))
+            }
+            Some(LuaControlOutcome::Relative(i)) => {
+                Some(ControlValue::Relative(DiscreteIncrement::new(i)))
+            }
+        };
+        Ok(control_value)
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LuaControlOutcome {
+    Absolute(f64),
+    Relative(i32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute() {
+        // Given
+        let text = "
+            if msg[1] ~= 0xb0 or msg[2] ~= 0x4b then
+                return nil
+            end
+            return { absolute = msg[3] / 127 }
+        ";
+        let lua = SafeLua::new().unwrap();
+        let script = LuaMidiControlScript::compile(&lua, text).unwrap();
+        // When
+        let matched = script.execute(&[0xb0, 0x4b, 127]).unwrap();
+        let unmatched = script.execute(&[0xb0, 0x4c, 127]).unwrap();
+        // Then
+        assert_eq!(
+            matched,
+            Some(ControlValue::AbsoluteContinuous(UnitValue::MAX))
+        );
+        assert_eq!(unmatched, None);
+    }
+
+    #[test]
+    fn relative_sysex() {
+        // Given
+        let text = "
+            if #msg ~= 4 then
+                return nil
+            end
+            return { relative = msg[3] - 64 }
+        ";
+        let lua = SafeLua::new().unwrap();
+        let script = LuaMidiControlScript::compile(&lua, text).unwrap();
+        // When
+        let decrement = script.execute(&[0xf0, 0x01, 62, 0xf7]).unwrap();
+        let zero = script.execute(&[0xf0, 0x01, 64, 0xf7]).unwrap();
+        // Then
+        assert_eq!(
+            decrement,
+            Some(ControlValue::Relative(DiscreteIncrement::new(-2)))
+        );
+        assert_eq!(zero, None);
+    }
+
+    #[test]
+    fn endless_loop_times_out() {
+        // Given
+        let lua = SafeLua::new().unwrap();
+        let script = LuaMidiControlScript::compile(&lua, "while true do end").unwrap();
+        // When
+        let result = script.execute(&[0xb0, 0x4b, 127]);
+        // Then
+        assert!(result.is_err());
+    }
+}
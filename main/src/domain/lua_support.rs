@@ -3,6 +3,9 @@ use std::error::Error;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// The execution time limit applied to user-provided Lua code.
+pub const LUA_EXECUTION_TIME_LIMIT: Duration = Duration::from_millis(200);
+
 #[derive(Debug)]
 pub struct SafeLua(Lua);
 
@@ -89,6 +92,26 @@ impl SafeLua {
         self,
         max_duration: Duration,
     ) -> Result<Self, Box<dyn Error>> {
+        self.set_execution_time_limit_hook(max_duration)?;
+        Ok(self)
+    }
+
+    /// Executes the given user code invocation with an execution time limit.
+    ///
+    /// In contrast to [`Self::start_execution_time_limit_countdown`], this doesn't consume the Lua
+    /// state and removes the hook afterwards, so it's suitable for long-living Lua states.
+    pub fn execute_with_time_limit<R>(
+        &self,
+        max_duration: Duration,
+        f: impl FnOnce() -> mlua::Result<R>,
+    ) -> Result<R, Box<dyn Error>> {
+        self.set_execution_time_limit_hook(max_duration)?;
+        let result = f();
+        self.0.remove_hook();
+        Ok(result?)
+    }
+
+    fn set_execution_time_limit_hook(&self, max_duration: Duration) -> mlua::Result<()> {
         let instant = Instant::now();
         self.0.set_hook(
             HookTriggers::every_nth_instruction(10),
@@ -101,8 +124,7 @@ impl SafeLua {
                     Ok(())
                 }
             },
-        )?;
-        Ok(self)
+        )
     }
 }
 
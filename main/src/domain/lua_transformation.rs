@@ -0,0 +1,169 @@
+use crate::domain::{
+    AdditionalTransformationInput, OutputVariable, SafeLua, LUA_EXECUTION_TIME_LIMIT,
+};
+use helgoboss_learn::Transformation;
+use mlua::{Function, Table, ToLua, Value};
+use std::error::Error;
+
+/// Represents a value transformation done via Lua.
+///
+/// In addition to `x`, `y` and `y_last`, the script has access to the normalized compartment
+/// parameter values (`params`) and to a `state` table which survives invocations. The script
+/// returns the desired output value. Returning `nil` leaves the output value untouched.
+///
+/// The Lua state belongs to the main thread, so this transformation must only be executed there.
+/// Mappings which use it for control are never controlled in real-time (see
+/// `GlueTransformation::is_main_thread_only`).
+#[derive(Clone, Debug)]
+pub struct LuaTransformation<'lua> {
+    lua: &'lua SafeLua,
+    function: Function<'lua>,
+    env: Table<'lua>,
+    params: Table<'lua>,
+    keys: LuaKeys<'lua>,
+    output_var: OutputVariable,
+}
+
+// Travels to the real-time thread as part of the mode but is never executed there.
+unsafe impl<'a> Send for LuaTransformation<'a> {}
+
+#[derive(Clone, Debug)]
+struct LuaKeys<'lua> {
+    x: Value<'lua>,
+    y: Value<'lua>,
+    y_last: Value<'lua>,
+}
+
+impl<'lua> LuaTransformation<'lua> {
+    /// Compiles the given script and creates an appropriate transformation.
+    pub fn compile(
+        lua: &'lua SafeLua,
+        lua_script: &str,
+        output_var: OutputVariable,
+    ) -> Result<Self, Box<dyn Error>> {
+        if lua_script.trim().is_empty() {
+            return Err("script empty".into());
+        }
+        let env = lua.create_fresh_environment(false)?;
+        let function = lua.compile_as_function("Transformation", lua_script, env.clone())?;
+        let params = lua.as_ref().create_table()?;
+        env.raw_set("params", params.clone())?;
+        env.raw_set("state", lua.as_ref().create_table()?)?;
+        let transformation = Self {
+            lua,
+            function,
+            env,
+            params,
+            keys: LuaKeys {
+                x: "x".to_lua(lua.as_ref())?,
+                y: "y".to_lua(lua.as_ref())?,
+                y_last: "y_last".to_lua(lua.as_ref())?,
+            },
+            output_var,
+        };
+        Ok(transformation)
+    }
+}
+
+impl<'lua> Transformation for LuaTransformation<'lua> {
+    type AdditionalInput = AdditionalTransformationInput;
+
+    fn transform(
+        &self,
+        input_value: f64,
+        output_value: f64,
+        additional_input: AdditionalTransformationInput,
+    ) -> Result<f64, &'static str> {
+        use OutputVariable::*;
+        let (x, y) = match self.output_var {
+            X => (output_value, input_value),
+            Y => (input_value, output_value),
+        };
+        let set_var = |key: &Value<'lua>, value: f64| {
+            self.env
+                .raw_set(key.clone(), value)
+                .map_err(|_| "couldn't set Lua transformation variable")
+        };
+        set_var(&self.keys.x, x)?;
+        set_var(&self.keys.y, y)?;
+        set_var(&self.keys.y_last, additional_input.y_last)?;
+        for (i, v) in additional_input.params.iter().enumerate() {
+            self.params
+                .raw_set(i + 1, *v)
+                .map_err(|_| "couldn't set Lua transformation parameter")?;
+        }
+        let value: Value = self
+            .lua
+            .execute_with_time_limit(LUA_EXECUTION_TIME_LIMIT, || self.function.call(()))
+            .map_err(|_| "failed to invoke Lua transformation")?;
+        match value {
+            Value::Nil => Ok(output_value),
+            Value::Integer(v) => Ok(v as f64),
+            Value::Number(v) => Ok(v),
+            _ => Err("Lua transformation result has wrong type"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn stateful_control() {
+        // Given
+        let text = "
+            state.count = (state.count or 0) + 1
+            return x * params[1] + state.count / 100
+        ";
+        let lua = SafeLua::new().unwrap();
+        let transformation = LuaTransformation::compile(&lua, text, OutputVariable::Y).unwrap();
+        let input = || {
+            let mut input = AdditionalTransformationInput::default();
+            input.params[0] = 0.5;
+            input
+        };
+        // When
+        let first = transformation.transform(0.8, 0.0, input()).unwrap();
+        let second = transformation.transform(0.8, 0.0, input()).unwrap();
+        // Then
+        assert_abs_diff_eq!(first, 0.41, epsilon = 1e-6);
+        assert_abs_diff_eq!(second, 0.42, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn nil_keeps_output_value() {
+        // Given
+        let text = "
+            if y > 0.5 then
+                return nil
+            end
+            return 1 - y
+        ";
+        let lua = SafeLua::new().unwrap();
+        let transformation = LuaTransformation::compile(&lua, text, OutputVariable::X).unwrap();
+        // When
+        let kept = transformation
+            .transform(0.7, 0.3, Default::default())
+            .unwrap();
+        let inverted = transformation
+            .transform(0.2, 0.3, Default::default())
+            .unwrap();
+        // Then
+        assert_abs_diff_eq!(kept, 0.3, epsilon = 1e-6);
+        assert_abs_diff_eq!(inverted, 0.8, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn endless_loop_times_out() {
+        // Given
+        let lua = SafeLua::new().unwrap();
+        let transformation =
+            LuaTransformation::compile(&lua, "while true do end", OutputVariable::Y).unwrap();
+        // When
+        let result = transformation.transform(0.5, 0.0, Default::default());
+        // Then
+        assert!(result.is_err());
+    }
+}
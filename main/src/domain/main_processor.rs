@@ -4,25 +4,25 @@ use crate::domain::{
     CompoundMappingSourceAddress, CompoundMappingTarget, ControlContext, ControlEvent,
     ControlEventTimestamp, ControlInput, ControlMode, ControlOutcome, DeviceFeedbackOutput,
     DomainEvent, DomainEventHandler, ExtendedProcessorContext, FeedbackAudioHookTask,
-    FeedbackDestinations, FeedbackOutput, FeedbackRealTimeTask, FeedbackResolution,
-    FeedbackSendBehavior, GroupId, HitInstructionContext, InstanceContainer,
+    FeedbackDestinations, FeedbackOrigin, FeedbackOutput, FeedbackRealTimeTask, FeedbackResolution,
+    FeedbackSendBehavior, GroupId, HitInstructionContext, IncomingMidiMessage, InstanceContainer,
     InstanceOrchestrationEvent, InstanceStateChanged, IoUpdatedEvent, KeyMessage,
     LimitedAsciiString, MainMapping, MainSourceMessage, MappingActivationEffect,
-    MappingControlResult, MappingId, MappingInfo, MessageCaptureEvent, MessageCaptureResult,
-    MidiControlInput, MidiDestination, MidiScanResult, NormalRealTimeTask, OrderedMappingIdSet,
-    OrderedMappingMap, OscDeviceId, OscFeedbackTask, PluginParamIndex, PluginParams,
-    ProcessorContext, QualifiedClipMatrixEvent, QualifiedMappingId, QualifiedSource, RawParamValue,
-    RealFeedbackValue, RealTimeMappingUpdate, RealTimeTargetUpdate,
+    MappingControlResult, MappingId, MappingInfo, MappingKey, MessageCaptureEvent,
+    MessageCaptureResult, MidiControlInput, MidiDestination, MidiScanResult, NormalRealTimeTask,
+    OrderedMappingIdSet, OrderedMappingMap, OscDeviceId, OscFeedbackTask, PluginParamIndex,
+    PluginParams, ProcessorContext, QualifiedClipMatrixEvent, QualifiedMappingId, QualifiedSource,
+    RawParamValue, RealFeedbackValue, RealTimeMappingUpdate, RealTimeTargetUpdate,
     RealearnMonitoringFxParameterValueChangedEvent, ReaperMessage, ReaperTarget,
-    SharedInstanceState, SourceFeedbackValue, SourceReleasedEvent, SpecificCompoundFeedbackValue,
-    TargetValueChangedEvent, UpdatedSingleMappingOnStateEvent, VirtualControlElement,
-    VirtualSourceValue,
+    SharedInstanceState, SourceFeedbackEvent, SourceFeedbackValue, SourceReleasedEvent,
+    SpecificCompoundFeedbackValue, TargetValueChangedEvent, UpdatedSingleMappingOnStateEvent,
+    VirtualControlElement, VirtualSourceValue,
 };
 use derive_more::Display;
 use enum_map::EnumMap;
 use helgoboss_learn::{
     AbsoluteValue, ControlValue, GroupInteraction, MidiSourceValue, MinIsMaxBehavior,
-    ModeControlOptions, RawMidiEvent, Target, BASE_EPSILON,
+    ModeControlOptions, RawMidiEvent, Target, UnitValue, BASE_EPSILON,
 };
 use std::borrow::Cow;
 use std::cell::RefCell;
@@ -34,6 +34,8 @@ use crate::domain::ui_util::{
     log_real_control_input, log_real_feedback_output, log_real_learn_input, log_target_output,
     log_virtual_control_input, log_virtual_feedback_output,
 };
+#[cfg(feature = "realearn-metrics")]
+use crate::domain::InstanceMetrics;
 use ascii::{AsciiString, ToAsciiChar};
 use helgoboss_midi::{ControlChange14BitMessage, ParameterNumberMessage, RawShortMessage};
 use playtime_clip_engine::main::ClipMatrixEvent;
@@ -43,6 +45,7 @@ use reaper_high::{ChangeEvent, Reaper};
 use reaper_medium::ReaperNormalizedFxParamValue;
 use rosc::{OscMessage, OscPacket, OscType};
 use slog::{debug, trace};
+use smallvec::SmallVec;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
@@ -65,6 +68,9 @@ pub struct MainProcessor<EH: DomainEventHandler> {
     collections: Collections,
     /// Contains IDs of those mappings who need to be polled as frequently as possible.
     poll_control_mappings: EnumMap<Compartment, OrderedMappingIdSet>,
+    /// Detecting passed markers is relatively expensive, so the control surface only does it if
+    /// at least one instance has a mapping with "Marker passed" source.
+    has_marker_passed_sources: bool,
 }
 
 #[derive(Debug)]
@@ -89,6 +95,8 @@ struct Basics<EH: DomainEventHandler> {
     // context. Rightfully so, because it's potentially reentrant!
     last_feedback_checksum_by_address:
         RefCell<HashMap<CompoundMappingSourceAddress, FeedbackChecksum>>,
+    #[cfg(feature = "realearn-metrics")]
+    metrics: InstanceMetrics,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -219,6 +227,7 @@ struct Channels {
     parameter_task_receiver: crossbeam_channel::Receiver<ParameterMainTask>,
     instance_feedback_event_receiver: crossbeam_channel::Receiver<InstanceStateChanged>,
     control_task_receiver: crossbeam_channel::Receiver<ControlMainTask>,
+    forwarded_midi_receiver: crossbeam_channel::Receiver<ControlEvent<ForwardedMidiMessage>>,
     normal_real_time_task_sender: SenderToRealTimeThread<NormalRealTimeTask>,
     feedback_real_time_task_sender: SenderToRealTimeThread<FeedbackRealTimeTask>,
     feedback_audio_hook_task_sender: SenderToRealTimeThread<FeedbackAudioHookTask>,
@@ -240,6 +249,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         >,
         parameter_task_receiver: crossbeam_channel::Receiver<ParameterMainTask>,
         control_task_receiver: crossbeam_channel::Receiver<ControlMainTask>,
+        forwarded_midi_receiver: crossbeam_channel::Receiver<ControlEvent<ForwardedMidiMessage>>,
         instance_feedback_event_receiver: crossbeam_channel::Receiver<InstanceStateChanged>,
         normal_real_time_task_sender: SenderToRealTimeThread<NormalRealTimeTask>,
         feedback_real_time_task_sender: SenderToRealTimeThread<FeedbackRealTimeTask>,
@@ -279,6 +289,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                     parameter_task_receiver,
                     instance_feedback_event_receiver,
                     control_task_receiver,
+                    forwarded_midi_receiver,
                     normal_real_time_task_sender,
                     feedback_real_time_task_sender,
                     feedback_audio_hook_task_sender,
@@ -288,6 +299,8 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                     integration_test_feedback_sender: None,
                 },
                 last_feedback_checksum_by_address: Default::default(),
+                #[cfg(feature = "realearn-metrics")]
+                metrics: InstanceMetrics::new(&instance_id),
             },
             collections: Collections {
                 mappings: Default::default(),
@@ -299,6 +312,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                 previous_target_values: Default::default(),
             },
             poll_control_mappings: Default::default(),
+            has_marker_passed_sources: false,
         }
     }
 
@@ -365,6 +379,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
             FeedbackReason::FinallySwitchOffSource,
             feedback_value,
             false,
+            None,
         );
     }
 
@@ -388,6 +403,17 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                 break;
             }
         }
+        // MIDI messages for MIDI control script sources
+        let mut count = 0;
+        while let Ok(event) = self.basics.channels.forwarded_midi_receiver.try_recv() {
+            if control_is_effectively_enabled {
+                self.control_midi_script_sources(event);
+            }
+            count += 1;
+            if count == CONTROL_TASK_BULK_SIZE {
+                break;
+            }
+        }
         self.poll_control(timestamp);
     }
 
@@ -443,6 +469,18 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         }
     }
 
+    /// MIDI control script sources can only be processed in the main thread, so the real-time
+    /// processor forwards the messages.
+    fn control_midi_script_sources(&mut self, event: ControlEvent<ForwardedMidiMessage>) {
+        if self.basics.control_mode != ControlMode::Controlling {
+            return;
+        }
+        let timestamp = event.timestamp();
+        let msg = event.into_payload().into_owned();
+        let evt = ControlEvent::new(MainSourceMessage::Midi(&msg), timestamp);
+        self.process_incoming_msg_for_controlling(evt);
+    }
+
     fn poll_control(&mut self, timestamp: ControlEventTimestamp) {
         for compartment in Compartment::enum_iter() {
             for id in self.poll_control_mappings[compartment].iter() {
@@ -465,6 +503,13 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                     } else {
                         Default::default()
                     };
+                    let mode_poll_result =
+                        if !mode_poll_result.successful && m.glide_is_in_progress() {
+                            // Glides are driven by polling as well.
+                            m.poll_glide(control_context, &self.basics.logger, processor_context)
+                        } else {
+                            mode_poll_result
+                        };
                     let (is_source_poll, mut final_poll_result) = if mode_poll_result.successful {
                         // Mode was polled successfully. This one has precedence.
                         // We poll even if control is effectively off because it might have been
@@ -478,7 +523,8 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                         (false, mode_poll_result)
                     } else if m.source().wants_to_be_polled() && m.control_is_effectively_on() {
                         // Mode was either not polled at all or without result, poll source.
-                        let res = if let Some(source_control_value) = m.poll_source() {
+                        let project = self.basics.context.project_or_current_project();
+                        let res = if let Some(source_control_value) = m.poll_source(project) {
                             let control_event = ControlEvent::new(source_control_value, timestamp);
                             control_mapping_stage_one(
                                 &self.basics,
@@ -594,7 +640,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
     pub fn run_essential(&mut self, timestamp: ControlEventTimestamp) {
         self.process_normal_tasks_from_real_time_processor();
         self.process_normal_tasks_from_session(timestamp);
-        self.process_parameter_tasks();
+        self.process_parameter_tasks(timestamp);
         self.process_feedback_tasks();
         self.process_instance_feedback_events();
         self.poll_for_feedback();
@@ -733,6 +779,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
             return;
         }
         for event in events {
+            self.notify_own_clip_matrix_changed(instance_id, event);
             self.process_clip_matrix_event_internal(event);
         }
     }
@@ -747,9 +794,19 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         {
             return;
         }
+        self.notify_own_clip_matrix_changed(event.instance_id, &event.event);
         self.process_clip_matrix_event_internal(&event.event)
     }
 
+    fn notify_own_clip_matrix_changed(&self, instance_id: InstanceId, event: &ClipMatrixEvent) {
+        if instance_id != self.basics.instance_id {
+            return;
+        }
+        self.basics
+            .event_handler
+            .handle_event(DomainEvent::ClipMatrixChanged(event));
+    }
+
     fn process_clip_matrix_event_internal(&self, event: &ClipMatrixEvent) {
         let is_position_change = matches!(
             event,
@@ -849,7 +906,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         }
     }
 
-    fn process_parameter_tasks(&mut self) {
+    fn process_parameter_tasks(&mut self, timestamp: ControlEventTimestamp) {
         let mut count = 0;
         while let Ok(task) = self.basics.channels.parameter_task_receiver.try_recv() {
             use ParameterMainTask::*;
@@ -858,7 +915,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                     self.update_all_params(params);
                 }
                 UpdateSingleParamValue { index, value } => {
-                    self.update_single_param_value(index, value)
+                    self.update_single_param_value(index, value, timestamp)
                 }
             }
             count += 1;
@@ -870,7 +927,12 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
 
     // https://github.com/rust-lang/rust-clippy/issues/6066
     #[allow(clippy::needless_collect)]
-    fn update_single_param_value(&mut self, index: PluginParamIndex, value: RawParamValue) {
+    fn update_single_param_value(
+        &mut self,
+        index: PluginParamIndex,
+        value: RawParamValue,
+        timestamp: ControlEventTimestamp,
+    ) {
         debug!(
             self.basics.logger,
             "Updating parameter {} to {}...", index, value
@@ -986,7 +1048,14 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
             target_updates,
             unused_sources,
             changed_mappings.into_iter(),
-        )
+        );
+        // 5. Mappings with compartment parameter sources: Control
+        let msg = ReaperMessage::CompartmentParameterValueChanged {
+            compartment,
+            index: compartment.to_compartment_param_index(index),
+            value: UnitValue::new_clamped(value as f64),
+        };
+        self.process_reaper_message(ControlEvent::new(&msg, timestamp));
     }
 
     fn update_all_params(&mut self, params: PluginParams) {
@@ -1058,6 +1127,9 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                 HitTarget { id, value } => {
                     self.hit_target(id, value);
                 }
+                EmulateVirtualControlElement(value) => {
+                    self.emulate_virtual_control_element(ControlEvent::new(value, timestamp));
+                }
                 // This is sent on events such as track list change, FX focus etc.
                 RefreshAllTargets => {
                     self.refresh_all_targets();
@@ -1296,6 +1368,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         // lower-floor instances.
         self.handle_feedback_after_having_updated_all_mappings(compartment, unused_sources);
         self.update_on_mappings();
+        self.update_has_marker_passed_sources();
     }
 
     fn process_normal_tasks_from_real_time_processor(&mut self) {
@@ -1540,6 +1613,10 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
             && self.basics.settings.control_input == ControlInput::Keyboard
     }
 
+    pub fn wants_passed_markers(&self) -> bool {
+        self.has_marker_passed_sources && self.wants_messages_in_general()
+    }
+
     pub fn wants_osc_from(&self, device_id: &OscDeviceId) -> bool {
         self.wants_messages_in_general()
             && self.basics.settings.control_input == ControlInput::Osc(*device_id)
@@ -1564,6 +1641,12 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         if self.basics.control_mode != ControlMode::Controlling {
             return;
         }
+        if let Some(project) = evt.payload().project() {
+            // Project-related messages are only interesting for instances in that project.
+            if project != self.basics.context.project_or_current_project() {
+                return;
+            }
+        }
         if self.basics.settings.real_input_logging_enabled {
             log_real_control_input(&self.basics.instance_id, evt);
         }
@@ -1723,7 +1806,7 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
                 .values_mut()
                 .filter(|m| m.control_is_effectively_on())
             {
-                let control_outcome = m.control_source(evt.payload());
+                let control_outcome = m.control_source(evt);
                 match_outcome.upgrade_from(control_outcome.into());
                 let control_value = match control_outcome {
                     Some(ControlOutcome::Matched(v)) => v,
@@ -2167,6 +2250,11 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         self.update_map_entries(compartment, *mapping);
         self.send_diff_feedback(diff_feedback);
         self.update_single_mapping_on_state(id);
+        self.update_has_marker_passed_sources();
+    }
+
+    fn update_has_marker_passed_sources(&mut self) {
+        self.has_marker_passed_sources = self.all_mappings().any(|m| m.source().is_marker_passed());
     }
 
     fn update_persistent_mapping_processing_state(
@@ -2318,6 +2406,36 @@ impl<EH: DomainEventHandler> MainProcessor<EH> {
         relevant_map.insert(m.id(), m);
     }
 
+    /// Controls the main mappings with virtual sources as if the given virtual control element had
+    /// been touched by a controller mapping.
+    fn emulate_virtual_control_element(&mut self, evt: ControlEvent<VirtualSourceValue>) {
+        if self.basics.settings.virtual_input_logging_enabled {
+            log_virtual_control_input(&self.basics.instance_id, evt.payload());
+        }
+        let mut control_results = self.basics.process_main_mappings_with_virtual_sources(
+            &mut self.collections.mappings[Compartment::Main],
+            evt,
+            ControlOptions::default(),
+            &self.collections.parameters,
+        );
+        self.basics.send_feedback(
+            &self.collections.mappings_with_virtual_targets,
+            FeedbackReason::Normal,
+            control_results
+                .iter_mut()
+                .filter_map(|r| r.control_result.feedback_value.take()),
+        );
+        for r in control_results {
+            control_mapping_stage_three(
+                &self.basics,
+                &mut self.collections,
+                r.compartment,
+                r.control_result,
+                GroupInteractionProcessing::On(r.group_interaction_input),
+            )
+        }
+    }
+
     fn hit_target(&mut self, id: QualifiedMappingId, value: AbsoluteValue) {
         let control_result = if let Some(m) =
             self.collections.mappings[id.compartment].get_mut(&id.id)
@@ -2395,6 +2513,9 @@ pub enum NormalMainTask {
         id: QualifiedMappingId,
         value: AbsoluteValue,
     },
+    /// Instructs the main processor to control all main mappings which use the given virtual
+    /// control element as source (as if a controller mapping had emitted the value).
+    EmulateVirtualControlElement(VirtualSourceValue),
     RefreshAllTargets,
     UpdateSettings(BasicSettings),
     /// This is a hacky way to notify a ReaLearn instance on the monitoring FX chain
@@ -2503,11 +2624,50 @@ pub enum ControlMainTask {
     },
 }
 
+#[derive(Clone, PartialEq, Debug)]
 pub enum OwnedIncomingMidiMessage {
     Short(RawShortMessage),
     SysEx(Vec<u8>),
 }
 
+/// Larger SysEx messages are not forwarded to MIDI control script sources because copying them
+/// would require allocation in the real-time thread.
+pub const MAX_FORWARDED_SYSEX_SIZE: usize = 256;
+
+/// Incoming MIDI message which can be copied in the real-time thread without allocation.
+///
+/// Because of the inline SysEx buffer, this is quite large. That's why it has its own channel
+/// (with preallocated slots) instead of being part of `ControlMainTask`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ForwardedMidiMessage {
+    Short(RawShortMessage),
+    SysEx(SmallVec<[u8; MAX_FORWARDED_SYSEX_SIZE]>),
+}
+
+impl ForwardedMidiMessage {
+    /// Returns `None` if the message is a SysEx message which is too large.
+    pub fn from_incoming(msg: IncomingMidiMessage) -> Option<Self> {
+        let msg = match msg {
+            IncomingMidiMessage::Short(m) => Self::Short(m),
+            IncomingMidiMessage::SysEx(bytes) => {
+                if bytes.len() > MAX_FORWARDED_SYSEX_SIZE {
+                    return None;
+                }
+                Self::SysEx(SmallVec::from_slice(bytes))
+            }
+        };
+        Some(msg)
+    }
+
+    /// Allocates if it's a SysEx message, so don't call in real-time thread.
+    pub fn into_owned(self) -> OwnedIncomingMidiMessage {
+        match self {
+            Self::Short(m) => OwnedIncomingMidiMessage::Short(m),
+            Self::SysEx(bytes) => OwnedIncomingMidiMessage::SysEx(bytes.into_vec()),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
 pub struct ControlOptions {
     pub enforce_send_feedback_after_control: bool,
@@ -2984,6 +3144,9 @@ impl<EH: DomainEventHandler> Basics<EH> {
                 };
                 self.event_handler
                     .notify_mapping_matched(Compartment::Controller, m.id());
+                #[cfg(feature = "realearn-metrics")]
+                self.metrics
+                    .record_mapping_match(Compartment::Controller, evt.timestamp());
                 let results = self.process_main_mappings_with_virtual_sources(
                     main_mappings,
                     evt.with_payload(virtual_source_value),
@@ -3108,6 +3271,7 @@ impl<EH: DomainEventHandler> Basics<EH> {
         feedback_reason: FeedbackReason,
         source_feedback_value: SourceFeedbackValue,
         is_feedback_after_control: bool,
+        origin: Option<FeedbackOrigin>,
     ) {
         // Block duplicates.
         // Extracting a feedback address is not super cheap for OSC and MIDI Raw because it has to
@@ -3140,6 +3304,9 @@ impl<EH: DomainEventHandler> Basics<EH> {
             test_sender.send_if_space(source_feedback_value);
         } else {
             // Production
+            #[cfg(feature = "realearn-metrics")]
+            self.metrics.record_feedback_output(feedback_output);
+            self.emit_source_feedback_server_event(feedback_output, &source_feedback_value, origin);
             match (source_feedback_value, feedback_output) {
                 (SourceFeedbackValue::Midi(v), FeedbackOutput::Midi(midi_output)) => {
                     match midi_output {
@@ -3192,6 +3359,34 @@ impl<EH: DomainEventHandler> Basics<EH> {
         }
     }
 
+    /// Lets server clients mirror the feedback which is sent to the controller.
+    fn emit_source_feedback_server_event(
+        &self,
+        output: FeedbackOutput,
+        value: &SourceFeedbackValue,
+        origin: Option<FeedbackOrigin>,
+    ) {
+        if !BackboneState::source_feedback_events_have_subscribers() {
+            return;
+        }
+        let (origin, text) = match origin {
+            None => (None, None),
+            Some(o) => {
+                let key = MappingKey::from(o.mapping_key.to_string());
+                (Some((o.compartment, key)), o.text)
+            }
+        };
+        let event = SourceFeedbackEvent {
+            instance_id: self.instance_id,
+            origin,
+            text,
+            output,
+            value: value.clone(),
+        };
+        // An error just means that there are no subscribers, which is fine.
+        let _ = BackboneState::source_feedback_event_sender().send(event);
+    }
+
     fn send_direct_feedback(
         &self,
         feedback_reason: FeedbackReason,
@@ -3222,6 +3417,7 @@ impl<EH: DomainEventHandler> Basics<EH> {
                             feedback_reason,
                             source_feedback_value,
                             is_feedback_after_control,
+                            Some(feedback_value.origin),
                         );
                     }
                 }
@@ -3266,8 +3462,9 @@ impl<EH: DomainEventHandler> Basics<EH> {
             .values_mut()
             .filter(|m| m.control_is_effectively_on())
             .filter_map(|m| {
-                if let CompoundMappingSource::Virtual(s) = &m.source() {
-                    let control_value = s.control(&evt.payload())?;
+                if let Some(ControlOutcome::Matched(control_value)) =
+                    m.control_virtual_source(evt.timestamp(), &evt.payload())
+                {
                     let control_event = evt.with_payload(control_value);
                     let options = ControlOptions {
                         enforce_target_refresh,
@@ -3439,6 +3636,10 @@ fn control_mapping_stage_one<EH: DomainEventHandler>(
     basics
         .event_handler
         .notify_mapping_matched(m.compartment(), m.id());
+    #[cfg(feature = "realearn-metrics")]
+    basics
+        .metrics
+        .record_mapping_match(m.compartment(), control_event.timestamp());
     m.control_from_mode(
         control_event,
         options,
@@ -1,15 +1,17 @@
 use crate::domain::{
     get_prop_value, prop_feedback_resolution, prop_is_affected_by, ActivationChange,
-    ActivationCondition, CompartmentParamIndex, CompoundChangeEvent, ControlContext, ControlEvent,
-    ControlEventTimestamp, ControlOptions, ExtendedProcessorContext, FeedbackResolution, GroupId,
-    HitInstructionReturnValue, KeyMessage, KeySource, MappingActivationEffect,
-    MappingControlContext, MappingData, MappingInfo, MessageCaptureEvent, MidiScanResult,
-    MidiSource, Mode, OscDeviceId, OscScanResult, PersistentMappingProcessingState,
+    ActivationCondition, BackboneState, ChordSource, CompartmentParamIndex, CompoundChangeEvent,
+    ControlContext, ControlEvent, ControlEventTimestamp, ControlOptions, ExtendedProcessorContext,
+    FeedbackColorMap, FeedbackResolution, Glide, GlideSettings, GroupId, HitInstructionReturnValue,
+    KeyMessage, KeySource, MappingActivationEffect, MappingControlContext, MappingData,
+    MappingInfo, MessageCaptureEvent, MidiControlScriptSource, MidiScanResult, MidiSource, Mode,
+    OscDeviceId, OscScanResult, OwnedIncomingMidiMessage, PersistentMappingProcessingState,
     PluginParamIndex, PluginParams, RealTimeMappingUpdate, RealTimeReaperTarget,
     RealTimeTargetUpdate, RealearnTarget, ReaperMessage, ReaperSource, ReaperTarget,
-    ReaperTargetType, Tag, TargetCharacter, TrackExclusivity, UnresolvedReaperTarget,
-    VirtualControlElement, VirtualFeedbackValue, VirtualSource, VirtualSourceAddress,
-    VirtualSourceValue, VirtualTarget, COMPARTMENT_PARAMETER_COUNT,
+    ReaperTargetType, RelativeAccelerator, SequenceSource, SourceCharacterGuess, Tag,
+    TargetCharacter, TrackExclusivity, UnresolvedReaperTarget, VirtualControlElement,
+    VirtualFeedbackValue, VirtualSource, VirtualSourceAddress, VirtualSourceValue, VirtualTarget,
+    COMPARTMENT_PARAMETER_COUNT,
 };
 use derive_more::Display;
 use enum_iterator::IntoEnumIterator;
@@ -190,6 +192,10 @@ pub struct MainMapping {
     initial_target_value: Option<AbsoluteValue>,
     /// Called "y_last" in the control transformation formula.
     last_non_performance_target_value: Cell<Option<AbsoluteValue>>,
+    glide_settings: Option<GlideSettings>,
+    /// Is `Some` while the target value is gliding towards the last control value.
+    glide: Option<Glide>,
+    feedback_color_map: Option<FeedbackColorMap>,
 }
 
 #[derive(Default, Debug)]
@@ -216,6 +222,9 @@ impl MainMapping {
         source: CompoundMappingSource,
         mode: Mode,
         group_interaction: GroupInteraction,
+        glide_settings: Option<GlideSettings>,
+        feedback_color_map: Option<FeedbackColorMap>,
+        relative_accelerator: Option<RelativeAccelerator>,
         unresolved_target: Option<UnresolvedCompoundMappingTarget>,
         activation_condition_1: ActivationCondition,
         activation_condition_2: ActivationCondition,
@@ -230,6 +239,7 @@ impl MainMapping {
                 source,
                 mode,
                 group_interaction,
+                relative_accelerator,
                 options,
                 time_of_last_control: None,
             },
@@ -247,6 +257,9 @@ impl MainMapping {
             extension,
             initial_target_value: None,
             last_non_performance_target_value: Cell::new(None),
+            glide_settings,
+            glide: None,
+            feedback_color_map,
         }
     }
 
@@ -385,6 +398,14 @@ impl MainMapping {
                 ..self.core.clone()
             },
             is_active: self.is_active_in_terms_of_activation_state(),
+            control_is_main_thread_only: self
+                .core
+                .mode
+                .settings()
+                .control_transformation
+                .as_ref()
+                .map(|t| t.is_main_thread_only())
+                .unwrap_or(false),
             target_category: self.unresolved_target.as_ref().map(|t| match t {
                 UnresolvedCompoundMappingTarget::Reaper(_) => UnresolvedTargetCategory::Reaper,
                 UnresolvedCompoundMappingTarget::Virtual(_) => UnresolvedTargetCategory::Virtual,
@@ -573,7 +594,9 @@ impl MainMapping {
     }
 
     pub fn wants_to_be_polled_for_control(&self) -> bool {
-        self.core.source.wants_to_be_polled() || self.core.mode.wants_to_be_polled()
+        self.core.source.wants_to_be_polled()
+            || self.core.mode.wants_to_be_polled()
+            || self.glide_settings.is_some()
     }
 
     /// The boolean return value tells if the resolved target changed in some way, the activation
@@ -698,10 +721,48 @@ impl MainMapping {
             logger,
             processor_context,
             true,
+            true,
             |_, context, mode, target| mode.poll(target, context, timestamp),
         )
     }
 
+    pub fn glide_is_in_progress(&self) -> bool {
+        self.glide.is_some()
+    }
+
+    /// This moves the target value along a glide which is in progress.
+    #[must_use]
+    pub fn poll_glide(
+        &mut self,
+        context: ControlContext,
+        logger: &slog::Logger,
+        processor_context: ExtendedProcessorContext,
+    ) -> MappingControlResult {
+        let glide = match self.glide {
+            None => return Default::default(),
+            Some(g) => g,
+        };
+        if self.targets.len() != 1 {
+            // The glide was started for another target.
+            self.glide = None;
+            return Default::default();
+        }
+        let (value, destination_reached) = glide.value_at(Instant::now());
+        if destination_reached {
+            self.glide = None;
+        }
+        let control_value = ControlValue::AbsoluteContinuous(UnitValue::new_clamped(value));
+        self.control_internal(
+            ControlOptions::default(),
+            context,
+            logger,
+            processor_context,
+            true,
+            false,
+            |_, _, _, _| Some(ModeControlResult::hit_target(control_value)),
+        )
+    }
+
     pub fn group_interaction(&self) -> GroupInteraction {
         self.core.group_interaction
     }
@@ -720,12 +781,14 @@ impl MainMapping {
         processor_context: ExtendedProcessorContext,
         last_non_performance_target_value: Option<AbsoluteValue>,
     ) -> MappingControlResult {
+        let source_control_event = self.core.accelerate(source_control_event);
         self.control_internal(
             options,
             context,
             logger,
             processor_context,
             false,
+            true,
             |options, context, mode, target| {
                 mode.control_with_options(
                     source_control_event,
@@ -758,6 +821,7 @@ impl MainMapping {
             logger,
             processor_context,
             false,
+            false,
             |_, _, mode, target| {
                 let mut v = value;
                 let control_type = target.control_type(context);
@@ -807,6 +871,7 @@ impl MainMapping {
             logger,
             processor_context,
             false,
+            false,
             |_, _, _, _| {
                 Some(ModeControlResult::hit_target(ControlValue::from_absolute(
                     value,
@@ -815,7 +880,11 @@ impl MainMapping {
         )
     }
 
+    /// If `may_glide` is `true` and the mapping has glide settings, absolute control values
+    /// don't hit the target directly but start (or retarget) a glide. There's only one glide per
+    /// mapping, so mappings with multiple targets never glide.
     #[must_use]
+    #[allow(clippy::too_many_arguments)]
     fn control_internal(
         &mut self,
         options: ControlOptions,
@@ -823,6 +892,7 @@ impl MainMapping {
         logger: &slog::Logger,
         processor_context: ExtendedProcessorContext,
         is_polling: bool,
+        may_glide: bool,
         get_mode_control_result: impl Fn(
             ControlOptions,
             MappingControlContext,
@@ -847,12 +917,16 @@ impl MainMapping {
         let ctx = MappingControlContext {
             control_context: context,
             mapping_data: self.data(),
+            compartment_params: processor_context
+                .params()
+                .compartment_params(self.core.compartment),
         };
         let actual_targets = if options.enforce_target_refresh {
             &mut fresh_targets
         } else {
             &mut self.targets
         };
+        let may_glide = may_glide && actual_targets.len() == 1;
         for target in actual_targets {
             let target = if let CompoundMappingTarget::Reaper(t) = target {
                 t
@@ -873,6 +947,23 @@ impl MainMapping {
                     if !is_polling {
                         self.core.time_of_last_control = Some(Instant::now());
                     }
+                    if may_glide {
+                        if let Some(settings) = self.glide_settings {
+                            let new_glide = create_glide(
+                                settings,
+                                self.glide.as_ref(),
+                                value,
+                                target,
+                                context,
+                                processor_context,
+                            );
+                            if let Some(g) = new_glide {
+                                // The target will be hit when polling.
+                                self.glide = Some(g);
+                                continue;
+                            }
+                        }
+                    }
                     // Be graceful here.
                     match target.hit(value, ctx) {
                         // TODO-low For now, the first hit instruction wins (at the moment we don't
@@ -1067,9 +1158,30 @@ impl MainMapping {
             options,
             Default::default(),
         )?;
+        let mode_value = self.apply_feedback_color_map(mode_value);
         self.feedback_given_mode_value(mode_value, destinations)
     }
 
+    /// Overrides the feedback color with the one from the feedback color map (if applicable).
+    fn apply_feedback_color_map<'a>(
+        &self,
+        mode_value: Cow<'a, FeedbackValue<'a>>,
+    ) -> Cow<'a, FeedbackValue<'a>> {
+        let color = match (&self.feedback_color_map, &*mode_value) {
+            (Some(map), FeedbackValue::Numeric(v)) => map.color_at(v.value.to_unit_value()),
+            _ => None,
+        };
+        let color = match color {
+            None => return mode_value,
+            Some(c) => c,
+        };
+        let mut value = mode_value.into_owned();
+        if let FeedbackValue::Numeric(v) = &mut value {
+            v.style.color = Some(color);
+        }
+        Cow::Owned(value)
+    }
+
     fn feedback_given_mode_value(
         &self,
         mode_value: Cow<FeedbackValue>,
@@ -1128,11 +1240,13 @@ impl MainMapping {
 
     /// Controls the source only.
     ///
-    /// Doesn't consider MIDI sources because they are handled completely in the real-time mapping.
+    /// Doesn't consider MIDI sources because they are handled completely in the real-time mapping
+    /// (except MIDI control script sources).
     pub fn control_source(
         &mut self,
-        msg: MainSourceMessage,
+        evt: ControlEvent<MainSourceMessage>,
     ) -> Option<ControlOutcome<ControlValue>> {
+        let msg = evt.payload();
         match (msg, &mut self.core.source) {
             (MainSourceMessage::Osc(m), CompoundMappingSource::Osc(s)) => {
                 // With OSC sources, we don't distinguish between matched or consumed because
@@ -1140,19 +1254,40 @@ impl MainMapping {
                 s.control(m).map(ControlOutcome::Matched)
             }
             (MainSourceMessage::Reaper(m), CompoundMappingSource::Reaper(s)) => {
+                if matches!(m.compartment(), Some(c) if c != self.core.compartment) {
+                    return None;
+                }
                 // With REAPER sources, we don't distinguish between matched or consumed because
                 // there's no such thing such as "letting messages through".
                 s.control(m).map(ControlOutcome::Matched)
             }
             (MainSourceMessage::Key(m), CompoundMappingSource::Key(s)) => s.control(m),
+            (MainSourceMessage::Midi(m), CompoundMappingSource::MidiControlScript(s)) => {
+                s.control(m).map(ControlOutcome::Matched)
+            }
+            (msg, CompoundMappingSource::Chord(s)) => {
+                s.control(evt.timestamp(), |member| control_main_member(msg, member))
+            }
+            (msg, CompoundMappingSource::Sequence(s)) => {
+                s.control(evt.timestamp(), |step| control_main_member(msg, step))
+            }
             _ => None,
         }
     }
 
+    /// Controls the source only, with a value coming from a virtual control element.
+    pub fn control_virtual_source(
+        &mut self,
+        timestamp: ControlEventTimestamp,
+        value: &VirtualSourceValue,
+    ) -> Option<ControlOutcome<ControlValue>> {
+        self.core.source.control_virtual(timestamp, value)
+    }
+
     /// Polls the source.
-    pub fn poll_source(&mut self) -> Option<ControlValue> {
+    pub fn poll_source(&mut self, project: Project) -> Option<ControlValue> {
         match &mut self.core.source {
-            CompoundMappingSource::Reaper(s) => s.poll(),
+            CompoundMappingSource::Reaper(s) => s.poll(project),
             _ => None,
         }
     }
@@ -1164,7 +1299,7 @@ impl MainMapping {
         if self.targets.is_empty() {
             return None;
         }
-        let control_value = match self.control_source(evt.payload())? {
+        let control_value = match self.control_source(evt)? {
             ControlOutcome::Consumed => {
                 return Some(ControlOutcome::Consumed);
             }
@@ -1181,11 +1316,28 @@ impl MainMapping {
     }
 }
 
+/// Lets a member of a compound source (e.g. a chord) react to the given main source message.
+fn control_main_member(
+    msg: MainSourceMessage,
+    member: &mut CompoundMappingSource,
+) -> Option<ControlValue> {
+    match (msg, member) {
+        (MainSourceMessage::Osc(m), CompoundMappingSource::Osc(s)) => s.control(m),
+        (MainSourceMessage::Key(m), CompoundMappingSource::Key(s)) => match s.control(m)? {
+            ControlOutcome::Matched(v) => Some(v),
+            ControlOutcome::Consumed => None,
+        },
+        _ => None,
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum MainSourceMessage<'a> {
     Osc(&'a OscMessage),
     Reaper(&'a ReaperMessage),
     Key(KeyMessage),
+    /// Only sent to MIDI control script sources.
+    Midi(&'a OwnedIncomingMidiMessage),
 }
 
 impl<'a> MainSourceMessage<'a> {
@@ -1197,7 +1349,7 @@ impl<'a> MainSourceMessage<'a> {
                 dev_id: None,
             }),
             Key(msg) => MessageCaptureResult::Keyboard(msg),
-            Reaper(_) => panic!("capturing of incoming MIDI messages not supported"),
+            Reaper(_) | Midi(_) => panic!("capturing of incoming MIDI messages not supported"),
         }
     }
 }
@@ -1206,6 +1358,9 @@ impl<'a> MainSourceMessage<'a> {
 pub struct RealTimeMapping {
     pub core: MappingCore,
     is_active: bool,
+    /// If `true`, the mode can't be applied in real-time, so control must always be forwarded to
+    /// the main processor (even if the target supports real-time control).
+    control_is_main_thread_only: bool,
     /// Is `Some` if user-provided target data is complete.
     target_category: Option<UnresolvedTargetCategory>,
     target_is_resolved: bool,
@@ -1314,6 +1469,10 @@ impl RealTimeMapping {
         matches!(self.target_category, Some(UnresolvedTargetCategory::Reaper))
     }
 
+    pub fn control_is_main_thread_only(&self) -> bool {
+        self.control_is_main_thread_only
+    }
+
     pub fn consumes(&self, msg: RawShortMessage) -> bool {
         self.core.source.consumes(&msg)
     }
@@ -1335,10 +1494,13 @@ impl RealTimeMapping {
         if !self.target_is_resolved {
             return None;
         }
-        let control_value = if let CompoundMappingSource::Midi(s) = &self.core.source {
-            s.control(evt.payload())?
-        } else {
-            return None;
+        let control_value = match self
+            .core
+            .source
+            .control_midi(evt.timestamp(), evt.payload())?
+        {
+            ControlOutcome::Matched(v) => v,
+            ControlOutcome::Consumed => return None,
         };
         if let Some(RealTimeCompoundMappingTarget::Virtual(t)) = self.resolved_target.as_ref() {
             match_partially(&mut self.core, t, evt.with_payload(control_value))
@@ -1362,12 +1524,23 @@ pub struct MappingCore {
     pub source: CompoundMappingSource,
     pub mode: Mode,
     group_interaction: GroupInteraction,
+    /// Software acceleration for relative control values.
+    relative_accelerator: Option<RelativeAccelerator>,
     options: ProcessorMappingOptions,
     /// Used for preventing echo feedback.
     time_of_last_control: Option<Instant>,
 }
 
 impl MappingCore {
+    /// Applies software acceleration (if enabled). Must be called right before the control
+    /// value is handed to the mode, so that each incoming event is accelerated exactly once.
+    pub fn accelerate(&mut self, evt: ControlEvent<ControlValue>) -> ControlEvent<ControlValue> {
+        match &mut self.relative_accelerator {
+            None => evt,
+            Some(a) => evt.with_payload(a.accelerate(evt.payload(), evt.timestamp())),
+        }
+    }
+
     fn is_echo(&self) -> bool {
         if let Some(t) = self.time_of_last_control {
             t.elapsed() <= MAX_ECHO_FEEDBACK_DELAY
@@ -1386,6 +1559,9 @@ pub enum CompoundMappingSource {
     Virtual(VirtualSource),
     Reaper(ReaperSource),
     Key(KeySource),
+    Chord(ChordSource),
+    Sequence(SequenceSource),
+    MidiControlScript(MidiControlScriptSource),
 }
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
@@ -1498,6 +1674,59 @@ impl CompoundMappingSource {
             (Osc(s), IncomingCompoundSourceValue::Osc(m)) => s.control(m),
             (Virtual(s), IncomingCompoundSourceValue::Virtual(m)) => s.control(m),
             (Key(s), IncomingCompoundSourceValue::Key(m)) => s.reacts_to_message_with(m),
+            (Chord(s), value) => s
+                .members()
+                .find_map(|m| m.reacts_to_source_value_with(value)),
+            (Sequence(s), value) => s.steps().find_map(|m| m.reacts_to_source_value_with(value)),
+            (
+                MidiControlScript(s),
+                IncomingCompoundSourceValue::Midi(MidiSourceValue::Plain(m)),
+            ) => s.control(&OwnedIncomingMidiMessage::Short(*m)),
+            _ => None,
+        }
+    }
+
+    /// Lets this source react to the given MIDI source value (MIDI sources, chords and sequences).
+    ///
+    /// Used in the real-time processor.
+    pub fn control_midi(
+        &mut self,
+        timestamp: ControlEventTimestamp,
+        value: &MidiSourceValue<RawShortMessage>,
+    ) -> Option<ControlOutcome<ControlValue>> {
+        use CompoundMappingSource::*;
+        match self {
+            Midi(s) => s.control(value).map(ControlOutcome::Matched),
+            Chord(s) => s.control(timestamp, |member| match member {
+                Midi(s) => s.control(value),
+                _ => None,
+            }),
+            Sequence(s) => s.control(timestamp, |step| match step {
+                Midi(s) => s.control(value),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Lets this source react to the given virtual source value (virtual sources, chords and
+    /// sequences).
+    pub fn control_virtual(
+        &mut self,
+        timestamp: ControlEventTimestamp,
+        value: &VirtualSourceValue,
+    ) -> Option<ControlOutcome<ControlValue>> {
+        use CompoundMappingSource::*;
+        match self {
+            Virtual(s) => s.control(value).map(ControlOutcome::Matched),
+            Chord(s) => s.control(timestamp, |member| match member {
+                Virtual(s) => s.control(value),
+                _ => None,
+            }),
+            Sequence(s) => s.control(timestamp, |step| match step {
+                Virtual(s) => s.control(value),
+                _ => None,
+            }),
             _ => None,
         }
     }
@@ -1506,8 +1735,10 @@ impl CompoundMappingSource {
         use MessageCaptureResult::*;
         let res = match event.result {
             Midi(scan_result) => {
-                let midi_source =
-                    MidiSource::from_source_value(scan_result.value, scan_result.character)?;
+                let midi_source = MidiSource::from_source_value(
+                    scan_result.value,
+                    scan_result.character.map(|g| g.character),
+                )?;
                 Self::Midi(midi_source)
             }
             Osc(msg) => {
@@ -1516,7 +1747,7 @@ impl CompoundMappingSource {
                 Self::Osc(osc_source)
             }
             Keyboard(msg) => {
-                let key_source = KeySource::new(msg.stroke());
+                let key_source = KeySource::new(msg.stroke(), Default::default());
                 Self::Key(key_source)
             }
         };
@@ -1530,7 +1761,9 @@ impl CompoundMappingSource {
             Virtual(s) => s.format_control_value(value),
             Osc(s) => s.format_control_value(value),
             Reaper(s) => s.format_control_value(value),
-            Never | Key(_) => Ok(format_percentage_without_unit(value.to_unit_value()?.get())),
+            Never | Key(_) | Chord(_) | Sequence(_) | MidiControlScript(_) => {
+                Ok(format_percentage_without_unit(value.to_unit_value()?.get()))
+            }
         }
     }
 
@@ -1541,7 +1774,9 @@ impl CompoundMappingSource {
             Virtual(s) => s.parse_control_value(text),
             Osc(s) => s.parse_control_value(text),
             Reaper(s) => s.parse_control_value(text),
-            Never | Key(_) => parse_percentage_without_unit(text)?.try_into(),
+            Never | Key(_) | Chord(_) | Sequence(_) | MidiControlScript(_) => {
+                parse_percentage_without_unit(text)?.try_into()
+            }
         }
     }
 
@@ -1552,8 +1787,12 @@ impl CompoundMappingSource {
             Virtual(s) => s.character(),
             Osc(s) => ExtendedSourceCharacter::Normal(s.character()),
             Reaper(s) => ExtendedSourceCharacter::Normal(s.character()),
+            MidiControlScript(s) => ExtendedSourceCharacter::Normal(s.character()),
             Never => ExtendedSourceCharacter::VirtualContinuous,
-            Key(_) => ExtendedSourceCharacter::Normal(SourceCharacter::MomentaryButton),
+            Key(s) => ExtendedSourceCharacter::Normal(s.character()),
+            Chord(_) | Sequence(_) => {
+                ExtendedSourceCharacter::Normal(SourceCharacter::MomentaryButton)
+            }
         }
     }
 
@@ -1569,7 +1808,9 @@ impl CompoundMappingSource {
             // This is handled in a special way by consumers.
             Virtual(_) => None,
             // No feedback for never source.
-            Reaper(_) | Key(_) | Never => None,
+            // Control only.
+            MidiControlScript(_) => None,
+            Reaper(_) | Key(_) | Chord(_) | Sequence(_) | Never => None,
         }
     }
 
@@ -1577,7 +1818,10 @@ impl CompoundMappingSource {
         use CompoundMappingSource::*;
         match self {
             Midi(s) => s.consumes(msg),
-            Reaper(_) | Virtual(_) | Osc(_) | Never | Key(_) => false,
+            Chord(s) => s.members().any(|m| m.consumes(msg)),
+            Sequence(s) => s.steps().any(|m| m.consumes(msg)),
+            // Processed in the main thread, so it can't decide about consumption in real-time.
+            Reaper(_) | Virtual(_) | Osc(_) | Never | Key(_) | MidiControlScript(_) => false,
         }
     }
 
@@ -1585,13 +1829,25 @@ impl CompoundMappingSource {
         matches!(self, CompoundMappingSource::Virtual(_))
     }
 
+    pub fn is_midi_control_script(&self) -> bool {
+        matches!(self, CompoundMappingSource::MidiControlScript(_))
+    }
+
+    pub fn is_marker_passed(&self) -> bool {
+        matches!(
+            self,
+            CompoundMappingSource::Reaper(ReaperSource::MarkerPassed)
+        )
+    }
+
     pub fn max_discrete_value(&self) -> Option<u32> {
         use CompoundMappingSource::*;
         match self {
             Midi(s) => s.max_discrete_value(),
             // TODO-medium OSC will also support discrete values as soon as we allow integers and
             //  configuring max values
-            Reaper(_) | Virtual(_) | Osc(_) | Never | Key(_) => None,
+            Reaper(_) | Virtual(_) | Osc(_) | Never | Key(_) | Chord(_) | Sequence(_)
+            | MidiControlScript(_) => None,
         }
     }
 }
@@ -1665,17 +1921,35 @@ impl SpecificCompoundFeedbackValue {
             {
                 // TODO-medium Support textual projection feedback
                 mode_value.to_numeric().map(|v| {
-                    ProjectionFeedbackValue::new(compartment, mapping_key, v.value.to_unit_value())
+                    ProjectionFeedbackValue::new(
+                        compartment,
+                        mapping_key.clone(),
+                        v.value.to_unit_value(),
+                    )
                 })
             } else {
                 None
             };
+            // Cloning the text is only worth it if someone mirrors the feedback.
+            let text = match &*mode_value {
+                FeedbackValue::Textual(v)
+                    if BackboneState::source_feedback_events_have_subscribers() =>
+                {
+                    Some(v.text.to_string())
+                }
+                _ => None,
+            };
             let source = if destinations.with_source_feedback {
                 source.feedback(mode_value)
             } else {
                 None
             };
-            SpecificCompoundFeedbackValue::Real(RealFeedbackValue::new(projection, source)?)
+            let origin = FeedbackOrigin {
+                compartment,
+                mapping_key,
+                text,
+            };
+            SpecificCompoundFeedbackValue::Real(RealFeedbackValue::new(origin, projection, source)?)
         };
         Some(val)
     }
@@ -1683,6 +1957,8 @@ impl SpecificCompoundFeedbackValue {
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct RealFeedbackValue {
+    /// The mapping which produced this feedback value.
+    pub origin: FeedbackOrigin,
     /// Feedback to be sent to projection.
     ///
     /// This is an option because there are situations when we don't want projection feedback but
@@ -1697,17 +1973,32 @@ pub struct RealFeedbackValue {
 
 impl RealFeedbackValue {
     pub fn new(
+        origin: FeedbackOrigin,
         projection: Option<ProjectionFeedbackValue>,
         source: Option<SourceFeedbackValue>,
     ) -> Option<Self> {
         if projection.is_none() && source.is_none() {
             return None;
         }
-        let val = Self { projection, source };
+        let val = Self {
+            origin,
+            projection,
+            source,
+        };
         Some(val)
     }
 }
 
+#[derive(Clone, PartialEq, Debug)]
+pub struct FeedbackOrigin {
+    pub compartment: Compartment,
+    pub mapping_key: Rc<str>,
+    /// The text in case of textual feedback (e.g. for an LCD).
+    ///
+    /// Only set if there are server clients which mirror the feedback.
+    pub text: Option<String>,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct ProjectionFeedbackValue {
     pub compartment: Compartment,
@@ -2228,6 +2519,15 @@ impl Compartment {
             .unwrap()
     }
 
+    /// Returns the plug-in parameter index corresponding to the given compartment-local parameter
+    /// index.
+    pub fn to_plugin_param_index(
+        self,
+        compartment_param_index: CompartmentParamIndex,
+    ) -> PluginParamIndex {
+        (self.plugin_param_offset() + compartment_param_index.get()).unwrap()
+    }
+
     /// Returns the plug-in parameter range corresponding to this compartment.
     pub fn plugin_param_range(self) -> RangeInclusive<PluginParamIndex> {
         let offset = self.plugin_param_offset();
@@ -2260,6 +2560,7 @@ fn match_partially(
     // TODO-medium If we want to support fire after timeout and turbo for mappings with
     //  virtual targets one day, we need to poll this in real-time processor and OSC
     //  processing, too!
+    let control_event = core.accelerate(control_event);
     let res = core.mode.control_with_options(
         control_event,
         target,
@@ -2307,6 +2608,41 @@ pub struct MappingControlResult {
     pub hit_instruction: HitInstructionReturnValue,
 }
 
+/// Creates a glide from the current value (or the value of the glide in progress) to the given
+/// control value.
+///
+/// Returns `None` if the target should be hit directly instead.
+fn create_glide(
+    settings: GlideSettings,
+    glide_in_progress: Option<&Glide>,
+    value: ControlValue,
+    target: &ReaperTarget,
+    context: ControlContext,
+    processor_context: ExtendedProcessorContext,
+) -> Option<Glide> {
+    let to = match value {
+        ControlValue::AbsoluteContinuous(v) => v.get(),
+        _ => return None,
+    };
+    let now = Instant::now();
+    let from = match glide_in_progress {
+        Some(g) => g.value_at(now).0,
+        None => target.current_value(context)?.to_unit_value().get(),
+    };
+    let duration = settings.time.duration(|| {
+        processor_context
+            .context()
+            .project_or_current_project()
+            .tempo()
+            .bpm()
+            .get()
+    });
+    if duration.is_zero() {
+        return None;
+    }
+    Some(Glide::new(from, to, now, duration, settings.shape))
+}
+
 /// Not usable for mappings with virtual targets.
 fn should_send_manual_feedback_due_to_target(
     target: &ReaperTarget,
@@ -2380,6 +2716,14 @@ impl MessageCaptureResult {
         }
     }
 
+    /// Returns the guessed source character if this is a MIDI message which allowed guessing.
+    pub fn source_character_guess(&self) -> Option<SourceCharacterGuess> {
+        match self {
+            MessageCaptureResult::Midi(res) => res.character,
+            _ => None,
+        }
+    }
+
     pub fn to_input_descriptor(&self, ignore_midi_channel: bool) -> Option<InputDescriptor> {
         use MessageCaptureResult::*;
         let res = match self {
@@ -0,0 +1,88 @@
+//! Records metrics about mapping throughput, latency and feedback.
+//!
+//! Only available if the `realearn-metrics` feature is enabled. All functions must be called in
+//! the main thread.
+use crate::domain::{
+    Compartment, ControlEventTimestamp, FeedbackOutput, InstanceId, MidiDestination,
+};
+use enum_map::EnumMap;
+use metrics::{Counter, Histogram};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Metric handles of one instance.
+///
+/// Labels are created only once (when registering the handles), so recording doesn't allocate.
+pub struct InstanceMetrics {
+    mapping_matches: EnumMap<Compartment, Counter>,
+    control_latencies: EnumMap<Compartment, Histogram>,
+    feedback_messages: RefCell<HashMap<FeedbackOutput, Counter>>,
+}
+
+impl InstanceMetrics {
+    pub fn new(instance_id: &InstanceId) -> Self {
+        let instance_label = instance_id.to_string();
+        Self {
+            mapping_matches: EnumMap::from(|compartment| {
+                metrics::register_counter!(
+                    "realearn_mapping_matches_total",
+                    "instance" => instance_label.clone(),
+                    "compartment" => compartment_label(compartment)
+                )
+            }),
+            control_latencies: EnumMap::from(|compartment| {
+                // Time between receiving the control event and letting it reach the mapping
+                metrics::register_histogram!(
+                    "realearn_control_latency_seconds",
+                    "instance" => instance_label.clone(),
+                    "compartment" => compartment_label(compartment)
+                )
+            }),
+            feedback_messages: Default::default(),
+        }
+    }
+
+    /// Records that a mapping matched an incoming control event.
+    pub fn record_mapping_match(&self, compartment: Compartment, timestamp: ControlEventTimestamp) {
+        self.mapping_matches[compartment].increment(1);
+        self.control_latencies[compartment].record(ControlEventTimestamp::now() - timestamp);
+    }
+
+    /// Records that a feedback message has been scheduled for sending to the given output.
+    pub fn record_feedback_output(&self, feedback_output: FeedbackOutput) {
+        self.feedback_messages
+            .borrow_mut()
+            .entry(feedback_output)
+            .or_insert_with(|| {
+                metrics::register_counter!(
+                    "realearn_feedback_messages_total",
+                    "output" => output_label(feedback_output)
+                )
+            })
+            .increment(1);
+    }
+}
+
+impl fmt::Debug for InstanceMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstanceMetrics").finish_non_exhaustive()
+    }
+}
+
+fn compartment_label(compartment: Compartment) -> &'static str {
+    match compartment {
+        Compartment::Controller => "controller",
+        Compartment::Main => "main",
+    }
+}
+
+fn output_label(feedback_output: FeedbackOutput) -> String {
+    match feedback_output {
+        FeedbackOutput::Midi(MidiDestination::FxOutput) => "midi-fx-output".to_string(),
+        FeedbackOutput::Midi(MidiDestination::Device(dev_id)) => {
+            format!("midi-device-{}", dev_id.get())
+        }
+        FeedbackOutput::Osc(dev_id) => format!("osc-device-{}", dev_id),
+    }
+}
@@ -0,0 +1,45 @@
+use crate::domain::{LuaMidiControlScript, OwnedIncomingMidiMessage};
+use helgoboss_learn::{ControlValue, SourceCharacter};
+use helgoboss_midi::ShortMessage;
+
+/// A MIDI source which lets a Lua script turn arbitrary incoming MIDI messages into control values.
+///
+/// Other than the other MIDI sources, this one is processed in the main thread because Lua
+/// scripts can't be executed in real-time. The real-time processor forwards incoming messages.
+#[derive(Clone, Debug)]
+pub struct MidiControlScriptSource {
+    script: Option<LuaMidiControlScript<'static>>,
+    character: SourceCharacter,
+}
+
+impl PartialEq for MidiControlScriptSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.script_code() == other.script_code() && self.character == other.character
+    }
+}
+
+impl MidiControlScriptSource {
+    pub fn new(script: Option<LuaMidiControlScript<'static>>, character: SourceCharacter) -> Self {
+        Self { script, character }
+    }
+
+    pub fn script_code(&self) -> Option<&str> {
+        self.script.as_ref().map(|s| s.code())
+    }
+
+    pub fn character(&self) -> SourceCharacter {
+        self.character
+    }
+
+    pub fn control(&self, msg: &OwnedIncomingMidiMessage) -> Option<ControlValue> {
+        let script = self.script.as_ref()?;
+        let result = match msg {
+            OwnedIncomingMidiMessage::Short(m) => {
+                let (status_byte, data_byte_1, data_byte_2) = m.to_bytes();
+                script.execute(&[status_byte, data_byte_1.get(), data_byte_2.get()])
+            }
+            OwnedIncomingMidiMessage::SysEx(bytes) => script.execute(bytes),
+        };
+        result.ok().flatten()
+    }
+}
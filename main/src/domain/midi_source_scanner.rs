@@ -1,16 +1,26 @@
-use helgoboss_learn::{MidiSourceValue, RawMidiEvent, SourceCharacter};
+use helgoboss_learn::{MidiSourceValue, RawMidiEvent, SourceCharacter, UnitValue};
 use helgoboss_midi::{
-    Channel, ControlChange14BitMessageScanner, ControllerNumber,
+    Channel, ControlChange14BitMessage, ControlChange14BitMessageScanner, ControllerNumber,
     PollingParameterNumberMessageScanner, RawShortMessage, ShortMessage, ShortMessageFactory,
-    StructuredShortMessage, U7,
+    StructuredShortMessage, U14, U7,
 };
 use reaper_medium::MidiInputDeviceId;
 use std::cmp::Ordering;
 use std::time::{Duration, Instant};
 
-const MAX_CC_MSG_COUNT: usize = 10;
-const MAX_CC_WAITING_TIME: Duration = Duration::from_millis(250);
-
+/// Enough for a short burst of encoder turns in both directions.
+const MAX_CC_MSG_COUNT: usize = 32;
+/// We stop waiting for more CC messages as soon as the control element rests for this long ...
+const MAX_CC_IDLE_TIME: Duration = Duration::from_millis(250);
+/// ... or at the latest after this time.
+const MAX_CC_WAITING_TIME: Duration = Duration::from_millis(1500);
+/// Guesses below this confidence are reported to the user.
+const MIN_CERTAIN_CONFIDENCE: f64 = 0.6;
+
+/// Figures out which MIDI source corresponds to incoming messages and guesses its character.
+///
+/// CC messages are collected for a short while. If the control element turns out to send MSB/LSB
+/// pairs (e.g. when sweeping a 14-bit fader quickly), the result is a 14-bit CC source.
 #[derive(Debug)]
 pub struct MidiScanner {
     // Scanners for more complex MIDI message types
@@ -40,50 +50,137 @@ enum State {
 #[derive(Debug)]
 struct ControlChangeState {
     start_time: Instant,
+    last_msg_time: Instant,
     channel: Channel,
     controller_number: ControllerNumber,
     msg_count: usize,
     values: [U7; MAX_CC_MSG_COUNT],
+    /// Number of received LSB messages (only if `controller_number` can be a 14-bit MSB).
+    lsb_msg_count: usize,
+    /// 14-bit values, one for each LSB message which completed an MSB/LSB pair.
+    values_14_bit: [U14; MAX_CC_MSG_COUNT],
+    pair_count: usize,
 }
 
 impl ControlChangeState {
     fn new(channel: Channel, controller_number: ControllerNumber) -> ControlChangeState {
+        let now = Instant::now();
         ControlChangeState {
-            start_time: Instant::now(),
+            start_time: now,
+            last_msg_time: now,
             channel,
             controller_number,
             msg_count: 0,
             values: [U7::MIN; MAX_CC_MSG_COUNT],
+            lsb_msg_count: 0,
+            values_14_bit: [U14::MIN; MAX_CC_MSG_COUNT],
+            pair_count: 0,
         }
     }
 
     fn add_value(&mut self, value: U7) {
-        assert!(self.msg_count < MAX_CC_MSG_COUNT);
+        assert!(self.total_msg_count() < MAX_CC_MSG_COUNT);
         self.values[self.msg_count] = value;
         self.msg_count += 1;
+        self.last_msg_time = Instant::now();
+    }
+
+    /// Adds the value of an LSB message which belongs to our (MSB) controller number.
+    fn add_lsb_value(&mut self, value: U7) {
+        assert!(self.total_msg_count() < MAX_CC_MSG_COUNT);
+        self.lsb_msg_count += 1;
+        self.last_msg_time = Instant::now();
+        // An LSB without preceding MSB can't be combined
+        if self.msg_count == 0 {
+            return;
+        }
+        let msb = self.values[self.msg_count - 1].get() as u16;
+        self.values_14_bit[self.pair_count] = U14::new((msb << 7) | value.get() as u16);
+        self.pair_count += 1;
+    }
+
+    /// The first message of a sweep might have been an LSB. In that case, we start over with
+    /// the MSB as controller number.
+    fn switch_to_msb(&mut self, msb_controller_number: ControllerNumber) {
+        self.controller_number = msb_controller_number;
+        self.lsb_msg_count = self.msg_count;
+        self.msg_count = 0;
+    }
+
+    fn total_msg_count(&self) -> usize {
+        self.msg_count + self.lsb_msg_count
     }
 
     fn time_to_guess(&self) -> bool {
-        self.msg_count >= MAX_CC_MSG_COUNT || Instant::now() - self.start_time > MAX_CC_WAITING_TIME
+        let now = Instant::now();
+        self.total_msg_count() >= MAX_CC_MSG_COUNT
+            || now - self.last_msg_time > MAX_CC_IDLE_TIME
+            || now - self.start_time > MAX_CC_WAITING_TIME
     }
 
     fn matches(&self, channel: Channel, controller_number: ControllerNumber) -> bool {
         channel == self.channel && controller_number == self.controller_number
     }
+
+    fn matches_lsb(&self, channel: Channel, controller_number: ControllerNumber) -> bool {
+        channel == self.channel
+            && self.controller_number.get() < 32
+            && controller_number.get() == self.controller_number.get() + 32
+    }
+
+    fn matches_msb(&self, channel: Channel, controller_number: ControllerNumber) -> bool {
+        channel == self.channel
+            && self.msg_count > 0
+            && self.lsb_msg_count == 0
+            && (32..64).contains(&self.controller_number.get())
+            && controller_number.get() + 32 == self.controller_number.get()
+    }
+
+    fn matches_14_bit(&self, msg: &ControlChange14BitMessage) -> bool {
+        msg.channel() == self.channel
+            && (msg.msb_controller_number() == self.controller_number
+                || msg.lsb_controller_number() == self.controller_number)
+    }
+
+    fn is_14_bit(&self) -> bool {
+        self.pair_count > 0
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct MidiScanResult {
     pub value: MidiSourceValue<'static, RawShortMessage>,
     pub dev_id: Option<MidiInputDeviceId>,
-    pub character: Option<SourceCharacter>,
+    pub character: Option<SourceCharacterGuess>,
+}
+
+/// The character which the scanner guessed by looking at the values emitted by a control element.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SourceCharacterGuess {
+    pub character: SourceCharacter,
+    /// How sure the scanner is about the guessed character (0% = wild guess, 100% = certain).
+    pub confidence: UnitValue,
+}
+
+impl SourceCharacterGuess {
+    fn new(character: SourceCharacter, confidence: f64) -> Self {
+        Self {
+            character,
+            confidence: UnitValue::new_clamped(confidence),
+        }
+    }
+
+    /// Returns `true` if the guess is so vague that the user should double-check it.
+    pub fn is_uncertain(&self) -> bool {
+        self.confidence.get() < MIN_CERTAIN_CONFIDENCE
+    }
 }
 
 impl MidiScanResult {
     pub fn new(
         value: MidiSourceValue<'static, RawShortMessage>,
         dev_id: Option<MidiInputDeviceId>,
-        character: Option<SourceCharacter>,
+        character: Option<SourceCharacterGuess>,
     ) -> Self {
         Self {
             value,
@@ -186,9 +283,22 @@ impl MidiScanner {
                     {
                         if cc_state.matches(channel, controller_number) {
                             cc_state.add_value(control_value);
+                        } else if cc_state.matches_lsb(channel, controller_number) {
+                            cc_state.add_lsb_value(control_value);
+                        } else if cc_state.matches_msb(channel, controller_number) {
+                            cc_state.switch_to_msb(controller_number);
+                            cc_state.add_value(control_value);
                         }
                     }
                     self.guess_or_not()
+                } else if matches!(
+                    &source_value,
+                    MidiSourceValue::ControlChange14Bit(msg) if cc_state.matches_14_bit(msg)
+                ) {
+                    // The 14-bit scanner found an MSB/LSB pair of the CC we are already
+                    // collecting. We keep collecting (the plain messages follow) in order to be
+                    // able to guess the character.
+                    None
                 } else {
                     // Looks like in the meantime, the composite scanners ((N)RPN or
                     // 14-bit CC) have figured out that the combination is a composite
@@ -237,6 +347,16 @@ impl MidiScanner {
 }
 
 fn guess(cc_state: &ControlChangeState, dev_id: Option<MidiInputDeviceId>) -> MidiScanResult {
+    if cc_state.is_14_bit() {
+        let values = &cc_state.values_14_bit[0..cc_state.pair_count];
+        let first_cc_14_bit_msg =
+            ControlChange14BitMessage::new(cc_state.channel, cc_state.controller_number, values[0]);
+        return MidiScanResult {
+            value: MidiSourceValue::ControlChange14Bit(first_cc_14_bit_msg),
+            dev_id,
+            character: Some(guess_14_bit_character(values)),
+        };
+    }
     let first_cc_msg = RawShortMessage::control_change(
         cc_state.channel,
         cc_state.controller_number,
@@ -246,7 +366,7 @@ fn guess(cc_state: &ControlChangeState, dev_id: Option<MidiInputDeviceId>) -> Mi
         value: MidiSourceValue::Plain(first_cc_msg),
         dev_id,
         character: Some(guess_custom_character(
-            &cc_state.values[0..cc_state.msg_count - 1],
+            &cc_state.values[0..cc_state.msg_count],
         )),
     }
 }
@@ -292,18 +412,19 @@ fn contains_consecutive_duplicates(values: &[U7]) -> bool {
     false
 }
 
-fn guess_custom_character(values: &[U7]) -> SourceCharacter {
+fn guess_custom_character(values: &[U7]) -> SourceCharacterGuess {
     use SourceCharacter::*;
     // We don't just interpret 127 or 100 as button because we consider typical keyboard keys also
     // as buttons. They can be velocity-sensitive and therefore transmit any value.
     #[allow(clippy::if_same_then_else)]
     if values.len() == 1 {
         // Only one message received. Looks like a button has been pressed and not released.
-        MomentaryButton
+        // Could also be a knob which has been touched very carefully.
+        SourceCharacterGuess::new(MomentaryButton, 0.6)
     } else if values.len() == 2 && values[1] == U7::MIN {
         // Two messages received and second message has value 0. Looks like a button has been
         // pressed and released.
-        MomentaryButton
+        SourceCharacterGuess::new(MomentaryButton, 0.9)
     } else {
         // Multiple messages received. Button character is ruled out already. Check continuity.
         if contains_direction_change(values) {
@@ -315,7 +436,7 @@ fn guess_custom_character(values: &[U7]) -> SourceCharacter {
                 // to send. So it's probably an encoder which is
                 // configured to transmit absolute values hitting
                 // the lower boundary.
-                RangeElement
+                SourceCharacterGuess::new(RangeElement, 0.75)
             } else if values.contains(&U7::MAX) {
                 // Here we rely on the fact that the user should turn clock-wise. So it
                 // can't be relative type 1 because 127 means
@@ -323,27 +444,151 @@ fn guess_custom_character(values: &[U7]) -> SourceCharacter {
                 // other relative types because this would happen with extreme acceleration
                 // only. So it's probably an encoder which is configured to transmit
                 // absolute values hitting the upper boundary.
-                RangeElement
+                SourceCharacterGuess::new(RangeElement, 0.75)
             } else {
                 guess_encoder_type(values)
             }
         } else {
             // Was continuous without duplicates until now so it's probably a knob/fader.
-            SourceCharacter::RangeElement
+            SourceCharacterGuess::new(RangeElement, 0.9)
         }
     }
 }
 
-/// Unfortunately, encoder type 3 clockwise movement is not really distinguishable from 1 or 2.
-/// So we won't support its detection.
-fn guess_encoder_type(values: &[U7]) -> SourceCharacter {
+/// Relative encoders practically never send 14-bit values, so a 14-bit control element is either a
+/// button or a range element.
+fn guess_14_bit_character(values: &[U14]) -> SourceCharacterGuess {
     use SourceCharacter::*;
-    match values[0].get() {
-        1..=7 | 121..=127 => Encoder1,
-        57..=71 => Encoder2,
-        // The remaining values are supported but not so typical for encoders because they only
-        // happen at high accelerations.
-        _ => RangeElement,
+    match values {
+        [_] => SourceCharacterGuess::new(MomentaryButton, 0.6),
+        [_, last] if *last == U14::MIN => SourceCharacterGuess::new(MomentaryButton, 0.9),
+        _ => SourceCharacterGuess::new(RangeElement, 0.9),
+    }
+}
+
+/// Returns the share of value steps which continue a movement into the same direction with a
+/// small delta, which is typical for faders and knobs but not for relative encoders.
+///
+/// Encoders send values which reflect the speed, not the position, so they rarely move
+/// steadily into one direction.
+fn absolute_continuity(values: &[U7]) -> f64 {
+    if values.len() < 3 {
+        return 0.0;
+    }
+    // Called in real-time thread, so we don't collect the deltas
+    let delta = |i: usize| values[i + 1].get() as i16 - values[i].get() as i16;
+    let delta_count = values.len() - 1;
+    let continuing_count = (1..delta_count)
+        .filter(|i| {
+            let (previous, current) = (delta(i - 1), delta(*i));
+            previous != 0 && current.signum() == previous.signum() && current.abs() <= 4
+        })
+        .count();
+    continuing_count as f64 / (delta_count - 1) as f64
+}
+
+/// Looks at the typical increment and decrement values of the different encoder types.
+///
+/// Turning into one direction only is ambiguous (e.g. type 1 and type 3 both send 1 as increment).
+/// Turning into both directions lets us tell all three types apart:
+///
+/// - Type 1: 1 = increment, 127 = decrement
+/// - Type 2: 65 = increment, 63 = decrement
+/// - Type 3: 1 = increment, 65 = decrement
+///
+/// Type 2 values are easily confused with a fader or knob which is moved around the center, so
+/// in that case we also take the continuity of the values into account.
+fn guess_encoder_type(values: &[U7]) -> SourceCharacterGuess {
+    let guess = guess_encoder_type_by_values(values);
+    if guess.character != SourceCharacter::Encoder2 {
+        return guess;
+    }
+    let continuity = absolute_continuity(values);
+    if continuity >= 0.5 {
+        SourceCharacterGuess::new(SourceCharacter::RangeElement, continuity)
+    } else {
+        SourceCharacterGuess::new(
+            SourceCharacter::Encoder2,
+            guess.confidence.get() * (1.0 - continuity),
+        )
+    }
+}
+
+fn guess_encoder_type_by_values(values: &[U7]) -> SourceCharacterGuess {
+    use SourceCharacter::*;
+    #[derive(Default)]
+    struct Counts {
+        low: usize,
+        high: usize,
+        above_center: usize,
+        below_center: usize,
+        other: usize,
+    }
+    let mut counts = Counts::default();
+    for v in values {
+        // Values with higher magnitude are supported but not so typical for encoders because
+        // they only happen at high accelerations.
+        match v.get() {
+            // "No change" for all encoder types, so this doesn't tell us anything.
+            0 | 64 => {}
+            1..=7 => counts.low += 1,
+            121..=127 => counts.high += 1,
+            65..=71 => counts.above_center += 1,
+            57..=63 => counts.below_center += 1,
+            _ => counts.other += 1,
+        }
+    }
+    let total = counts.low + counts.high + counts.above_center + counts.below_center + counts.other;
+    if total == 0 {
+        return SourceCharacterGuess::new(RangeElement, 0.5);
+    }
+    let share = |count: usize| count as f64 / total as f64;
+    let both_directions = |inc: usize, dec: usize| if inc > 0 && dec > 0 { inc + dec } else { 0 };
+    let candidates = [
+        (Encoder1, both_directions(counts.low, counts.high)),
+        (
+            Encoder2,
+            both_directions(counts.above_center, counts.below_center),
+        ),
+        (Encoder3, both_directions(counts.low, counts.above_center)),
+    ];
+    let (best_character, best_count) =
+        candidates
+            .iter()
+            .copied()
+            .fold((RangeElement, 0), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+    if best_count > 0 && share(best_count) >= 0.5 {
+        return SourceCharacterGuess::new(best_character, share(best_count));
+    }
+    // Only one direction. Type 1 and 3 clockwise as well as type 2 and 3 counter-clockwise are
+    // not distinguishable, so we prefer the more common types 1 and 2 but are less confident.
+    let one_direction = [
+        (Encoder1, counts.low, 0.5),
+        (Encoder1, counts.high, 0.75),
+        (Encoder2, counts.above_center, 0.5),
+        (Encoder2, counts.below_center, 0.75),
+    ];
+    let (best_character, best_count, ambiguity_factor) =
+        one_direction
+            .iter()
+            .copied()
+            .fold((RangeElement, 0, 1.0), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+    if best_count > 0 && share(best_count) >= 0.5 {
+        SourceCharacterGuess::new(best_character, ambiguity_factor * share(best_count))
+    } else {
+        SourceCharacterGuess::new(RangeElement, share(counts.other))
     }
 }
 
@@ -353,7 +598,9 @@ mod tests {
 
     mod scanning {
         use super::*;
-        use helgoboss_midi::test_util::{channel, control_change, nrpn_14_bit, u14};
+        use helgoboss_midi::test_util::{
+            channel, control_change, controller_number, nrpn_14_bit, u14,
+        };
         use helgoboss_midi::{ParameterNumberMessage, ParameterNumberMessageScanner};
 
         #[test]
@@ -407,11 +654,71 @@ mod tests {
             );
             assert_eq!(source_4_short, None);
         }
+
+        #[test]
+        fn scan_fast_14_bit_sweep() {
+            // Given
+            let mut scanner = MidiScanner::default();
+            // When
+            let results: Vec<_> = (0..16)
+                .flat_map(|i| {
+                    [
+                        scanner.feed_short(control_change(1, 7, 60 + i), None),
+                        scanner.feed_short(control_change(1, 39, 8 * i), None),
+                    ]
+                })
+                .collect();
+            // Then
+            let (last, others) = results.split_last().unwrap();
+            assert!(others.iter().all(|r| r.is_none()));
+            let result = last.clone().unwrap();
+            assert_eq!(
+                result.value,
+                MidiSourceValue::ControlChange14Bit(ControlChange14BitMessage::new(
+                    channel(1),
+                    controller_number(7),
+                    u14(60 << 7)
+                ))
+            );
+            assert_eq!(
+                result.character.unwrap().character,
+                SourceCharacter::RangeElement
+            );
+        }
+
+        #[test]
+        fn scan_fast_14_bit_sweep_starting_with_lsb() {
+            // Given
+            let mut scanner = MidiScanner::default();
+            // When
+            let first = scanner.feed_short(control_change(1, 39, 0), None);
+            let results: Vec<_> = (0..15)
+                .flat_map(|i| {
+                    [
+                        scanner.feed_short(control_change(1, 7, 60 + i), None),
+                        scanner.feed_short(control_change(1, 39, 8 * i + 4), None),
+                    ]
+                })
+                .collect();
+            let last = scanner.feed_short(control_change(1, 7, 75), None);
+            // Then
+            assert_eq!(first, None);
+            assert!(results.iter().all(|r| r.is_none()));
+            let result = last.unwrap();
+            assert_eq!(
+                result.value,
+                MidiSourceValue::ControlChange14Bit(ControlChange14BitMessage::new(
+                    channel(1),
+                    controller_number(7),
+                    u14((60 << 7) | 4)
+                ))
+            );
+        }
     }
 
     mod source_character_guessing {
         use super::*;
-        use helgoboss_midi::test_util::u7;
+        use helgoboss_midi::test_util::{u14, u7};
         use SourceCharacter::*;
 
         #[test]
@@ -565,7 +872,102 @@ mod tests {
             assert_eq!(guess(&[100, 100, 100, 99, 99, 99, 98, 98]), RangeElement);
         }
 
+        #[test]
+        fn encoder_1_both_directions() {
+            assert_eq!(guess(&[1, 1, 2, 127, 127, 126]), Encoder1);
+            assert_eq!(confidence(&[1, 1, 2, 127, 127, 126]), 1.0);
+        }
+
+        #[test]
+        fn encoder_2_both_directions() {
+            assert_eq!(guess(&[65, 65, 66, 63, 63, 62]), Encoder2);
+            assert_eq!(confidence(&[65, 65, 66, 63, 63, 62]), 1.0);
+        }
+
+        #[test]
+        fn encoder_3_both_directions() {
+            assert_eq!(guess(&[1, 1, 2, 65, 65, 66]), Encoder3);
+            assert_eq!(confidence(&[1, 1, 2, 65, 65, 66]), 1.0);
+        }
+
+        #[test]
+        fn encoder_3_with_acceleration_both_directions() {
+            assert_eq!(guess(&[1, 3, 5, 3, 1, 65, 67, 66]), Encoder3);
+        }
+
+        #[test]
+        fn one_direction_is_less_confident() {
+            assert!(confidence(&[1, 1, 1, 1, 1]) < confidence(&[1, 1, 127, 127]));
+            assert!(confidence(&[65, 65, 65, 65]) < confidence(&[65, 65, 63, 63]));
+        }
+
+        #[test]
+        fn one_direction_is_uncertain() {
+            assert!(guess_with_confidence(&[1, 1, 1, 1, 1]).is_uncertain());
+            assert!(guess_with_confidence(&[65, 65, 65, 65]).is_uncertain());
+            assert!(!guess_with_confidence(&[1, 1, 127, 127]).is_uncertain());
+            assert!(!guess_with_confidence(&[100]).is_uncertain());
+        }
+
+        #[test]
+        fn range_back_and_forth() {
+            assert_eq!(guess(&[40, 45, 50, 45, 40, 35]), RangeElement);
+        }
+
+        #[test]
+        fn range_wiggled_around_center() {
+            // Given
+            let values = [60, 62, 64, 66, 68, 66, 64, 62, 60];
+            // When
+            let guess = guess_with_confidence(&values);
+            // Then
+            assert_eq!(guess.character, RangeElement);
+            assert!(!guess.is_uncertain());
+        }
+
+        #[test]
+        fn range_wiggled_around_center_in_small_steps() {
+            assert_eq!(
+                guess(&[61, 62, 63, 64, 65, 66, 67, 68, 67, 66, 65, 64, 63, 62, 61]),
+                RangeElement
+            );
+        }
+
+        #[test]
+        fn encoder_2_with_some_continuity_is_less_confident() {
+            // Given
+            let steady = [65, 65, 66, 63, 63, 62];
+            let partly_continuous = [65, 66, 67, 63, 63, 62];
+            // When
+            let steady_guess = guess_with_confidence(&steady);
+            let partly_continuous_guess = guess_with_confidence(&partly_continuous);
+            // Then
+            assert_eq!(steady_guess.character, Encoder2);
+            assert_eq!(partly_continuous_guess.character, Encoder2);
+            assert!(partly_continuous_guess.confidence < steady_guess.confidence);
+        }
+
+        #[test]
+        fn fourteen_bit_sweep() {
+            assert_eq!(
+                guess_14_bit_character(&[u14(1000), u14(1100), u14(1300)]).character,
+                RangeElement
+            );
+            assert_eq!(
+                guess_14_bit_character(&[u14(16383), u14(0)]).character,
+                MomentaryButton
+            );
+        }
+
         fn guess(values: &[u8]) -> SourceCharacter {
+            guess_with_confidence(values).character
+        }
+
+        fn confidence(values: &[u8]) -> f64 {
+            guess_with_confidence(values).confidence.get()
+        }
+
+        fn guess_with_confidence(values: &[u8]) -> SourceCharacterGuess {
             let u7_values: Vec<_> = values.iter().map(|v| u7(*v)).collect();
             guess_custom_character(&u7_values)
         }
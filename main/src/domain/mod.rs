@@ -22,12 +22,33 @@ pub use midi_source::*;
 mod eel_transformation;
 pub use eel_transformation::*;
 
+mod curve_transformation;
+pub use curve_transformation::*;
+
+mod lua_transformation;
+pub use lua_transformation::*;
+
+mod glide;
+pub use glide::*;
+
+mod feedback_color_map;
+pub use feedback_color_map::*;
+
+mod relative_acceleration;
+pub use relative_acceleration::*;
+
 mod eel_midi_source_script;
 pub use eel_midi_source_script::*;
 
 mod lua_midi_source_script;
 pub use lua_midi_source_script::*;
 
+mod lua_midi_control_script;
+pub use lua_midi_control_script::*;
+
+mod midi_control_script_source;
+pub use midi_control_script_source::*;
+
 mod flexible_midi_source_script;
 pub use flexible_midi_source_script::*;
 
@@ -96,6 +117,15 @@ pub use reaper_source::*;
 mod key_source;
 pub use key_source::*;
 
+mod chord_source;
+pub use chord_source::*;
+
+mod sequence_source;
+pub use sequence_source::*;
+
+#[cfg(test)]
+mod compound_source_test_util;
+
 mod device_change_detector;
 pub use device_change_detector::*;
 
@@ -122,3 +152,8 @@ pub use control_event::*;
 
 mod lua_support;
 pub use lua_support::*;
+
+#[cfg(feature = "realearn-metrics")]
+mod metrics_util;
+#[cfg(feature = "realearn-metrics")]
+pub use metrics_util::*;
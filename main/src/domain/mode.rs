@@ -1,3 +1,59 @@
-use crate::domain::{ControlEventTimestamp, EelTransformation};
+use crate::domain::{
+    ControlEventTimestamp, CurveTransformation, EelTransformation, LuaTransformation,
+    COMPARTMENT_PARAMETER_COUNT,
+};
+use helgoboss_learn::Transformation;
 
-pub type Mode = helgoboss_learn::Mode<EelTransformation, ControlEventTimestamp>;
+pub type Mode = helgoboss_learn::Mode<GlueTransformation, ControlEventTimestamp>;
+
+/// Additional input that's available to control and feedback transformations.
+pub struct AdditionalTransformationInput {
+    pub y_last: f64,
+    /// Normalized values of the compartment parameters.
+    pub params: [f64; COMPARTMENT_PARAMETER_COUNT as usize],
+}
+
+impl Default for AdditionalTransformationInput {
+    fn default() -> Self {
+        Self {
+            y_last: 0.0,
+            params: [0.0; COMPARTMENT_PARAMETER_COUNT as usize],
+        }
+    }
+}
+
+/// A control or feedback transformation, either scripted or declarative.
+#[derive(Clone, Debug)]
+pub enum GlueTransformation {
+    Eel(EelTransformation),
+    Lua(LuaTransformation<'static>),
+    Curve(CurveTransformation),
+}
+
+impl GlueTransformation {
+    /// Returns `true` if this transformation can't be executed in the real-time thread.
+    ///
+    /// This is the case for Lua transformations because the Lua state belongs to the main thread.
+    pub fn is_main_thread_only(&self) -> bool {
+        matches!(self, GlueTransformation::Lua(_))
+    }
+}
+
+impl Transformation for GlueTransformation {
+    type AdditionalInput = AdditionalTransformationInput;
+
+    fn transform(
+        &self,
+        input_value: f64,
+        output_value: f64,
+        additional_input: AdditionalTransformationInput,
+    ) -> Result<f64, &'static str> {
+        match self {
+            GlueTransformation::Eel(t) => t.transform(input_value, output_value, additional_input),
+            GlueTransformation::Lua(t) => t.transform(input_value, output_value, additional_input),
+            GlueTransformation::Curve(t) => {
+                t.transform(input_value, output_value, additional_input)
+            }
+        }
+    }
+}
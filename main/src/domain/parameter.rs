@@ -241,6 +241,15 @@ impl CompartmentParams {
         }
     }
 
+    /// Returns the raw values of all parameters.
+    pub fn raw_values(&self) -> [RawParamValue; COMPARTMENT_PARAMETER_COUNT as usize] {
+        let mut values = [0.0; COMPARTMENT_PARAMETER_COUNT as usize];
+        for (v, p) in values.iter_mut().zip(self.0.iter()) {
+            *v = p.value;
+        }
+        values
+    }
+
     /// Returns a map of all parameter settings that don't correspond to the defaults.
     pub fn non_default_settings(&self) -> Vec<(CompartmentParamIndex, ParamSetting)> {
         self.0
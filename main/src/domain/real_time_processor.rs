@@ -1,12 +1,12 @@
 use crate::domain::{
-    classify_midi_message, BasicSettings, Compartment, CompoundMappingSource, ControlEvent,
-    ControlEventTimestamp, ControlMainTask, ControlMode, ControlOptions, FeedbackSendBehavior,
-    Garbage, GarbageBin, InstanceId, LifecycleMidiMessage, LifecyclePhase, MappingId, MatchOutcome,
-    MidiClockCalculator, MidiEvent, MidiMessageClassification, MidiScanResult, MidiScanner,
-    MidiSendTarget, NormalRealTimeToMainThreadTask, OrderedMappingMap, OwnedIncomingMidiMessage,
-    PartialControlMatch, PersistentMappingProcessingState, QualifiedMappingId,
-    RealTimeCompoundMappingTarget, RealTimeControlContext, RealTimeMapping, RealTimeReaperTarget,
-    SampleOffset, SendMidiDestination, VirtualSourceValue,
+    classify_midi_message, BasicSettings, Compartment, ControlEvent, ControlEventTimestamp,
+    ControlMainTask, ControlMode, ControlOptions, ControlOutcome, FeedbackSendBehavior,
+    ForwardedMidiMessage, Garbage, GarbageBin, InstanceId, LifecycleMidiMessage, LifecyclePhase,
+    MappingId, MatchOutcome, MidiClockCalculator, MidiEvent, MidiMessageClassification,
+    MidiScanResult, MidiScanner, MidiSendTarget, NormalRealTimeToMainThreadTask, OrderedMappingMap,
+    OwnedIncomingMidiMessage, PartialControlMatch, PersistentMappingProcessingState,
+    QualifiedMappingId, RealTimeCompoundMappingTarget, RealTimeControlContext, RealTimeMapping,
+    RealTimeReaperTarget, SampleOffset, SendMidiDestination, VirtualSourceValue,
 };
 use helgoboss_learn::{ControlValue, MidiSourceValue, RawMidiEvent};
 use helgoboss_midi::{
@@ -52,6 +52,7 @@ pub struct RealTimeProcessor {
     feedback_task_sender: SenderToRealTimeThread<FeedbackRealTimeTask>,
     normal_main_task_sender: SenderToNormalThread<NormalRealTimeToMainThreadTask>,
     control_main_task_sender: SenderToNormalThread<ControlMainTask>,
+    forwarded_midi_sender: SenderToNormalThread<ControlEvent<ForwardedMidiMessage>>,
     garbage_bin: GarbageBin,
     // Scanners for more complex MIDI message types
     nrpn_scanner: PollingParameterNumberMessageScanner,
@@ -64,6 +65,8 @@ pub struct RealTimeProcessor {
     clip_matrix: Option<WeakMatrix>,
     clip_matrix_is_owned: bool,
     clip_record_task: Option<FxInputClipRecordTask>,
+    /// Cached when mappings are synced in order to not check all mappings for each MIDI message.
+    has_midi_control_script_sources: bool,
 }
 
 #[derive(Debug)]
@@ -82,6 +85,7 @@ impl RealTimeProcessor {
         feedback_task_sender: SenderToRealTimeThread<FeedbackRealTimeTask>,
         normal_main_task_sender: SenderToNormalThread<NormalRealTimeToMainThreadTask>,
         control_main_task_sender: SenderToNormalThread<ControlMainTask>,
+        forwarded_midi_sender: SenderToNormalThread<ControlEvent<ForwardedMidiMessage>>,
         garbage_bin: GarbageBin,
     ) -> RealTimeProcessor {
         use Compartment::*;
@@ -95,6 +99,7 @@ impl RealTimeProcessor {
             feedback_task_sender,
             normal_main_task_sender,
             control_main_task_sender,
+            forwarded_midi_sender,
             mappings: enum_map! {
                 Controller => ordered_map_with_capacity(1000),
                 Main => ordered_map_with_capacity(5000),
@@ -110,6 +115,7 @@ impl RealTimeProcessor {
             clip_matrix: None,
             clip_matrix_is_owned: false,
             clip_record_task: None,
+            has_midi_control_script_sources: false,
         }
     }
 
@@ -286,6 +292,7 @@ impl RealTimeProcessor {
                     self.mappings[compartment].extend(drained_mappings);
                     self.garbage_bin
                         .dispose(Garbage::RealTimeMappings(mappings));
+                    self.update_has_midi_control_script_sources();
                     // Handle activation MIDI
                     if self.processor_feedback_is_effectively_on() {
                         self.send_lifecycle_midi_for_all_mappings_in(
@@ -320,6 +327,7 @@ impl RealTimeProcessor {
                     if let Some(m) = old_mapping {
                         self.garbage_bin.dispose_real_time_mapping(m);
                     }
+                    self.update_has_midi_control_script_sources();
                 }
                 UpdatePersistentMappingProcessingState { id, state } => {
                     permit_alloc(|| {
@@ -722,6 +730,7 @@ impl RealTimeProcessor {
                     // we do the consumption check at a later state.
                     let plain_match_outcome =
                         self.process_incoming_midi_normal_plain(event, caller);
+                    self.forward_midi_to_control_script_sources(event.map_payload(|e| e.payload()));
                     let midi_event = event.payload();
                     let (nrpn_match_outcome, cc14_match_outcome) = match midi_event.payload() {
                         IncomingMidiMessage::Short(short_msg) => {
@@ -857,6 +866,25 @@ impl RealTimeProcessor {
         }
     }
 
+    fn forward_midi_to_control_script_sources(&self, evt: ControlEvent<IncomingMidiMessage>) {
+        if !self.has_midi_control_script_sources {
+            return;
+        }
+        // Lua scripts can't be executed in real-time. Let the main processor do the job.
+        let msg = match ForwardedMidiMessage::from_incoming(evt.payload()) {
+            None => return,
+            Some(m) => m,
+        };
+        self.forwarded_midi_sender
+            .send_if_space(evt.with_payload(msg));
+    }
+
+    fn update_has_midi_control_script_sources(&mut self) {
+        self.has_midi_control_script_sources = self
+            .all_mappings()
+            .any(|m| m.core.source.is_midi_control_script());
+    }
+
     /// Might allocate!
     fn log_real_learn_input(&self, evt: ControlEvent<IncomingMidiMessage>) {
         // It's okay if we crackle when logging input.
@@ -998,28 +1026,30 @@ impl RealTimeProcessor {
             // doesn't. Check again that it's a REAPER target.
             .filter(|m| m.control_is_effectively_on() && m.has_reaper_target())
         {
-            if let CompoundMappingSource::Midi(s) = &m.source() {
-                let midi_event = source_value_event.payload();
-                if let Some(control_value) = s.control(midi_event.payload()) {
-                    let _ = process_real_mapping(
-                        m,
-                        &self.control_main_task_sender,
-                        &self.feedback_task_sender,
-                        compartment,
-                        source_value_event
-                            .with_payload(MidiEvent::new(midi_event.offset(), control_value)),
-                        ControlOptions {
-                            enforce_target_refresh: match_outcome.matched(),
-                            ..Default::default()
-                        },
-                        caller,
-                        self.settings.midi_destination(),
-                        self.settings.real_output_logging_enabled,
-                        self.clip_matrix.as_ref(),
-                    );
-                    // It can't be consumed because we checked this before for all mappings.
-                    match_outcome = MatchOutcome::Matched;
-                }
+            let midi_event = source_value_event.payload();
+            if let Some(ControlOutcome::Matched(control_value)) = m
+                .core
+                .source
+                .control_midi(source_value_event.timestamp(), midi_event.payload())
+            {
+                let _ = process_real_mapping(
+                    m,
+                    &self.control_main_task_sender,
+                    &self.feedback_task_sender,
+                    compartment,
+                    source_value_event
+                        .with_payload(MidiEvent::new(midi_event.offset(), control_value)),
+                    ControlOptions {
+                        enforce_target_refresh: match_outcome.matched(),
+                        ..Default::default()
+                    },
+                    caller,
+                    self.settings.midi_destination(),
+                    self.settings.real_output_logging_enabled,
+                    self.clip_matrix.as_ref(),
+                );
+                // It can't be consumed because we checked this before for all mappings.
+                match_outcome = MatchOutcome::Matched;
             }
         }
         match_outcome
@@ -1472,17 +1502,19 @@ fn process_real_mapping(
     clip_matrix: Option<&WeakMatrix>,
 ) -> Result<(), &'static str> {
     let pure_control_event = flatten_control_midi_event(value_event);
+    let control_is_main_thread_only = mapping.control_is_main_thread_only();
     if let Some(RealTimeCompoundMappingTarget::Reaper(reaper_target)) =
         mapping.resolved_target.as_mut()
     {
-        if reaper_target.wants_real_time_control(caller) {
+        if reaper_target.wants_real_time_control(caller) && !control_is_main_thread_only {
             // Try to process directly here in real-time.
             let control_context = RealTimeControlContext { clip_matrix };
+            let accelerated_control_event = mapping.core.accelerate(pure_control_event);
             let control_value: Option<ControlValue> = mapping
                 .core
                 .mode
                 .control_with_options(
-                    pure_control_event,
+                    accelerated_control_event,
                     reaper_target,
                     control_context,
                     options.mode_control_options,
@@ -1657,27 +1689,29 @@ fn control_main_mappings_virtual(
         .values_mut()
         .filter(|m| m.control_is_effectively_on())
     {
-        if let CompoundMappingSource::Virtual(s) = &m.source() {
-            let midi_event = value_event.payload();
-            if let Some(control_value) = s.control(&midi_event.payload()) {
-                let _ = process_real_mapping(
-                    m,
-                    main_task_sender,
-                    rt_feedback_sender,
-                    Compartment::Main,
-                    value_event.with_payload(MidiEvent::new(midi_event.offset(), control_value)),
-                    ControlOptions {
-                        enforce_target_refresh: match_outcome.matched(),
-                        ..options
-                    },
-                    caller,
-                    midi_feedback_output,
-                    output_logging_enabled,
-                    matrix,
-                );
-                // If we find an associated main mapping, this is not just consumed, it's matched.
-                match_outcome = MatchOutcome::Matched;
-            }
+        let midi_event = value_event.payload();
+        if let Some(ControlOutcome::Matched(control_value)) = m
+            .core
+            .source
+            .control_virtual(value_event.timestamp(), &midi_event.payload())
+        {
+            let _ = process_real_mapping(
+                m,
+                main_task_sender,
+                rt_feedback_sender,
+                Compartment::Main,
+                value_event.with_payload(MidiEvent::new(midi_event.offset(), control_value)),
+                ControlOptions {
+                    enforce_target_refresh: match_outcome.matched(),
+                    ..options
+                },
+                caller,
+                midi_feedback_output,
+                output_logging_enabled,
+                matrix,
+            );
+            // If we find an associated main mapping, this is not just consumed, it's matched.
+            match_outcome = MatchOutcome::Matched;
         }
     }
     match_outcome
@@ -4,15 +4,16 @@ use crate::domain::ui_util::{
     parse_unit_value_from_percentage, OutputReason,
 };
 use crate::domain::{
-    AdditionalEelTransformationInput, AdditionalFeedbackEvent, DomainEventHandler, Exclusivity,
-    ExtendedProcessorContext, FeedbackAudioHookTask, FeedbackOutput, FeedbackRealTimeTask, GroupId,
-    InstanceId, InstanceStateChanged, MainMapping, MappingControlResult, MappingId,
-    OrderedMappingMap, OscFeedbackTask, ProcessorContext, RealTimeReaperTarget, ReaperTarget,
-    SharedInstanceState, Tag, TagScope, TargetCharacter, TrackExclusivity, ACTION_TARGET,
-    ALL_TRACK_FX_ENABLE_TARGET, ANY_ON_TARGET, AUTOMATION_MODE_OVERRIDE_TARGET, CLIP_COLUMN_TARGET,
-    CLIP_MANAGEMENT_TARGET, CLIP_MATRIX_TARGET, CLIP_ROW_TARGET, CLIP_SEEK_TARGET,
-    CLIP_TRANSPORT_TARGET, CLIP_VOLUME_TARGET, ENABLE_INSTANCES_TARGET, ENABLE_MAPPINGS_TARGET,
-    FX_ENABLE_TARGET, FX_NAVIGATE_TARGET, FX_ONLINE_TARGET, FX_OPEN_TARGET, FX_PARAMETER_TARGET,
+    AdditionalFeedbackEvent, AdditionalTransformationInput, CompartmentParams, DomainEventHandler,
+    Exclusivity, ExtendedProcessorContext, FeedbackAudioHookTask, FeedbackOutput,
+    FeedbackRealTimeTask, GroupId, InstanceId, InstanceStateChanged, MainMapping,
+    MappingControlResult, MappingId, OrderedMappingMap, OscFeedbackTask, ProcessorContext,
+    RealTimeReaperTarget, ReaperTarget, SharedInstanceState, Tag, TagScope, TargetCharacter,
+    TrackExclusivity, ACTION_TARGET, ALL_TRACK_FX_ENABLE_TARGET, ANY_ON_TARGET,
+    AUTOMATION_MODE_OVERRIDE_TARGET, CLIP_COLUMN_TARGET, CLIP_MANAGEMENT_TARGET,
+    CLIP_MATRIX_TARGET, CLIP_ROW_TARGET, CLIP_SEEK_TARGET, CLIP_TRANSPORT_TARGET,
+    CLIP_VOLUME_TARGET, ENABLE_INSTANCES_TARGET, ENABLE_MAPPINGS_TARGET, FX_ENABLE_TARGET,
+    FX_NAVIGATE_TARGET, FX_ONLINE_TARGET, FX_OPEN_TARGET, FX_PARAMETER_TARGET,
     FX_PARAMETER_TOUCH_STATE_TARGET, FX_PRESET_TARGET, GO_TO_BOOKMARK_TARGET,
     LOAD_FX_SNAPSHOT_TARGET, LOAD_MAPPING_SNAPSHOT_TARGET, MIDI_SEND_TARGET,
     NAVIGATE_WITHIN_GROUP_TARGET, OSC_SEND_TARGET, PLAYRATE_TARGET, ROUTE_AUTOMATION_MODE_TARGET,
@@ -395,11 +396,9 @@ impl<'a> RealTimeControlContext<'a> {
     }
 }
 
-impl<'a> TransformationInputProvider<AdditionalEelTransformationInput>
-    for RealTimeControlContext<'a>
-{
-    fn additional_input(&self) -> AdditionalEelTransformationInput {
-        AdditionalEelTransformationInput::default()
+impl<'a> TransformationInputProvider<AdditionalTransformationInput> for RealTimeControlContext<'a> {
+    fn additional_input(&self) -> AdditionalTransformationInput {
+        AdditionalTransformationInput::default()
     }
 }
 
@@ -421,18 +420,18 @@ impl<'a> ControlContext<'a> {
 pub struct MappingControlContext<'a> {
     pub control_context: ControlContext<'a>,
     pub mapping_data: MappingData,
+    pub compartment_params: &'a CompartmentParams,
 }
 
-impl<'a> TransformationInputProvider<AdditionalEelTransformationInput>
-    for MappingControlContext<'a>
-{
-    fn additional_input(&self) -> AdditionalEelTransformationInput {
-        AdditionalEelTransformationInput {
+impl<'a> TransformationInputProvider<AdditionalTransformationInput> for MappingControlContext<'a> {
+    fn additional_input(&self) -> AdditionalTransformationInput {
+        AdditionalTransformationInput {
             y_last: self
                 .mapping_data
                 .last_non_performance_target_value
                 .map(|v| v.to_unit_value().get())
                 .unwrap_or_default(),
+            params: self.compartment_params.raw_values().map(|v| v as f64),
         }
     }
 }
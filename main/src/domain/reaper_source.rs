@@ -1,20 +1,37 @@
+use crate::domain::{Compartment, CompartmentParamIndex};
 use core::fmt;
 use derive_more::Display;
+use enum_iterator::IntoEnumIterator;
 use helgoboss_learn::{
     format_percentage_without_unit, parse_percentage_without_unit, ControlValue,
     DetailedSourceCharacter, SourceCharacter, UnitValue,
 };
-use reaper_medium::{MidiInputDeviceId, MidiOutputDeviceId};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use reaper_high::{Project, Reaper};
+use reaper_medium::{MidiInputDeviceId, MidiOutputDeviceId, PlayState};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::f64::consts::PI;
 use std::fmt::{Display, Formatter};
-use std::time::{Duration, Instant};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ReaperSource {
     MidiDeviceChanges,
     RealearnInstanceStart,
+    TrackSelectionChanges,
+    PlayStateChanges,
+    RecordStateChanges,
+    ProjectSwitch,
+    FxFocusChanges,
+    MarkerPassed,
+    CompartmentParameterValue(CompartmentParamIndex),
     Timer(TimerSource),
+    Lfo(LfoSource),
+    Beat(BeatSource),
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -51,10 +68,415 @@ impl TimerSource {
     }
 }
 
+/// Low-frequency oscillator which continuously emits absolute values.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LfoSource {
+    waveform: LfoWaveform,
+    rate: LfoRate,
+    /// Phase offset in cycles.
+    phase: UnitValue,
+    /// How far the values swing around the center.
+    depth: UnitValue,
+    /// Current position within the cycle (without phase offset).
+    position: f64,
+    last_poll: Option<Instant>,
+    last_value: Option<UnitValue>,
+    /// Current value of the sample-and-hold waveform.
+    held_value: f64,
+    random_state: u64,
+}
+
+impl LfoSource {
+    pub fn new(waveform: LfoWaveform, rate: LfoRate, phase: UnitValue, depth: UnitValue) -> Self {
+        Self {
+            waveform,
+            rate,
+            phase,
+            depth,
+            position: 0.0,
+            last_poll: None,
+            last_value: None,
+            held_value: 0.5,
+            random_state: create_random_seed(),
+        }
+    }
+
+    /// Returns a value only if it's different from the previously returned one.
+    pub fn poll(&mut self, project: Project) -> Option<ControlValue> {
+        let now = Instant::now();
+        let elapsed = match self.last_poll.replace(now) {
+            None => Duration::ZERO,
+            Some(last_poll) => now - last_poll,
+        };
+        let frequency = self.rate.frequency(|| project.tempo().bpm().get());
+        self.advance(elapsed.as_secs_f64() * frequency)
+    }
+
+    fn advance(&mut self, cycles: f64) -> Option<ControlValue> {
+        let new_position = self.position + cycles;
+        if new_position >= 1.0 || self.last_value.is_none() {
+            self.held_value = self.next_random_value();
+        }
+        self.position = new_position.fract();
+        let value = UnitValue::new_clamped(self.current_value());
+        if self.last_value == Some(value) {
+            return None;
+        }
+        self.last_value = Some(value);
+        Some(ControlValue::AbsoluteContinuous(value))
+    }
+
+    fn current_value(&self) -> f64 {
+        let p = (self.position + self.phase.get()).fract();
+        use LfoWaveform::*;
+        let raw = match self.waveform {
+            Sine => 0.5 + 0.5 * (2.0 * PI * p).sin(),
+            Triangle => 1.0 - (2.0 * p - 1.0).abs(),
+            Saw => p,
+            Square => {
+                if p < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            SampleAndHold => self.held_value,
+        };
+        0.5 + (raw - 0.5) * self.depth.get()
+    }
+
+    /// Xorshift, good enough for modulation purposes.
+    fn next_random_value(&mut self) -> f64 {
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.random_state = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Seeds each LFO differently (based on the creation time), so that multiple sample-and-hold LFOs
+/// don't emit the same sequence of values.
+fn create_random_seed() -> u64 {
+    // Makes sure that LFOs created within the same clock tick get different seeds as well
+    static CREATION_COUNT: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    let count = CREATION_COUNT.fetch_add(1, Ordering::Relaxed);
+    // SplitMix64 finalizer, spreads similar inputs over the whole range
+    let mut z = (nanos ^ count.rotate_left(32)).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    // Xorshift gets stuck at zero
+    (z ^ (z >> 31)).max(1)
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    IntoEnumIterator,
+    TryFromPrimitive,
+    IntoPrimitive,
+    Display,
+)]
+#[repr(usize)]
+pub enum LfoWaveform {
+    #[serde(rename = "sine")]
+    #[display(fmt = "Sine")]
+    Sine,
+    #[serde(rename = "triangle")]
+    #[display(fmt = "Triangle")]
+    Triangle,
+    #[serde(rename = "saw")]
+    #[display(fmt = "Saw")]
+    Saw,
+    #[serde(rename = "square")]
+    #[display(fmt = "Square")]
+    Square,
+    #[serde(rename = "sample-and-hold")]
+    #[display(fmt = "Random (sample & hold)")]
+    SampleAndHold,
+}
+
+impl Default for LfoWaveform {
+    fn default() -> Self {
+        Self::Sine
+    }
+}
+
+/// Speed of an LFO, either absolute or relative to the project tempo.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum LfoRate {
+    /// Cycles per second.
+    #[serde(rename = "hertz")]
+    Hertz { frequency: f64 },
+    /// One cycle takes the given fraction of a whole note, e.g. 1/4 = one beat in 4/4.
+    #[serde(rename = "tempo-synced")]
+    TempoSynced { numerator: u32, denominator: u32 },
+}
+
+impl Default for LfoRate {
+    fn default() -> Self {
+        Self::Hertz { frequency: 1.0 }
+    }
+}
+
+impl LfoRate {
+    /// Returns the frequency in Hz. The tempo is only queried if the rate is tempo-synced.
+    pub fn frequency(&self, get_tempo_bpm: impl FnOnce() -> f64) -> f64 {
+        match *self {
+            LfoRate::Hertz { frequency } => frequency.max(0.0),
+            LfoRate::TempoSynced {
+                numerator,
+                denominator,
+            } => {
+                if numerator == 0 {
+                    return 0.0;
+                }
+                let quarter_notes_per_cycle = 4.0 * numerator as f64 / denominator as f64;
+                get_tempo_bpm() / 60.0 / quarter_notes_per_cycle
+            }
+        }
+    }
+}
+
+impl Display for LfoRate {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LfoRate::Hertz { frequency } => write!(f, "{} Hz", frequency),
+            LfoRate::TempoSynced {
+                numerator,
+                denominator,
+            } => write!(f, "{}/{}", numerator, denominator),
+        }
+    }
+}
+
+impl FromStr for LfoRate {
+    type Err = &'static str;
+
+    /// Parses note divisions such as "1/4" or frequencies such as "2.5 Hz" or "2.5".
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if let Some((numerator, denominator)) = text.split_once('/') {
+            let numerator = numerator.trim().parse().map_err(|_| "invalid numerator")?;
+            let denominator: u32 = denominator
+                .trim()
+                .parse()
+                .map_err(|_| "invalid denominator")?;
+            if denominator == 0 {
+                return Err("denominator must not be zero");
+            }
+            return Ok(LfoRate::TempoSynced {
+                numerator,
+                denominator,
+            });
+        }
+        let number = text
+            .trim_end_matches(|c: char| c.is_alphabetic())
+            .trim_end();
+        let frequency: f64 = number.parse().map_err(|_| "invalid frequency")?;
+        if !frequency.is_finite() || frequency < 0.0 {
+            return Err("frequency must be a positive number");
+        }
+        Ok(LfoRate::Hertz { frequency })
+    }
+}
+
+/// Fires whenever the play position crosses a bar, beat or subdivision boundary.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BeatSource {
+    grid: BeatGrid,
+    /// Number of steps per beat if the grid is [`BeatGrid::Subdivision`].
+    subdivisions: u32,
+    only_while_playing: bool,
+    output: BeatSourceOutput,
+    last_step: Option<BeatStep>,
+    last_position_in_bar: Option<UnitValue>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct BeatStep {
+    measure_index: i32,
+    index_in_bar: u32,
+}
+
+/// Musical position of a project, as far as relevant for the beat source.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BeatPosition {
+    pub is_playing: bool,
+    pub measure_index: i32,
+    pub beats_since_measure: f64,
+    pub beats_per_measure: u32,
+}
+
+impl BeatPosition {
+    /// Uses the same reference position as the beat feedback events of the control surface.
+    pub fn of_project(project: Project) -> Self {
+        let pos = project.play_position_next_audio_block();
+        let res = Reaper::get()
+            .medium_reaper()
+            .time_map_2_time_to_beats(project.context(), pos);
+        Self {
+            is_playing: project.play_state().is_playing,
+            measure_index: res.measure_index,
+            beats_since_measure: res.beats_since_measure.get(),
+            beats_per_measure: res.time_signature.numerator.get(),
+        }
+    }
+}
+
+impl BeatSource {
+    pub fn new(
+        grid: BeatGrid,
+        subdivisions: u32,
+        only_while_playing: bool,
+        output: BeatSourceOutput,
+    ) -> Self {
+        Self {
+            grid,
+            subdivisions,
+            only_while_playing,
+            output,
+            last_step: None,
+            last_position_in_bar: None,
+        }
+    }
+
+    pub fn output(&self) -> BeatSourceOutput {
+        self.output
+    }
+
+    pub fn poll(&mut self, project: Project) -> Option<ControlValue> {
+        self.process(BeatPosition::of_project(project))
+    }
+
+    /// With output [`BeatSourceOutput::Trigger`], this returns a value only when reaching a new
+    /// step of the grid. With [`BeatSourceOutput::PositionInBar`], it returns a value whenever the
+    /// position changed.
+    fn process(&mut self, pos: BeatPosition) -> Option<ControlValue> {
+        if self.only_while_playing && !pos.is_playing {
+            self.last_step = None;
+            self.last_position_in_bar = None;
+            return None;
+        }
+        let value = match self.output {
+            BeatSourceOutput::Trigger => {
+                let step = self.step_at(pos);
+                if self.last_step.replace(step) == Some(step) {
+                    return None;
+                }
+                ControlValue::AbsoluteContinuous(UnitValue::MAX)
+            }
+            BeatSourceOutput::PositionInBar => {
+                let position_in_bar = UnitValue::new_clamped(
+                    pos.beats_since_measure / pos.beats_per_measure.max(1) as f64,
+                );
+                if self.last_position_in_bar.replace(position_in_bar) == Some(position_in_bar) {
+                    return None;
+                }
+                ControlValue::AbsoluteContinuous(position_in_bar)
+            }
+        };
+        Some(value)
+    }
+
+    fn step_at(&self, pos: BeatPosition) -> BeatStep {
+        let steps_per_beat = match self.grid {
+            BeatGrid::Bar => 0,
+            BeatGrid::Beat => 1,
+            BeatGrid::Subdivision => self.subdivisions.max(1),
+        };
+        let steps_per_bar = (pos.beats_per_measure * steps_per_beat).max(1);
+        let index_in_bar = (pos.beats_since_measure * steps_per_beat as f64)
+            .floor()
+            .max(0.0) as u32;
+        BeatStep {
+            measure_index: pos.measure_index,
+            index_in_bar: index_in_bar.min(steps_per_bar - 1),
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    IntoEnumIterator,
+    TryFromPrimitive,
+    IntoPrimitive,
+    Display,
+)]
+#[repr(usize)]
+pub enum BeatGrid {
+    #[serde(rename = "bar")]
+    #[display(fmt = "Bar")]
+    Bar,
+    #[serde(rename = "beat")]
+    #[display(fmt = "Beat")]
+    Beat,
+    #[serde(rename = "subdivision")]
+    #[display(fmt = "Subdivision")]
+    Subdivision,
+}
+
+impl Default for BeatGrid {
+    fn default() -> Self {
+        Self::Beat
+    }
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    IntoEnumIterator,
+    TryFromPrimitive,
+    IntoPrimitive,
+    Display,
+)]
+#[repr(usize)]
+pub enum BeatSourceOutput {
+    /// Emits 100% on each step.
+    #[serde(rename = "trigger")]
+    #[display(fmt = "Trigger")]
+    Trigger,
+    /// Continuously emits the play position within the bar (0% = start, 100% = end).
+    #[serde(rename = "position-in-bar")]
+    #[display(fmt = "Position in bar")]
+    PositionInBar,
+}
+
+impl Default for BeatSourceOutput {
+    fn default() -> Self {
+        Self::Trigger
+    }
+}
+
 impl ReaperSource {
     /// If this returns `true`, the `poll` method should be called, on a regular basis.
     pub fn wants_to_be_polled(&self) -> bool {
-        matches!(self, ReaperSource::Timer(_))
+        matches!(
+            self,
+            ReaperSource::Timer(_) | ReaperSource::Lfo(_) | ReaperSource::Beat(_)
+        )
     }
 
     pub fn possible_detailed_characters(&self) -> Vec<DetailedSourceCharacter> {
@@ -62,7 +484,17 @@ impl ReaperSource {
         match self {
             MidiDeviceChanges => vec![DetailedSourceCharacter::MomentaryOnOffButton],
             RealearnInstanceStart => vec![DetailedSourceCharacter::MomentaryOnOffButton],
+            TrackSelectionChanges | PlayStateChanges | RecordStateChanges | FxFocusChanges => {
+                vec![DetailedSourceCharacter::MomentaryOnOffButton]
+            }
+            ProjectSwitch | MarkerPassed => vec![DetailedSourceCharacter::PressOnlyButton],
+            CompartmentParameterValue(_) => vec![DetailedSourceCharacter::RangeControl],
             Timer(_) => vec![DetailedSourceCharacter::PressOnlyButton],
+            Lfo(_) => vec![DetailedSourceCharacter::RangeControl],
+            Beat(s) => match s.output() {
+                BeatSourceOutput::Trigger => vec![DetailedSourceCharacter::PressOnlyButton],
+                BeatSourceOutput::PositionInBar => vec![DetailedSourceCharacter::RangeControl],
+            },
         }
     }
 
@@ -76,14 +508,23 @@ impl ReaperSource {
     }
 
     pub fn character(&self) -> SourceCharacter {
-        SourceCharacter::MomentaryButton
+        match self {
+            ReaperSource::Lfo(_) | ReaperSource::CompartmentParameterValue(_) => {
+                SourceCharacter::RangeElement
+            }
+            ReaperSource::Beat(s) if s.output() == BeatSourceOutput::PositionInBar => {
+                SourceCharacter::RangeElement
+            }
+            _ => SourceCharacter::MomentaryButton,
+        }
     }
 
-    pub fn poll(&mut self) -> Option<ControlValue> {
-        if let ReaperSource::Timer(t) = self {
-            t.poll()
-        } else {
-            None
+    pub fn poll(&mut self, project: Project) -> Option<ControlValue> {
+        match self {
+            ReaperSource::Timer(t) => t.poll(),
+            ReaperSource::Lfo(l) => l.poll(project),
+            ReaperSource::Beat(b) => b.poll(project),
+            _ => None,
         }
     }
 
@@ -104,11 +545,43 @@ impl ReaperSource {
                 }
                 _ => return None,
             },
+            TrackSelectionChanged { is_selected, .. } => match self {
+                ReaperSource::TrackSelectionChanges => on_off(*is_selected),
+                _ => return None,
+            },
+            PlayStateChanged { new_value, .. } => match self {
+                ReaperSource::PlayStateChanges => on_off(new_value.is_playing),
+                ReaperSource::RecordStateChanges => on_off(new_value.is_recording),
+                _ => return None,
+            },
+            ProjectSwitched => match self {
+                ReaperSource::ProjectSwitch => ControlValue::AbsoluteContinuous(UnitValue::MAX),
+                _ => return None,
+            },
+            FxFocusChanged { is_focused } => match self {
+                ReaperSource::FxFocusChanges => on_off(*is_focused),
+                _ => return None,
+            },
+            MarkerPassed { .. } => match self {
+                ReaperSource::MarkerPassed => ControlValue::AbsoluteContinuous(UnitValue::MAX),
+                _ => return None,
+            },
+            CompartmentParameterValueChanged { index, value, .. } => match self {
+                ReaperSource::CompartmentParameterValue(i) if i == index => {
+                    ControlValue::AbsoluteContinuous(*value)
+                }
+                _ => return None,
+            },
         };
         Some(control_value)
     }
 }
 
+fn on_off(on: bool) -> ControlValue {
+    let value = if on { UnitValue::MAX } else { UnitValue::MIN };
+    ControlValue::AbsoluteContinuous(value)
+}
+
 #[derive(PartialEq, Debug, Display)]
 pub enum ReaperMessage {
     #[display(fmt = "MidiDevicesConnected ({})", _0)]
@@ -116,6 +589,69 @@ pub enum ReaperMessage {
     #[display(fmt = "MidiDevicesDisconnected ({})", _0)]
     MidiDevicesDisconnected(MidiDeviceChangePayload),
     RealearnInstanceStarted,
+    #[display(fmt = "TrackSelectionChanged (selected: {})", is_selected)]
+    TrackSelectionChanged {
+        project: Project,
+        is_selected: bool,
+    },
+    #[display(fmt = "PlayStateChanged ({:?})", new_value)]
+    PlayStateChanged {
+        project: Project,
+        new_value: PlayState,
+    },
+    ProjectSwitched,
+    #[display(fmt = "FxFocusChanged (focused: {})", is_focused)]
+    FxFocusChanged {
+        is_focused: bool,
+    },
+    #[display(fmt = "MarkerPassed (index: {})", marker_index)]
+    MarkerPassed {
+        project: Project,
+        marker_index: u32,
+    },
+    #[display(
+        fmt = "CompartmentParameterValueChanged ({} parameter {}: {})",
+        compartment,
+        index,
+        value
+    )]
+    CompartmentParameterValueChanged {
+        compartment: Compartment,
+        index: CompartmentParamIndex,
+        value: UnitValue,
+    },
+}
+
+impl ReaperMessage {
+    /// Returns the project to which this message is related, if any.
+    ///
+    /// Instances should only react to project-related messages of their own project.
+    pub fn project(&self) -> Option<Project> {
+        use ReaperMessage::*;
+        match self {
+            TrackSelectionChanged { project, .. }
+            | PlayStateChanged { project, .. }
+            | MarkerPassed { project, .. } => Some(*project),
+            MidiDevicesConnected(_)
+            | MidiDevicesDisconnected(_)
+            | RealearnInstanceStarted
+            | ProjectSwitched
+            | FxFocusChanged { .. }
+            | CompartmentParameterValueChanged { .. } => None,
+        }
+    }
+
+    /// Returns the compartment to which this message is related, if any.
+    ///
+    /// Mappings should only react to compartment-related messages of their own compartment.
+    pub fn compartment(&self) -> Option<Compartment> {
+        match self {
+            ReaperMessage::CompartmentParameterValueChanged { compartment, .. } => {
+                Some(*compartment)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug)]
@@ -133,3 +669,184 @@ impl Display for MidiDeviceChangePayload {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lfo_rate() {
+        assert_eq!(
+            "1/4".parse(),
+            Ok(LfoRate::TempoSynced {
+                numerator: 1,
+                denominator: 4
+            })
+        );
+        assert_eq!("2.5 Hz".parse(), Ok(LfoRate::Hertz { frequency: 2.5 }));
+        assert_eq!("3".parse(), Ok(LfoRate::Hertz { frequency: 3.0 }));
+        assert!("1/0".parse::<LfoRate>().is_err());
+        assert!("-1".parse::<LfoRate>().is_err());
+    }
+
+    #[test]
+    fn tempo_synced_lfo_frequency() {
+        let rate = LfoRate::TempoSynced {
+            numerator: 1,
+            denominator: 4,
+        };
+        // One beat at 120 bpm takes half a second
+        assert_eq!(rate.frequency(|| 120.0), 2.0);
+    }
+
+    #[test]
+    fn lfo_waveforms() {
+        // Given
+        let mut saw = LfoSource::new(
+            LfoWaveform::Saw,
+            Default::default(),
+            UnitValue::MIN,
+            UnitValue::MAX,
+        );
+        let mut square = LfoSource::new(
+            LfoWaveform::Square,
+            Default::default(),
+            UnitValue::new(0.5),
+            UnitValue::new(0.5),
+        );
+        // When
+        // Then
+        assert_eq!(saw.advance(0.0), Some(abs(0.0)));
+        assert_eq!(saw.advance(0.25), Some(abs(0.25)));
+        assert_eq!(saw.advance(1.0), None);
+        assert_eq!(square.advance(0.0), Some(abs(0.25)));
+        assert_eq!(square.advance(0.25), None);
+        assert_eq!(square.advance(0.25), Some(abs(0.75)));
+    }
+
+    #[test]
+    fn lfo_sample_and_hold() {
+        // Given
+        let mut lfo = LfoSource::new(
+            LfoWaveform::SampleAndHold,
+            Default::default(),
+            UnitValue::MIN,
+            UnitValue::MAX,
+        );
+        // When
+        let first = lfo.advance(0.0);
+        // Then
+        assert!(first.is_some());
+        assert_eq!(lfo.advance(0.5), None);
+        assert_ne!(lfo.advance(0.5), None);
+    }
+
+    #[test]
+    fn lfo_sample_and_hold_differs_between_instances() {
+        // Given
+        let create_lfo = || {
+            LfoSource::new(
+                LfoWaveform::SampleAndHold,
+                Default::default(),
+                UnitValue::MIN,
+                UnitValue::MAX,
+            )
+        };
+        let mut lfo_1 = create_lfo();
+        let mut lfo_2 = create_lfo();
+        // When
+        let sequence_1: Vec<_> = (0..4).map(|_| lfo_1.advance(1.0)).collect();
+        let sequence_2: Vec<_> = (0..4).map(|_| lfo_2.advance(1.0)).collect();
+        // Then
+        assert_ne!(sequence_1, sequence_2);
+    }
+
+    #[test]
+    fn beat_source_fires_on_subdivisions() {
+        // Given
+        let mut source = BeatSource::new(BeatGrid::Subdivision, 2, true, BeatSourceOutput::Trigger);
+        // When
+        // Then
+        assert_eq!(source.process(pos(false, 0, 0.0)), None);
+        assert_eq!(source.process(pos(true, 0, 0.0)), Some(abs(1.0)));
+        assert_eq!(source.process(pos(true, 0, 0.4)), None);
+        assert_eq!(source.process(pos(true, 0, 0.5)), Some(abs(1.0)));
+        assert_eq!(source.process(pos(true, 0, 3.9)), Some(abs(1.0)));
+    }
+
+    #[test]
+    fn beat_source_emits_position_in_bar() {
+        // Given
+        let mut source = BeatSource::new(BeatGrid::Bar, 0, true, BeatSourceOutput::PositionInBar);
+        // When
+        // Then
+        assert_eq!(source.process(pos(false, 0, 1.0)), None);
+        assert_eq!(source.process(pos(true, 0, 0.0)), Some(abs(0.0)));
+        assert_eq!(source.process(pos(true, 0, 1.0)), Some(abs(0.25)));
+        assert_eq!(source.process(pos(true, 0, 1.0)), None);
+        assert_eq!(source.process(pos(true, 0, 3.0)), Some(abs(0.75)));
+        assert_eq!(source.process(pos(true, 1, 0.0)), Some(abs(0.0)));
+    }
+
+    #[test]
+    fn beat_source_fires_on_bars() {
+        // Given
+        let mut source = BeatSource::new(BeatGrid::Bar, 0, false, BeatSourceOutput::Trigger);
+        // When
+        // Then
+        assert_eq!(source.process(pos(false, 2, 1.0)), Some(abs(1.0)));
+        assert_eq!(source.process(pos(true, 2, 3.0)), None);
+        assert_eq!(source.process(pos(true, 3, 0.0)), Some(abs(1.0)));
+    }
+
+    fn pos(is_playing: bool, measure_index: i32, beats_since_measure: f64) -> BeatPosition {
+        BeatPosition {
+            is_playing,
+            measure_index,
+            beats_since_measure,
+            beats_per_measure: 4,
+        }
+    }
+
+    #[test]
+    fn project_event_sources() {
+        // Given
+        let mut fx_focus = ReaperSource::FxFocusChanges;
+        let mut project_switch = ReaperSource::ProjectSwitch;
+        let focused = ReaperMessage::FxFocusChanged { is_focused: true };
+        let unfocused = ReaperMessage::FxFocusChanged { is_focused: false };
+        // When
+        // Then
+        assert_eq!(fx_focus.control(&focused), Some(abs(1.0)));
+        assert_eq!(fx_focus.control(&unfocused), Some(abs(0.0)));
+        assert_eq!(fx_focus.control(&ReaperMessage::ProjectSwitched), None);
+        assert_eq!(
+            project_switch.control(&ReaperMessage::ProjectSwitched),
+            Some(abs(1.0))
+        );
+        assert_eq!(project_switch.control(&focused), None);
+        assert_eq!(ReaperMessage::ProjectSwitched.project(), None);
+    }
+
+    #[test]
+    fn compartment_parameter_value() {
+        // Given
+        let index = CompartmentParamIndex::try_from(2).unwrap();
+        let other_index = CompartmentParamIndex::try_from(3).unwrap();
+        let mut source = ReaperSource::CompartmentParameterValue(index);
+        let msg = |index| ReaperMessage::CompartmentParameterValueChanged {
+            compartment: Compartment::Main,
+            index,
+            value: UnitValue::new(0.25),
+        };
+        // When
+        // Then
+        assert_eq!(source.control(&msg(index)), Some(abs(0.25)));
+        assert_eq!(source.control(&msg(other_index)), None);
+        assert_eq!(msg(index).compartment(), Some(Compartment::Main));
+    }
+
+    fn abs(v: f64) -> ControlValue {
+        ControlValue::AbsoluteContinuous(UnitValue::new(v))
+    }
+}
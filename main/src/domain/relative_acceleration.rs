@@ -0,0 +1,164 @@
+use crate::domain::ControlEventTimestamp;
+use helgoboss_learn::{ControlValue, DiscreteIncrement};
+use std::time::Duration;
+
+/// Tick rate (ticks per second) up to which increments are not accelerated.
+const SLOW_TICK_RATE: f64 = 10.0;
+/// Tick rate (ticks per second) from which on increments get the maximum acceleration.
+const FAST_TICK_RATE: f64 = 100.0;
+/// Upper limit for the configurable maximum factor. Larger factors don't make sense because
+/// they would make the step size jump from minimum to maximum anyway.
+pub const MAX_ACCELERATION_FACTOR: u32 = 100;
+
+/// Software acceleration for relative encoders which don't accelerate by themselves.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RelativeAcceleration {
+    pub profile: AccelerationProfile,
+    /// Factor by which increments get multiplied when turning the encoder very fast.
+    pub max_factor: u32,
+}
+
+/// Determines how the turning speed translates into the acceleration factor.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AccelerationProfile {
+    /// The factor grows proportionally with the turning speed.
+    Linear,
+    /// The factor stays small at moderate speeds and grows steeply at high speeds.
+    Exponential,
+    /// The factor doubles in a few coarse steps, similar to Mackie Control V-Pots.
+    MackieLike,
+}
+
+impl RelativeAcceleration {
+    /// Returns the factor for a tick which arrives the given time after the previous tick.
+    pub fn factor(&self, tick_interval: Duration) -> u32 {
+        let max_factor = self.max_factor.clamp(1, MAX_ACCELERATION_FACTOR);
+        let max = max_factor as f64;
+        let tick_rate = 1.0 / tick_interval.as_secs_f64();
+        // 0.0 = slow, 1.0 = fast
+        let speed =
+            ((tick_rate - SLOW_TICK_RATE) / (FAST_TICK_RATE - SLOW_TICK_RATE)).clamp(0.0, 1.0);
+        let factor = match self.profile {
+            AccelerationProfile::Linear => 1.0 + (max - 1.0) * speed,
+            AccelerationProfile::Exponential => max.powf(speed),
+            AccelerationProfile::MackieLike => 2f64.powf((speed * 3.0).floor()),
+        };
+        (factor.round() as u32).clamp(1, max_factor)
+    }
+}
+
+/// Multiplies incoming relative increments depending on the time since the previous one.
+#[derive(Clone, Debug)]
+pub struct RelativeAccelerator {
+    acceleration: RelativeAcceleration,
+    last_tick: Option<Tick>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Tick {
+    timestamp: ControlEventTimestamp,
+    is_positive: bool,
+}
+
+impl RelativeAccelerator {
+    pub fn new(acceleration: RelativeAcceleration) -> Self {
+        Self {
+            acceleration,
+            last_tick: None,
+        }
+    }
+
+    /// Accelerates the given value if it's relative. Other values are returned unchanged.
+    pub fn accelerate(
+        &mut self,
+        value: ControlValue,
+        timestamp: ControlEventTimestamp,
+    ) -> ControlValue {
+        let increment = match value {
+            ControlValue::Relative(i) => i,
+            _ => return value,
+        };
+        let is_positive = increment.is_positive();
+        let factor = match self.last_tick {
+            // A change of direction usually means fine adjustment, so we don't accelerate.
+            Some(last) if last.is_positive == is_positive => {
+                self.acceleration.factor(timestamp - last.timestamp)
+            }
+            _ => 1,
+        };
+        self.last_tick = Some(Tick {
+            timestamp,
+            is_positive,
+        });
+        ControlValue::Relative(DiscreteIncrement::new(
+            increment.get().saturating_mul(factor as i32),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles() {
+        // Given
+        let acceleration = |profile| RelativeAcceleration {
+            profile,
+            max_factor: 8,
+        };
+        let linear = acceleration(AccelerationProfile::Linear);
+        let exponential = acceleration(AccelerationProfile::Exponential);
+        let mackie_like = acceleration(AccelerationProfile::MackieLike);
+        let slow = Duration::from_millis(200);
+        // 64 ticks per second
+        let medium = Duration::from_secs_f64(1.0 / 64.0);
+        let fast = Duration::from_millis(5);
+        // When
+        // Then
+        assert_eq!(linear.factor(slow), 1);
+        assert_eq!(linear.factor(medium), 5);
+        assert_eq!(linear.factor(fast), 8);
+        assert_eq!(exponential.factor(slow), 1);
+        assert_eq!(exponential.factor(medium), 3);
+        assert_eq!(exponential.factor(fast), 8);
+        assert_eq!(mackie_like.factor(slow), 1);
+        assert_eq!(mackie_like.factor(medium), 2);
+        assert_eq!(mackie_like.factor(fast), 8);
+    }
+
+    #[test]
+    fn direction_change_is_not_accelerated() {
+        // Given
+        let mut accelerator = RelativeAccelerator::new(RelativeAcceleration {
+            profile: AccelerationProfile::Linear,
+            max_factor: 10,
+        });
+        let inc = |i| ControlValue::Relative(DiscreteIncrement::new(i));
+        let now = ControlEventTimestamp::now();
+        // When
+        let first = accelerator.accelerate(inc(1), now);
+        let same_direction = accelerator.accelerate(inc(2), now);
+        let other_direction = accelerator.accelerate(inc(-1), now);
+        // Then
+        assert_eq!(first, inc(1));
+        assert_eq!(same_direction, inc(20));
+        assert_eq!(other_direction, inc(-1));
+    }
+
+    #[test]
+    fn huge_values() {
+        // Given
+        let mut accelerator = RelativeAccelerator::new(RelativeAcceleration {
+            profile: AccelerationProfile::Linear,
+            max_factor: u32::MAX,
+        });
+        let inc = |i| ControlValue::Relative(DiscreteIncrement::new(i));
+        let now = ControlEventTimestamp::now();
+        // When
+        accelerator.accelerate(inc(i32::MAX), now);
+        let accelerated = accelerator.accelerate(inc(i32::MAX), now);
+        // Then
+        assert_eq!(accelerated, inc(i32::MAX));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::domain::{CompoundMappingSource, ControlEventTimestamp, ControlOutcome};
+use helgoboss_learn::{ControlValue, UnitValue};
+use std::time::Duration;
+
+/// A source which fires when several sources emit messages in a certain order, e.g. "A, then B".
+///
+/// Only messages with a value greater than zero count as step (so button releases are ignored).
+/// A step in the wrong order makes the sequence start over.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SequenceSource {
+    steps: Vec<CompoundMappingSource>,
+    /// If set, the sequence starts over if the next step doesn't follow within this time span.
+    timeout: Option<Duration>,
+    /// Number of steps already done.
+    progress: usize,
+    last_step: Option<ControlEventTimestamp>,
+}
+
+impl SequenceSource {
+    pub fn new(steps: Vec<CompoundMappingSource>, timeout: Option<Duration>) -> Self {
+        Self {
+            steps,
+            timeout,
+            progress: 0,
+            last_step: None,
+        }
+    }
+
+    pub fn steps(&self) -> impl Iterator<Item = &CompoundMappingSource> {
+        self.steps.iter()
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Lets each step react to the incoming message using the given function.
+    ///
+    /// Returns `None` if no step reacted at all, `Matched` if the sequence has just been completed
+    /// and `Consumed` otherwise.
+    pub fn control(
+        &mut self,
+        timestamp: ControlEventTimestamp,
+        mut control_step: impl FnMut(&mut CompoundMappingSource) -> Option<ControlValue>,
+    ) -> Option<ControlOutcome<ControlValue>> {
+        if self.is_timed_out(timestamp) {
+            self.reset();
+        }
+        let mut matched = false;
+        let mut pressed = false;
+        let mut advances = false;
+        let mut restarts = false;
+        for (i, step) in self.steps.iter_mut().enumerate() {
+            let v = match control_step(step) {
+                None => continue,
+                Some(v) => v,
+            };
+            matched = true;
+            if v.to_unit_value().map(|v| v.is_zero()).unwrap_or(true) {
+                continue;
+            }
+            pressed = true;
+            if i == self.progress {
+                advances = true;
+            } else if i == 0 {
+                restarts = true;
+            }
+        }
+        if !matched {
+            return None;
+        }
+        if !pressed {
+            return Some(ControlOutcome::Consumed);
+        }
+        if advances {
+            self.progress += 1;
+            self.last_step = Some(timestamp);
+            if self.progress == self.steps.len() {
+                self.reset();
+                let value = ControlValue::AbsoluteContinuous(UnitValue::MAX);
+                return Some(ControlOutcome::Matched(value));
+            }
+        } else if restarts {
+            self.progress = 1;
+            self.last_step = Some(timestamp);
+        } else {
+            self.reset();
+        }
+        Some(ControlOutcome::Consumed)
+    }
+
+    fn is_timed_out(&self, timestamp: ControlEventTimestamp) -> bool {
+        match (self.timeout, self.last_step) {
+            (Some(timeout), Some(last_step)) => timestamp - last_step > timeout,
+            _ => false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.progress = 0;
+        self.last_step = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::compound_source_test_util::{abs, button, press};
+
+    #[test]
+    fn a_then_b() {
+        // Given
+        let mut sequence =
+            CompoundMappingSource::Sequence(SequenceSource::new(vec![button(0), button(1)], None));
+        // When
+        // Then
+        assert!(matches!(
+            press(&mut sequence, 0, true),
+            Some(ControlOutcome::Consumed)
+        ));
+        assert!(matches!(
+            press(&mut sequence, 0, false),
+            Some(ControlOutcome::Consumed)
+        ));
+        assert!(matches!(
+            press(&mut sequence, 1, true),
+            Some(ControlOutcome::Matched(v)) if v == abs(1.0)
+        ));
+        assert!(matches!(
+            press(&mut sequence, 1, true),
+            Some(ControlOutcome::Consumed)
+        ));
+        assert!(press(&mut sequence, 2, true).is_none());
+    }
+
+    #[test]
+    fn wrong_order() {
+        // Given
+        let mut sequence = CompoundMappingSource::Sequence(SequenceSource::new(
+            vec![button(0), button(1), button(2)],
+            None,
+        ));
+        // When
+        press(&mut sequence, 0, true);
+        press(&mut sequence, 2, true);
+        let outcome = press(&mut sequence, 1, true);
+        // Then
+        assert!(matches!(outcome, Some(ControlOutcome::Consumed)));
+    }
+
+    #[test]
+    fn repeated_step() {
+        // Given
+        let mut sequence = CompoundMappingSource::Sequence(SequenceSource::new(
+            vec![button(0), button(0), button(1)],
+            None,
+        ));
+        // When
+        press(&mut sequence, 0, true);
+        press(&mut sequence, 0, true);
+        let outcome = press(&mut sequence, 1, true);
+        // Then
+        assert!(matches!(outcome, Some(ControlOutcome::Matched(v)) if v == abs(1.0)));
+    }
+}
@@ -9,6 +9,9 @@ pub const GROUP_CONTROL_ENABLED: bool = true;
 pub const GROUP_FEEDBACK_ENABLED: bool = true;
 
 pub const SOURCE_OSC_IS_RELATIVE: bool = false;
+pub const SOURCE_LFO_PHASE: f64 = 0.0;
+pub const SOURCE_LFO_DEPTH: f64 = 1.0;
+pub const SOURCE_BEAT_ONLY_WHILE_PLAYING: bool = false;
 
 pub const UNIT_INTERVAL: Interval<f64> = Interval(0.0, 1.0);
 pub const GLUE_STEP_SIZE_INTERVAL: Interval<f64> = Interval(0.01, 0.01);
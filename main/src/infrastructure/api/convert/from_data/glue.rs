@@ -51,6 +51,7 @@ pub fn convert_glue(data: ModeModelData, style: ConversionStyle) -> ConversionRe
             };
             style.required_value(v)
         },
+        transformation_language: style.required_value(data.transformation_language),
         takeover_mode: {
             use schema::TakeoverMode as T;
             use TakeoverMode::*;
@@ -67,6 +68,7 @@ pub fn convert_glue(data: ModeModelData, style: ConversionStyle) -> ConversionRe
             defaults::GLUE_ROUND_TARGET_VALUE,
         ),
         control_transformation: style.required_value(data.eel_control_transformation),
+        control_curve: data.control_curve,
         button_filter: {
             use schema::ButtonFilter as T;
             use ButtonUsage::*;
@@ -172,6 +174,10 @@ pub fn convert_glue(data: ModeModelData, style: ConversionStyle) -> ConversionRe
             style.required_value(v)
         },
         feedback_value_table: data.feedback_value_table,
+        feedback_color_map: data.feedback_color_map,
+        feedback_curve: data.feedback_curve,
+        glide: data.glide,
+        relative_acceleration: data.relative_acceleration,
     };
     Ok(glue)
 }
@@ -1,4 +1,7 @@
-use crate::application::{MidiSourceType, ReaperSourceType, SourceCategory};
+use crate::application::{
+    MidiSourceType, ReaperSourceType, SourceCategory, DEFAULT_BEAT_SUBDIVISIONS,
+};
+use crate::domain::{BeatGrid, BeatSourceOutput, KeySourceMode, LfoRate, LfoWaveform};
 use crate::infrastructure::api::convert::from_data::{
     convert_control_element_id, convert_control_element_kind, convert_keystroke,
     convert_osc_argument, ConversionStyle,
@@ -130,6 +133,13 @@ pub fn convert_source(
                     };
                     schema::Source::MidiScript(s)
                 }
+                ControlScript => {
+                    let s = schema::MidiControlScriptSource {
+                        script: style.required_value(data.midi_script),
+                        character: convert_character(data.character, style),
+                    };
+                    schema::Source::MidiControlScript(s)
+                }
                 Display => {
                     use DisplayType::*;
                     match data.display_type {
@@ -192,9 +202,52 @@ pub fn convert_source(
                 RealearnInstanceStart => {
                     schema::Source::RealearnInstanceStart(schema::RealearnInstanceStartSource)
                 }
+                TrackSelectionChanges => {
+                    schema::Source::TrackSelectionChanges(schema::TrackSelectionChangesSource)
+                }
+                PlayStateChanges => {
+                    schema::Source::PlayStateChanges(schema::PlayStateChangesSource)
+                }
+                RecordStateChanges => {
+                    schema::Source::RecordStateChanges(schema::RecordStateChangesSource)
+                }
+                ProjectSwitch => schema::Source::ProjectSwitch(schema::ProjectSwitchSource),
+                FxFocusChanges => schema::Source::FxFocusChanges(schema::FxFocusChangesSource),
+                MarkerPassed => schema::Source::MarkerPassed(schema::MarkerPassedSource),
+                CompartmentParameterValue => schema::Source::CompartmentParameterValue(
+                    schema::CompartmentParameterValueSource {
+                        index: data.parameter_index.get(),
+                    },
+                ),
                 Timer => schema::Source::Timer(schema::TimerSource {
                     duration: data.timer_millis,
                 }),
+                Lfo => schema::Source::Lfo(schema::LfoSource {
+                    waveform: style.required_value(convert_lfo_waveform(data.lfo_waveform)),
+                    rate: style.required_value(convert_lfo_rate(data.lfo_rate)),
+                    phase: style.required_value_with_default(
+                        data.lfo_phase.get(),
+                        defaults::SOURCE_LFO_PHASE,
+                    ),
+                    depth: style.required_value_with_default(
+                        data.lfo_depth
+                            .map(|d| d.get())
+                            .unwrap_or(defaults::SOURCE_LFO_DEPTH),
+                        defaults::SOURCE_LFO_DEPTH,
+                    ),
+                }),
+                Beat => schema::Source::Beat(schema::BeatSource {
+                    grid: style.required_value(convert_beat_grid(data.beat_grid)),
+                    subdivisions: style.required_value_with_default(
+                        data.beat_subdivisions.unwrap_or(DEFAULT_BEAT_SUBDIVISIONS),
+                        DEFAULT_BEAT_SUBDIVISIONS,
+                    ),
+                    only_while_playing: style.required_value_with_default(
+                        data.beat_only_while_playing,
+                        defaults::SOURCE_BEAT_ONLY_WHILE_PLAYING,
+                    ),
+                    output: style.required_value(convert_beat_source_output(data.beat_output)),
+                }),
             }
         }
         Virtual => {
@@ -207,13 +260,44 @@ pub fn convert_source(
         Keyboard => {
             let s = schema::KeySource {
                 keystroke: data.keystroke.map(convert_keystroke),
+                mode: style.required_value(convert_key_source_mode(data.key_mode)),
             };
             schema::Source::Key(s)
         }
+        Chord => {
+            let s = schema::ChordSource {
+                members: convert_compound_members(data.chord_members, style)?,
+                window: data.chord_window_millis,
+            };
+            schema::Source::Chord(s)
+        }
+        Sequence => {
+            let s = schema::SequenceSource {
+                steps: convert_compound_members(data.sequence_steps, style)?,
+                timeout: data.sequence_timeout_millis,
+            };
+            schema::Source::Sequence(s)
+        }
     };
     Ok(source)
 }
 
+fn convert_compound_members(
+    members: Vec<SourceModelData>,
+    style: ConversionStyle,
+) -> ConversionResult<Vec<schema::Source>> {
+    members
+        .into_iter()
+        .map(|m| {
+            let props = NewSourceProps {
+                prevent_echo_feedback: false,
+                send_feedback_after_control: false,
+            };
+            convert_source(m, props, style)
+        })
+        .collect()
+}
+
 fn convert_channel(v: Option<Channel>) -> Option<u8> {
     Some(v?.get())
 }
@@ -260,6 +344,62 @@ fn convert_transport_msg(
     Some(res)
 }
 
+fn convert_lfo_waveform(v: LfoWaveform) -> schema::LfoWaveform {
+    use schema::LfoWaveform as T;
+    use LfoWaveform::*;
+    match v {
+        Sine => T::Sine,
+        Triangle => T::Triangle,
+        Saw => T::Saw,
+        Square => T::Square,
+        SampleAndHold => T::SampleAndHold,
+    }
+}
+
+fn convert_lfo_rate(v: LfoRate) -> schema::LfoRate {
+    use schema::LfoRate as T;
+    match v {
+        LfoRate::Hertz { frequency } => T::Hertz { frequency },
+        LfoRate::TempoSynced {
+            numerator,
+            denominator,
+        } => T::TempoSynced {
+            numerator,
+            denominator,
+        },
+    }
+}
+
+fn convert_beat_grid(v: BeatGrid) -> schema::BeatGrid {
+    use schema::BeatGrid as T;
+    use BeatGrid::*;
+    match v {
+        Bar => T::Bar,
+        Beat => T::Beat,
+        Subdivision => T::Subdivision,
+    }
+}
+
+fn convert_beat_source_output(v: BeatSourceOutput) -> schema::BeatSourceOutput {
+    use schema::BeatSourceOutput as T;
+    use BeatSourceOutput::*;
+    match v {
+        Trigger => T::Trigger,
+        PositionInBar => T::PositionInBar,
+    }
+}
+
+fn convert_key_source_mode(v: KeySourceMode) -> schema::KeySourceMode {
+    use schema::KeySourceMode as T;
+    use KeySourceMode::*;
+    match v {
+        Normal => T::Normal,
+        Repeat => T::Repeat,
+        Accelerate => T::Accelerate,
+        NumericEntry => T::NumericEntry,
+    }
+}
+
 fn convert_mackie_seven_segment_display_scope(
     v: MackieSevenSegmentDisplayScope,
 ) -> Option<schema::MackieSevenSegmentDisplayScope> {
@@ -128,6 +128,11 @@ pub fn convert_glue(g: Glue) -> ConversionResult<ModeModelData> {
         },
         eel_control_transformation: g.control_transformation.unwrap_or_default(),
         eel_feedback_transformation: fb_data.transformation,
+        transformation_language: g.transformation_language.unwrap_or_default(),
+        control_curve: g.control_curve,
+        feedback_curve: g.feedback_curve,
+        glide: g.glide,
+        relative_acceleration: g.relative_acceleration,
         reverse_is_enabled: g.reverse.unwrap_or(defaults::GLUE_REVERSE),
         feedback_color: fb_data.commons.color,
         feedback_background_color: fb_data.commons.background_color,
@@ -214,6 +219,7 @@ pub fn convert_glue(g: Glue) -> ConversionResult<ModeModelData> {
         },
         feedback_type: fb_data.feedback_type,
         feedback_value_table: g.feedback_value_table,
+        feedback_color_map: g.feedback_color_map,
     };
     Ok(data)
 }
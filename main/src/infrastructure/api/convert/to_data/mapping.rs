@@ -43,6 +43,18 @@ pub fn convert_mapping(
         } else {
             (false, false)
         };
+    if let (Some(Target::Virtual(_)), Some(glue)) = (m.target.as_ref(), m.glue.as_ref()) {
+        let uses_lua_control_transformation = glue.transformation_language
+            == Some(TransformationLanguage::Lua)
+            && glue.control_transformation.is_some();
+        if uses_lua_control_transformation {
+            return Err(
+                "Mappings with virtual target are controlled in real-time and therefore can't \
+                have a Lua control transformation. Use EEL instead."
+                    .into(),
+            );
+        }
+    }
     let v = MappingModelData {
         id: m.id.map(|id| id.into()),
         key: None,
@@ -1,17 +1,29 @@
-use crate::application::{MidiSourceType, ReaperSourceType, SourceCategory};
+use crate::application::{
+    chord_members_are_of_same_kind, MidiSourceType, ReaperSourceType, SourceCategory,
+    DEFAULT_BEAT_SUBDIVISIONS,
+};
+use crate::domain;
 use crate::infrastructure::api::convert::to_data::{
     convert_control_element_id, convert_control_element_type, convert_keystroke,
     convert_osc_arg_type, convert_osc_value_range,
 };
 use crate::infrastructure::api::convert::{defaults, ConversionResult};
 use crate::infrastructure::data::SourceModelData;
-use helgoboss_learn::DisplayType;
+use helgoboss_learn::{DisplayType, UnitValue};
 use helgoboss_midi::{Channel, U14};
 use realearn_api::schema::*;
 use std::convert::TryInto;
 
-pub fn convert_source(s: Source) -> ConversionResult<SourceModelData> {
+pub fn convert_source(mut s: Source) -> ConversionResult<SourceModelData> {
     use Source::*;
+    let chord_members = match &mut s {
+        Chord(s) => std::mem::take(&mut s.members),
+        _ => vec![],
+    };
+    let sequence_steps = match &mut s {
+        Sequence(s) => std::mem::take(&mut s.steps),
+        _ => vec![],
+    };
     let data = SourceModelData {
         category: convert_category(&s),
         r#type: convert_midi_source_type(&s),
@@ -21,6 +33,7 @@ pub fn convert_source(s: Source) -> ConversionResult<SourceModelData> {
             MidiControlChangeValue(s) => convert_character(s.character),
             MidiParameterNumberValue(s) => convert_character(s.character),
             MidiRaw(s) => convert_character(s.character),
+            MidiControlScript(s) => convert_character(s.character),
             _ => Default::default(),
         },
         is_registered: match &s {
@@ -46,6 +59,7 @@ pub fn convert_source(s: Source) -> ConversionResult<SourceModelData> {
         },
         midi_script: match &s {
             MidiScript(s) => s.script.as_ref().cloned().unwrap_or_default(),
+            MidiControlScript(s) => s.script.as_ref().cloned().unwrap_or_default(),
             _ => Default::default(),
         },
         display_type: match &s {
@@ -101,6 +115,10 @@ pub fn convert_source(s: Source) -> ConversionResult<SourceModelData> {
             Key(s) => s.keystroke.map(convert_keystroke),
             _ => Default::default(),
         },
+        key_mode: match &s {
+            Key(s) => convert_key_source_mode(s.mode.unwrap_or_default()),
+            _ => Default::default(),
+        },
         control_element_type: match &s {
             Virtual(s) => convert_control_element_type(s.character.unwrap_or_default()),
             _ => Default::default(),
@@ -112,22 +130,104 @@ pub fn convert_source(s: Source) -> ConversionResult<SourceModelData> {
         reaper_source_type: match &s {
             MidiDeviceChanges(_) => ReaperSourceType::MidiDeviceChanges,
             RealearnInstanceStart(_) => ReaperSourceType::RealearnInstanceStart,
+            TrackSelectionChanges(_) => ReaperSourceType::TrackSelectionChanges,
+            PlayStateChanges(_) => ReaperSourceType::PlayStateChanges,
+            RecordStateChanges(_) => ReaperSourceType::RecordStateChanges,
+            ProjectSwitch(_) => ReaperSourceType::ProjectSwitch,
+            FxFocusChanges(_) => ReaperSourceType::FxFocusChanges,
+            MarkerPassed(_) => ReaperSourceType::MarkerPassed,
+            CompartmentParameterValue(_) => ReaperSourceType::CompartmentParameterValue,
             Timer(_) => ReaperSourceType::Timer,
+            Lfo(_) => ReaperSourceType::Lfo,
+            Beat(_) => ReaperSourceType::Beat,
             _ => Default::default(),
         },
         timer_millis: match &s {
             Timer(t) => t.duration,
             _ => Default::default(),
         },
+        lfo_waveform: match &s {
+            Lfo(s) => convert_lfo_waveform(s.waveform.unwrap_or_default()),
+            _ => Default::default(),
+        },
+        lfo_rate: match &s {
+            Lfo(s) => convert_lfo_rate(s.rate.unwrap_or_default()),
+            _ => Default::default(),
+        },
+        lfo_phase: match &s {
+            Lfo(s) => UnitValue::new_clamped(s.phase.unwrap_or(defaults::SOURCE_LFO_PHASE)),
+            _ => Default::default(),
+        },
+        lfo_depth: match &s {
+            Lfo(s) => s.depth.map(UnitValue::new_clamped),
+            _ => None,
+        },
+        beat_grid: match &s {
+            Beat(s) => convert_beat_grid(s.grid.unwrap_or_default()),
+            _ => Default::default(),
+        },
+        beat_subdivisions: match &s {
+            Beat(s) => s.subdivisions.filter(|s| *s != DEFAULT_BEAT_SUBDIVISIONS),
+            _ => None,
+        },
+        beat_only_while_playing: match &s {
+            Beat(s) => s
+                .only_while_playing
+                .unwrap_or(defaults::SOURCE_BEAT_ONLY_WHILE_PLAYING),
+            _ => false,
+        },
+        beat_output: match &s {
+            Beat(s) => convert_beat_source_output(s.output.unwrap_or_default()),
+            _ => Default::default(),
+        },
+        parameter_index: match &s {
+            CompartmentParameterValue(s) => s.index.try_into()?,
+            _ => Default::default(),
+        },
+        chord_members: convert_chord_members(chord_members)?,
+        chord_window_millis: match &s {
+            Chord(s) => s.window,
+            _ => None,
+        },
+        sequence_steps: sequence_steps
+            .into_iter()
+            .map(convert_source)
+            .collect::<ConversionResult<_>>()?,
+        sequence_timeout_millis: match &s {
+            Sequence(s) => s.timeout,
+            _ => None,
+        },
     };
     Ok(data)
 }
 
+fn convert_chord_members(members: Vec<Source>) -> ConversionResult<Vec<SourceModelData>> {
+    let members: Vec<SourceModelData> = members
+        .into_iter()
+        .map(convert_source)
+        .collect::<ConversionResult<_>>()?;
+    if !chord_members_are_of_same_kind(members.iter().map(|m| m.category)) {
+        return Err("chord members must be all MIDI, all OSC/keyboard or all virtual".into());
+    }
+    Ok(members)
+}
+
 fn convert_category(s: &Source) -> SourceCategory {
     use Source::*;
     match s {
         NoneSource => SourceCategory::Never,
-        MidiDeviceChanges(_) | RealearnInstanceStart(_) | Timer(_) => SourceCategory::Reaper,
+        MidiDeviceChanges(_)
+        | RealearnInstanceStart(_)
+        | TrackSelectionChanges(_)
+        | PlayStateChanges(_)
+        | RecordStateChanges(_)
+        | ProjectSwitch(_)
+        | FxFocusChanges(_)
+        | MarkerPassed(_)
+        | CompartmentParameterValue(_)
+        | Timer(_)
+        | Lfo(_)
+        | Beat(_) => SourceCategory::Reaper,
         MidiNoteVelocity(_)
         | MidiNoteKeyNumber(_)
         | MidiPolyphonicKeyPressureAmount(_)
@@ -140,6 +240,7 @@ fn convert_category(s: &Source) -> SourceCategory {
         | MidiClockTransport(_)
         | MidiRaw(_)
         | MidiScript(_)
+        | MidiControlScript(_)
         | MackieLcd(_)
         | MackieSevenSegmentDisplay(_)
         | SiniConE24Display(_)
@@ -147,6 +248,8 @@ fn convert_category(s: &Source) -> SourceCategory {
         Osc(_) => SourceCategory::Osc,
         Key(_) => SourceCategory::Keyboard,
         Virtual(_) => SourceCategory::Virtual,
+        Chord(_) => SourceCategory::Chord,
+        Sequence(_) => SourceCategory::Sequence,
     }
 }
 
@@ -165,6 +268,7 @@ fn convert_midi_source_type(s: &Source) -> MidiSourceType {
         MidiClockTransport(_) => MidiSourceType::ClockTransport,
         MidiRaw(_) => MidiSourceType::Raw,
         MidiScript(_) => MidiSourceType::Script,
+        MidiControlScript(_) => MidiSourceType::ControlScript,
         MackieLcd(_) | MackieSevenSegmentDisplay(_) | SiniConE24Display(_) => {
             MidiSourceType::Display
         }
@@ -235,6 +339,62 @@ fn convert_midi_clock_transport_message(
     }
 }
 
+fn convert_lfo_waveform(s: LfoWaveform) -> domain::LfoWaveform {
+    use domain::LfoWaveform as T;
+    use LfoWaveform::*;
+    match s {
+        Sine => T::Sine,
+        Triangle => T::Triangle,
+        Saw => T::Saw,
+        Square => T::Square,
+        SampleAndHold => T::SampleAndHold,
+    }
+}
+
+fn convert_lfo_rate(s: LfoRate) -> domain::LfoRate {
+    use domain::LfoRate as T;
+    match s {
+        LfoRate::Hertz { frequency } => T::Hertz { frequency },
+        LfoRate::TempoSynced {
+            numerator,
+            denominator,
+        } => T::TempoSynced {
+            numerator,
+            denominator,
+        },
+    }
+}
+
+fn convert_beat_grid(s: BeatGrid) -> domain::BeatGrid {
+    use domain::BeatGrid as T;
+    use BeatGrid::*;
+    match s {
+        Bar => T::Bar,
+        Beat => T::Beat,
+        Subdivision => T::Subdivision,
+    }
+}
+
+fn convert_beat_source_output(s: BeatSourceOutput) -> domain::BeatSourceOutput {
+    use domain::BeatSourceOutput as T;
+    use BeatSourceOutput::*;
+    match s {
+        Trigger => T::Trigger,
+        PositionInBar => T::PositionInBar,
+    }
+}
+
+fn convert_key_source_mode(s: KeySourceMode) -> domain::KeySourceMode {
+    use domain::KeySourceMode as T;
+    use KeySourceMode::*;
+    match s {
+        Normal => T::Normal,
+        Repeat => T::Repeat,
+        Accelerate => T::Accelerate,
+        NumericEntry => T::NumericEntry,
+    }
+}
+
 fn convert_mackie_seven_segment_display_scope(
     s: MackieSevenSegmentDisplayScope,
 ) -> helgoboss_learn::MackieSevenSegmentDisplayScope {
@@ -7,7 +7,10 @@ use helgoboss_learn::{
     OutOfRangeBehavior, SoftSymmetricUnitValue, TakeoverMode, UnitValue, ValueSequence,
     VirtualColor,
 };
-use realearn_api::schema::FeedbackValueTable;
+use realearn_api::schema::{
+    Curve, FeedbackColorMap, FeedbackValueTable, Glide, RelativeAcceleration,
+    TransformationLanguage,
+};
 use serde::{Deserialize, Serialize};
 use slog::debug;
 use std::time::Duration;
@@ -51,6 +54,16 @@ pub struct ModeModelData {
     #[serde(default, skip_serializing_if = "is_default")]
     pub eel_feedback_transformation: String,
     #[serde(default, skip_serializing_if = "is_default")]
+    pub transformation_language: TransformationLanguage,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub control_curve: Option<Curve>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub feedback_curve: Option<Curve>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub glide: Option<Glide>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub relative_acceleration: Option<RelativeAcceleration>,
+    #[serde(default, skip_serializing_if = "is_default")]
     pub reverse_is_enabled: bool,
     #[serde(default, skip_serializing_if = "is_default")]
     pub feedback_color: Option<VirtualColor>,
@@ -88,6 +101,8 @@ pub struct ModeModelData {
     pub feedback_type: FeedbackType,
     #[serde(default, skip_serializing_if = "is_default")]
     pub feedback_value_table: Option<FeedbackValueTable>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub feedback_color_map: Option<FeedbackColorMap>,
 }
 
 fn default_step_size() -> SoftSymmetricUnitValue {
@@ -119,6 +134,11 @@ impl ModeModelData {
             } else {
                 model.eel_feedback_transformation().to_owned()
             },
+            transformation_language: model.transformation_language(),
+            control_curve: model.control_curve().cloned(),
+            feedback_curve: model.feedback_curve().cloned(),
+            glide: model.glide().cloned(),
+            relative_acceleration: model.relative_acceleration().cloned(),
             feedback_color: model.feedback_color().cloned(),
             feedback_background_color: model.feedback_background_color().cloned(),
             reverse_is_enabled: model.reverse(),
@@ -138,6 +158,7 @@ impl ModeModelData {
             target_value_sequence: model.target_value_sequence().clone(),
             feedback_type: model.feedback_type(),
             feedback_value_table: model.feedback_value_table().cloned(),
+            feedback_color_map: model.feedback_color_map().cloned(),
         }
     }
 
@@ -196,6 +217,13 @@ impl ModeModelData {
             (self.eel_feedback_transformation.clone(), String::new())
         };
         model.change(P::SetEelFeedbackTransformation(eel_fb_transformation));
+        model.change(P::SetTransformationLanguage(self.transformation_language));
+        model.change(P::SetControlCurve(self.control_curve.clone()));
+        model.change(P::SetFeedbackCurve(self.feedback_curve.clone()));
+        model.change(P::SetGlide(self.glide.clone()));
+        model.change(P::SetRelativeAcceleration(
+            self.relative_acceleration.clone(),
+        ));
         model.change(P::SetTextualFeedbackExpression(textual_fb_expression));
         model.change(P::SetFeedbackColor(self.feedback_color.clone()));
         model.change(P::SetFeedbackBackgroundColor(
@@ -228,5 +256,6 @@ impl ModeModelData {
         ));
         model.change(P::SetFeedbackType(self.feedback_type));
         model.change(P::SetFeedbackValueTable(self.feedback_value_table.clone()));
+        model.change(P::SetFeedbackColorMap(self.feedback_color_map.clone()));
     }
 }
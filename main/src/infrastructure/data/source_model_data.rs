@@ -1,14 +1,19 @@
 use super::none_if_minus_one;
 use crate::application::{
     Change, MidiSourceType, ReaperSourceType, SourceCategory, SourceCommand, SourceModel,
-    VirtualControlElementType,
+    VirtualControlElementType, DEFAULT_BEAT_SUBDIVISIONS,
 };
 use crate::base::default_util::is_default;
 use crate::base::notification;
-use crate::domain::{Compartment, Keystroke};
+use crate::domain::{
+    BeatGrid, BeatSourceOutput, Compartment, CompartmentParamIndex, KeySourceMode, Keystroke,
+    LfoRate, LfoWaveform,
+};
 use crate::infrastructure::data::common::OscValueRange;
 use crate::infrastructure::data::VirtualControlElementIdData;
-use helgoboss_learn::{DisplayType, MidiClockTransportMessage, OscTypeTag, SourceCharacter};
+use helgoboss_learn::{
+    DisplayType, MidiClockTransportMessage, OscTypeTag, SourceCharacter, UnitValue,
+};
 use helgoboss_midi::{Channel, U14, U7};
 use realearn_api::schema::MidiScriptKind;
 use semver::Version;
@@ -74,6 +79,8 @@ pub struct SourceModelData {
     // Keyboard
     #[serde(default, skip_serializing_if = "is_default")]
     pub keystroke: Option<Keystroke>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub key_mode: KeySourceMode,
     // Virtual
     #[serde(default, skip_serializing_if = "is_default")]
     pub control_element_type: VirtualControlElementType,
@@ -84,6 +91,38 @@ pub struct SourceModelData {
     pub reaper_source_type: ReaperSourceType,
     #[serde(default, skip_serializing_if = "is_default")]
     pub timer_millis: u64,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lfo_waveform: LfoWaveform,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lfo_rate: LfoRate,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lfo_phase: UnitValue,
+    /// `None` means full depth (the default).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lfo_depth: Option<UnitValue>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub beat_grid: BeatGrid,
+    /// `None` means the default number of subdivisions.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub beat_subdivisions: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub beat_only_while_playing: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub beat_output: BeatSourceOutput,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub parameter_index: CompartmentParamIndex,
+    // Chord
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub chord_members: Vec<SourceModelData>,
+    /// `None` means that the members can be pressed in any time span.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub chord_window_millis: Option<u64>,
+    // Sequence
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sequence_steps: Vec<SourceModelData>,
+    /// `None` means that the sequence never times out.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sequence_timeout_millis: Option<u64>,
 }
 
 impl SourceModelData {
@@ -114,12 +153,35 @@ impl SourceModelData {
             osc_arg_value_range: OscValueRange::from_interval(model.osc_arg_value_range()),
             osc_feedback_args: model.osc_feedback_args().to_vec(),
             keystroke: model.keystroke(),
+            key_mode: model.key_mode(),
             control_element_type: model.control_element_type(),
             control_element_index: VirtualControlElementIdData::from_model(
                 model.control_element_id(),
             ),
             reaper_source_type: model.reaper_source_type(),
             timer_millis: model.timer_millis(),
+            lfo_waveform: model.lfo_waveform(),
+            lfo_rate: model.lfo_rate(),
+            lfo_phase: model.lfo_phase(),
+            lfo_depth: Some(model.lfo_depth()).filter(|d| *d != UnitValue::MAX),
+            beat_grid: model.beat_grid(),
+            beat_subdivisions: Some(model.beat_subdivisions())
+                .filter(|s| *s != DEFAULT_BEAT_SUBDIVISIONS),
+            beat_only_while_playing: model.beat_only_while_playing(),
+            beat_output: model.beat_output(),
+            parameter_index: model.parameter_index(),
+            chord_members: model
+                .chord_members()
+                .iter()
+                .map(SourceModelData::from_model)
+                .collect(),
+            chord_window_millis: model.chord_window_millis(),
+            sequence_steps: model
+                .sequence_steps()
+                .iter()
+                .map(SourceModelData::from_model)
+                .collect(),
+            sequence_timeout_millis: model.sequence_timeout_millis(),
         }
     }
 
@@ -199,7 +261,32 @@ impl SourceModelData {
         ));
         model.change(P::SetReaperSourceType(self.reaper_source_type));
         model.change(P::SetTimerMillis(self.timer_millis));
+        model.change(P::SetLfoWaveform(self.lfo_waveform));
+        model.change(P::SetLfoRate(self.lfo_rate));
+        model.change(P::SetLfoPhase(self.lfo_phase));
+        model.change(P::SetLfoDepth(self.lfo_depth.unwrap_or(UnitValue::MAX)));
+        model.change(P::SetBeatGrid(self.beat_grid));
+        model.change(P::SetBeatSubdivisions(
+            self.beat_subdivisions.unwrap_or(DEFAULT_BEAT_SUBDIVISIONS),
+        ));
+        model.change(P::SetBeatOnlyWhilePlaying(self.beat_only_while_playing));
+        model.change(P::SetBeatOutput(self.beat_output));
+        model.change(P::SetParameterIndex(self.parameter_index));
         model.change(P::SetKeystroke(self.keystroke));
+        model.change(P::SetKeyMode(self.key_mode));
+        let create_members = |data: &[SourceModelData]| {
+            data.iter()
+                .map(|d| {
+                    let mut member = SourceModel::default();
+                    d.apply_to_model_flexible(&mut member, compartment, preset_version);
+                    member
+                })
+                .collect()
+        };
+        model.change(P::SetChordMembers(create_members(&self.chord_members)));
+        model.change(P::SetChordWindowMillis(self.chord_window_millis));
+        model.change(P::SetSequenceSteps(create_members(&self.sequence_steps)));
+        model.change(P::SetSequenceTimeoutMillis(self.sequence_timeout_millis));
     }
 }
 
@@ -22,6 +22,7 @@ use crate::infrastructure::data::{
 };
 use crate::infrastructure::plugin::debug_util;
 use crate::infrastructure::server;
+use crate::infrastructure::server::auth::AccessControl;
 use crate::infrastructure::server::{RealearnServer, SharedRealearnServer, COMPANION_WEB_APP_URL};
 use crate::infrastructure::ui::MessagePanel;
 
@@ -267,10 +268,13 @@ impl App {
             server: Rc::new(RefCell::new(RealearnServer::new(
                 config.main.server_http_port,
                 config.main.server_https_port,
+                config.main.server_grpc_port,
                 App::server_resource_dir_path().join("certificates"),
                 server_sender,
                 Self::control_surface_metrics_enabled(),
                 prometheus_handle,
+                config.server_access_control(),
+                config.server_allowed_origins(),
             ))),
             config: RefCell::new(config),
             changed_subject: Default::default(),
@@ -1420,6 +1424,33 @@ impl AppConfig {
         self.main.server_enabled > 0
     }
 
+    /// If the configured tokens are invalid, authentication stays enabled but only the pairing
+    /// code is accepted (better safe than sorry).
+    pub fn server_access_control(&self) -> AccessControl {
+        if self.main.server_auth_enabled == 0 {
+            return AccessControl::disabled();
+        }
+        let tokens =
+            AccessControl::parse_tokens(&self.main.server_access_tokens).unwrap_or_else(|e| {
+                Reaper::get().show_console_msg(format!(
+                    "Ignoring configured ReaLearn server access tokens because {}\n",
+                    e
+                ));
+                Default::default()
+            });
+        AccessControl::enabled(tokens)
+    }
+
+    pub fn server_allowed_origins(&self) -> Vec<String> {
+        self.main
+            .server_allowed_origins
+            .split(',')
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
     pub fn companion_web_app_url(&self) -> url::Url {
         Url::parse(&self.main.companion_web_app_url).expect("invalid companion web app URL")
     }
@@ -1443,6 +1474,20 @@ struct MainConfig {
         skip_serializing_if = "is_default_server_https_port"
     )]
     server_https_port: u16,
+    #[serde(
+        default = "default_server_grpc_port",
+        skip_serializing_if = "is_default_server_grpc_port"
+    )]
+    server_grpc_port: u16,
+    /// If enabled, clients need to present an access token (or the pairing code).
+    #[serde(default, skip_serializing_if = "is_default")]
+    server_auth_enabled: u8,
+    /// Comma-separated list of `token:scope` pairs, scope being `read-only` or `read-write`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    server_access_tokens: String,
+    /// Comma-separated list of origins allowed for CORS requests. Empty means any origin.
+    #[serde(default, skip_serializing_if = "is_default")]
+    server_allowed_origins: String,
     #[serde(
         default = "default_companion_web_app_url",
         skip_serializing_if = "is_default_companion_web_app_url"
@@ -1452,6 +1497,7 @@ struct MainConfig {
 
 const DEFAULT_SERVER_HTTP_PORT: u16 = 39080;
 const DEFAULT_SERVER_HTTPS_PORT: u16 = 39443;
+const DEFAULT_SERVER_GRPC_PORT: u16 = 39051;
 
 fn default_server_http_port() -> u16 {
     DEFAULT_SERVER_HTTP_PORT
@@ -1469,6 +1515,14 @@ fn is_default_server_https_port(v: &u16) -> bool {
     *v == DEFAULT_SERVER_HTTPS_PORT
 }
 
+fn default_server_grpc_port() -> u16 {
+    DEFAULT_SERVER_GRPC_PORT
+}
+
+fn is_default_server_grpc_port(v: &u16) -> bool {
+    *v == DEFAULT_SERVER_GRPC_PORT
+}
+
 fn default_companion_web_app_url() -> String {
     COMPANION_WEB_APP_URL.to_string()
 }
@@ -1483,6 +1537,10 @@ impl Default for MainConfig {
             server_enabled: Default::default(),
             server_http_port: default_server_http_port(),
             server_https_port: default_server_https_port(),
+            server_grpc_port: default_server_grpc_port(),
+            server_auth_enabled: Default::default(),
+            server_access_tokens: Default::default(),
+            server_allowed_origins: Default::default(),
             companion_web_app_url: default_companion_web_app_url(),
         }
     }
@@ -9,9 +9,9 @@ use super::RealearnEditor;
 use crate::base::{Global, NamedChannelSender, SenderToNormalThread, SenderToRealTimeThread};
 use crate::domain::{
     AudioBlockProps, BackboneState, ControlEvent, ControlEventTimestamp, ControlMainTask,
-    FeedbackRealTimeTask, InstanceId, MainProcessor, MidiEvent, NormalMainTask,
-    NormalRealTimeToMainThreadTask, ParameterMainTask, PluginParamIndex, ProcessorContext,
-    RealTimeProcessorLocker, SharedRealTimeProcessor, PLUGIN_PARAMETER_COUNT,
+    FeedbackRealTimeTask, ForwardedMidiMessage, InstanceId, MainProcessor, MidiEvent,
+    NormalMainTask, NormalRealTimeToMainThreadTask, ParameterMainTask, PluginParamIndex,
+    ProcessorContext, RealTimeProcessorLocker, SharedRealTimeProcessor, PLUGIN_PARAMETER_COUNT,
 };
 use crate::domain::{NormalRealTimeTask, RealTimeProcessor};
 use crate::infrastructure::plugin::realearn_plugin_parameters::RealearnPluginParameters;
@@ -50,6 +50,7 @@ const FEEDBACK_REAL_TIME_TASK_QUEUE_SIZE: usize = 2000;
 // Raised this limit to a safer number because of https://github.com/helgoboss/realearn/issues/201.
 const NORMAL_MAIN_TASK_QUEUE_SIZE: usize = 10_000;
 const CONTROL_MAIN_TASK_QUEUE_SIZE: usize = 5000;
+const FORWARDED_MIDI_QUEUE_SIZE: usize = 500;
 const PARAMETER_MAIN_TASK_QUEUE_SIZE: usize = 5000;
 const INSTANCE_FEEDBACK_EVENT_QUEUE_SIZE: usize = 10_000;
 
@@ -79,6 +80,8 @@ pub struct RealearnPlugin {
     // Will be cloned to session as soon as it gets created.
     control_main_task_receiver: crossbeam_channel::Receiver<ControlMainTask>,
     // Will be cloned to session as soon as it gets created.
+    forwarded_midi_receiver: crossbeam_channel::Receiver<ControlEvent<ForwardedMidiMessage>>,
+    // Will be cloned to session as soon as it gets created.
     normal_rt_to_main_task_receiver: crossbeam_channel::Receiver<NormalRealTimeToMainThreadTask>,
     // Will be cloned to session as soon as it gets created.
     parameter_main_task_receiver: crossbeam_channel::Receiver<ParameterMainTask>,
@@ -133,6 +136,11 @@ impl Plugin for RealearnPlugin {
                     "control main tasks",
                     CONTROL_MAIN_TASK_QUEUE_SIZE,
                 );
+            let (forwarded_midi_sender, forwarded_midi_receiver) =
+                SenderToNormalThread::new_bounded_channel(
+                    "forwarded MIDI messages",
+                    FORWARDED_MIDI_QUEUE_SIZE,
+                );
             let (parameter_main_task_sender, parameter_main_task_receiver) =
                 SenderToNormalThread::new_bounded_channel(
                     "parameter main tasks",
@@ -150,6 +158,7 @@ impl Plugin for RealearnPlugin {
                 feedback_real_time_task_sender.clone(),
                 normal_rt_to_main_task_sender,
                 control_main_task_sender,
+                forwarded_midi_sender,
                 App::garbage_bin().clone(),
             );
             Self {
@@ -166,6 +175,7 @@ impl Plugin for RealearnPlugin {
                 real_time_processor: Arc::new(Mutex::new(real_time_processor)),
                 parameter_main_task_receiver,
                 control_main_task_receiver,
+                forwarded_midi_receiver,
                 normal_rt_to_main_task_receiver,
                 was_playing_in_last_cycle: false,
                 sample_rate: Default::default(),
@@ -394,6 +404,7 @@ impl RealearnPlugin {
         let feedback_real_time_task_sender = self.feedback_real_time_task_sender.clone();
         let normal_main_task_channel = self.normal_main_task_channel.clone();
         let control_main_task_receiver = self.control_main_task_receiver.clone();
+        let forwarded_midi_receiver = self.forwarded_midi_receiver.clone();
         let parameter_main_task_receiver = self.parameter_main_task_receiver.clone();
         let normal_rt_to_main_task_receiver = self.normal_rt_to_main_task_receiver.clone();
         let logger = self.logger.clone();
@@ -461,6 +472,7 @@ impl RealearnPlugin {
                     normal_rt_to_main_task_receiver,
                     parameter_main_task_receiver,
                     control_main_task_receiver,
+                    forwarded_midi_receiver,
                     instance_feedback_event_receiver,
                     normal_real_time_task_sender,
                     feedback_real_time_task_sender,
@@ -0,0 +1,247 @@
+//! Contains the optional token-based access control of the HTTP, WebSocket and gRPC servers.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// What a client is allowed to do.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum AccessScope {
+    /// Allows reading data and subscribing to events.
+    ReadOnly,
+    /// Additionally allows modifying data and controlling targets.
+    ReadWrite,
+}
+
+impl AccessScope {
+    pub fn includes(self, required: AccessScope) -> bool {
+        self >= required
+    }
+}
+
+impl FromStr for AccessScope {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read-only" | "ro" => Ok(Self::ReadOnly),
+            "read-write" | "rw" => Ok(Self::ReadWrite),
+            _ => Err("access scope must be 'read-only' or 'read-write'"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AccessDenied {
+    TokenMissing,
+    TokenInvalid,
+    ScopeInsufficient,
+}
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            AccessDenied::TokenMissing => "access token missing",
+            AccessDenied::TokenInvalid => "access token invalid",
+            AccessDenied::ScopeInsufficient => "access token doesn't allow write access",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Decides which clients are allowed to access the servers.
+///
+/// If authentication is disabled, every client gets read-write access (that's how it always used
+/// to be).
+#[derive(Clone, Debug, Default)]
+pub struct AccessControl {
+    enabled: bool,
+    tokens: HashMap<String, AccessScope>,
+}
+
+impl AccessControl {
+    pub fn disabled() -> Self {
+        Default::default()
+    }
+
+    pub fn enabled(tokens: HashMap<String, AccessScope>) -> Self {
+        Self {
+            enabled: true,
+            tokens,
+        }
+    }
+
+    /// Parses a comma-separated list of `token:scope` pairs as used in the config file, e.g.
+    /// `abc:read-write,def:read-only`. The scope defaults to read-only.
+    pub fn parse_tokens(text: &str) -> Result<HashMap<String, AccessScope>, &'static str> {
+        text.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (token, scope) = match entry.split_once(':') {
+                    None => (entry, AccessScope::ReadOnly),
+                    Some((token, scope)) => (token, scope.trim().parse()?),
+                };
+                let token = token.trim();
+                if token.is_empty() {
+                    return Err("access token must not be empty");
+                }
+                Ok((token.to_string(), scope))
+            })
+            .collect()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn add_token(&mut self, token: String, scope: AccessScope) {
+        self.tokens.insert(token, scope);
+    }
+
+    /// Returns the scope granted to a client presenting the given token.
+    pub fn authenticate(&self, token: Option<&str>) -> Result<AccessScope, AccessDenied> {
+        if !self.enabled {
+            return Ok(AccessScope::ReadWrite);
+        }
+        let token = token.ok_or(AccessDenied::TokenMissing)?;
+        // No hash map lookup and no early return, so the response time doesn't tell anything
+        // about how close the presented token is to a valid one.
+        let mut granted_scope = None;
+        for (valid_token, scope) in &self.tokens {
+            if constant_time_eq(valid_token.as_bytes(), token.as_bytes()) {
+                granted_scope = Some(*scope);
+            }
+        }
+        granted_scope.ok_or(AccessDenied::TokenInvalid)
+    }
+
+    pub fn authorize(
+        &self,
+        token: Option<&str>,
+        required: AccessScope,
+    ) -> Result<AccessScope, AccessDenied> {
+        let scope = self.authenticate(token)?;
+        if !scope.includes(required) {
+            return Err(AccessDenied::ScopeInsufficient);
+        }
+        Ok(scope)
+    }
+}
+
+/// Extracts the token from an `Authorization: Bearer <token>` header value.
+pub fn parse_bearer_token(header_value: &str) -> Option<&str> {
+    let (scheme, token) = header_value.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+    Some(token.trim())
+}
+
+pub fn log_rejection(protocol: &str, resource: &str, reason: AccessDenied) {
+    tracing::warn!("Rejected {} request for {}: {}", protocol, resource, reason);
+}
+
+/// Compares two byte strings in a time which only depends on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+const PAIRING_CODE_LENGTH: usize = 24;
+
+const PAIRING_CODE_ALPHABET: [char; 62] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
+    'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B',
+    'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U',
+    'V', 'W', 'X', 'Y', 'Z',
+];
+
+/// Generates the code which grants read-write access to clients that scanned the QR code.
+///
+/// The code is long enough that brute-forcing it over the network is hopeless, which is
+/// important because failed attempts are not throttled.
+pub fn generate_pairing_code() -> String {
+    nanoid::nanoid!(PAIRING_CODE_LENGTH, &PAIRING_CODE_ALPHABET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tokens() {
+        let tokens = AccessControl::parse_tokens("abc:read-write, def:ro,ghi").unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens["abc"], AccessScope::ReadWrite);
+        assert_eq!(tokens["def"], AccessScope::ReadOnly);
+        assert_eq!(tokens["ghi"], AccessScope::ReadOnly);
+        assert!(AccessControl::parse_tokens("abc:admin").is_err());
+        assert!(AccessControl::parse_tokens(":rw").is_err());
+        assert!(AccessControl::parse_tokens("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn authorize() {
+        let mut access_control = AccessControl::enabled(
+            AccessControl::parse_tokens("reader:read-only,writer:read-write").unwrap(),
+        );
+        let pairing_code = generate_pairing_code();
+        access_control.add_token(pairing_code.clone(), AccessScope::ReadWrite);
+        use AccessScope::*;
+        assert_eq!(
+            access_control.authorize(None, ReadOnly),
+            Err(AccessDenied::TokenMissing)
+        );
+        assert_eq!(
+            access_control.authorize(Some("foo"), ReadOnly),
+            Err(AccessDenied::TokenInvalid)
+        );
+        assert_eq!(
+            access_control.authorize(Some("reader"), ReadOnly),
+            Ok(ReadOnly)
+        );
+        assert_eq!(
+            access_control.authorize(Some("reader"), ReadWrite),
+            Err(AccessDenied::ScopeInsufficient)
+        );
+        assert_eq!(
+            access_control.authorize(Some("writer"), ReadWrite),
+            Ok(ReadWrite)
+        );
+        assert_eq!(
+            access_control.authorize(Some(&pairing_code), ReadWrite),
+            Ok(ReadWrite)
+        );
+        assert_eq!(
+            AccessControl::disabled().authorize(None, ReadWrite),
+            Ok(ReadWrite)
+        );
+    }
+
+    #[test]
+    fn pairing_code() {
+        let code = generate_pairing_code();
+        assert_eq!(code.len(), PAIRING_CODE_LENGTH);
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(code, generate_pairing_code());
+    }
+
+    #[test]
+    fn compare_in_constant_time() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn bearer_token() {
+        assert_eq!(parse_bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(parse_bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(parse_bearer_token("Basic abc"), None);
+        assert_eq!(parse_bearer_token("abc"), None);
+    }
+}
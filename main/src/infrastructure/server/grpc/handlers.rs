@@ -1,42 +1,352 @@
-use crate::domain::BackboneState;
-use crate::infrastructure::server::grpc::proto::{greeter_server, DoubleReply, DoubleRequest};
-use crate::infrastructure::server::grpc::proto::{HelloReply, HelloRequest};
+use crate::application::Session;
+use crate::domain::{
+    compartment_param_index_iter, BackboneState, Compartment, CompartmentParamIndex,
+    FeedbackOutput, MappingKey, MidiDestination, ServerEvent, ServerEventPayload,
+    SourceFeedbackEvent, SourceFeedbackValue,
+};
+use crate::infrastructure::plugin::App;
+use crate::infrastructure::server::auth::{
+    log_rejection, parse_bearer_token, AccessControl, AccessDenied, AccessScope,
+};
+use crate::infrastructure::server::grpc::proto;
+use crate::infrastructure::server::grpc::proto::{
+    midi_feedback, realearn_service_server, session_event, source_feedback, CompartmentParameter,
+    Empty, GetCompartmentParametersReply, GetCompartmentParametersRequest, HitTargetRequest,
+    ListMappingsReply, ListMappingsRequest, ListSessionsReply, ListSessionsRequest, Mapping,
+    MappingOnStateChanged, MidiFeedback, OscFeedback, SessionEvent, SetCompartmentParameterRequest,
+    SourceFeedback, StreamSessionEventsRequest, StreamSourceFeedbackRequest, TargetValueChanged,
+};
 use futures::{Stream, StreamExt};
+use helgoboss_learn::{AbsoluteValue, MidiSourceValue, UnitValue};
+use helgoboss_midi::{DataEntryByteOrder, RawShortMessage, ShortMessage};
+use rosc::{OscMessage, OscPacket};
+use std::convert::TryFrom;
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio_stream::wrappers::BroadcastStream;
 use tonic::{Request, Response, Status};
 
-#[derive(Debug, Default)]
-pub struct MyGreeter {}
+/// Implementation of the ReaLearn gRPC service.
+///
+/// All unary calls are executed in the main thread (thanks to the `MainThreadLayer`), so they can
+/// access the app and sessions directly.
+#[derive(Debug)]
+pub struct RealearnServiceImpl {
+    access_control: Arc<AccessControl>,
+}
+
+impl RealearnServiceImpl {
+    pub fn new(access_control: Arc<AccessControl>) -> Self {
+        Self { access_control }
+    }
+
+    /// Checks the access token passed as `authorization: Bearer <token>` metadata.
+    fn authorize<T>(&self, request: &Request<T>, required: AccessScope) -> Result<(), Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_bearer_token);
+        self.access_control
+            .authorize(token, required)
+            .map_err(|e| {
+                log_rejection("gRPC", "RealearnService", e);
+                match e {
+                    AccessDenied::TokenMissing | AccessDenied::TokenInvalid => {
+                        Status::unauthenticated(e.to_string())
+                    }
+                    AccessDenied::ScopeInsufficient => Status::permission_denied(e.to_string()),
+                }
+            })?;
+        Ok(())
+    }
+}
 
 #[tonic::async_trait]
-impl greeter_server::Greeter for MyGreeter {
-    async fn say_hello(
+impl realearn_service_server::RealearnService for RealearnServiceImpl {
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsReply>, Status> {
+        self.authorize(&request, AccessScope::ReadOnly)?;
+        let sessions = App::get().with_sessions(|sessions| {
+            sessions
+                .iter()
+                .filter_map(|s| s.upgrade())
+                .map(|s| {
+                    let session = s.borrow();
+                    let context = session.processor_context();
+                    proto::Session {
+                        id: session.id().to_string(),
+                        instance_id: session.instance_id().to_string(),
+                        fx_name: context.containing_fx().name().into_string(),
+                        track_name: context
+                            .track()
+                            .and_then(|t| t.name())
+                            .map(|n| n.into_string())
+                            .unwrap_or_default(),
+                    }
+                })
+                .collect()
+        });
+        Ok(Response::new(ListSessionsReply { sessions }))
+    }
+
+    async fn list_mappings(
+        &self,
+        request: Request<ListMappingsRequest>,
+    ) -> Result<Response<ListMappingsReply>, Status> {
+        self.authorize(&request, AccessScope::ReadOnly)?;
+        let req = request.into_inner();
+        let compartment = convert_compartment(req.compartment)?;
+        let mappings = with_session(&req.session_id, |session| {
+            session
+                .mappings(compartment)
+                .map(|m| {
+                    let m = m.borrow();
+                    Mapping {
+                        key: m.key().to_string(),
+                        name: m.effective_name(),
+                        is_enabled: m.is_enabled(),
+                        is_on: session.mapping_is_on(m.qualified_id()),
+                    }
+                })
+                .collect()
+        })?;
+        Ok(Response::new(ListMappingsReply { mappings }))
+    }
+
+    async fn get_compartment_parameters(
         &self,
-        request: Request<HelloRequest>,
-    ) -> Result<Response<HelloReply>, Status> {
-        println!("Got a request: {:?}", request);
+        request: Request<GetCompartmentParametersRequest>,
+    ) -> Result<Response<GetCompartmentParametersReply>, Status> {
+        self.authorize(&request, AccessScope::ReadOnly)?;
+        let req = request.into_inner();
+        let compartment = convert_compartment(req.compartment)?;
+        let parameters = with_session(&req.session_id, |session| {
+            let params = session.params().compartment_params(compartment);
+            compartment_param_index_iter()
+                .map(|i| {
+                    let param = params.at(i);
+                    let setting = param.setting();
+                    CompartmentParameter {
+                        index: i.get(),
+                        key: setting.key.clone().unwrap_or_default(),
+                        name: setting.name.clone(),
+                        value_count: setting.value_count.map(|c| c.get()).unwrap_or(0),
+                        value_labels: setting.value_labels.clone(),
+                        value: param.raw_value() as f64,
+                    }
+                })
+                .collect()
+        })?;
+        Ok(Response::new(GetCompartmentParametersReply { parameters }))
+    }
 
-        let reply = HelloReply {
-            message: format!("Hello {}!", request.into_inner().name),
-        };
+    async fn set_compartment_parameter(
+        &self,
+        request: Request<SetCompartmentParameterRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        self.authorize(&request, AccessScope::ReadWrite)?;
+        let req = request.into_inner();
+        let compartment = convert_compartment(req.compartment)?;
+        let index = CompartmentParamIndex::try_from(req.index)
+            .map_err(|_| Status::invalid_argument("parameter index out of range"))?;
+        let value = UnitValue::new_clamped(req.value);
+        with_session(&req.session_id, |session| {
+            session.set_compartment_param_value(compartment, index, value)
+        })?
+        .map_err(Status::internal)?;
+        Ok(Response::new(Empty {}))
+    }
 
-        Ok(Response::new(reply))
+    async fn hit_target(
+        &self,
+        request: Request<HitTargetRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        self.authorize(&request, AccessScope::ReadWrite)?;
+        let req = request.into_inner();
+        let compartment = convert_compartment(req.compartment)?;
+        let value = UnitValue::new_clamped(req.value);
+        let mapping_key = MappingKey::from(req.mapping_key);
+        with_session(&req.session_id, |session| {
+            let mapping = session
+                .find_mapping_by_key(compartment, &mapping_key)
+                .ok_or_else(|| Status::not_found("mapping not found"))?;
+            let id = mapping.borrow().qualified_id();
+            session.hit_target(id, AbsoluteValue::Continuous(value));
+            Ok(())
+        })??;
+        Ok(Response::new(Empty {}))
     }
 
-    type StreamExperimentStream = SyncBoxStream<'static, Result<DoubleReply, Status>>;
+    type StreamSessionEventsStream = SyncBoxStream<'static, Result<SessionEvent, Status>>;
 
-    async fn stream_experiment(
+    async fn stream_session_events(
         &self,
-        _request: Request<DoubleRequest>,
-    ) -> Result<Response<Self::StreamExperimentStream>, Status> {
+        request: Request<StreamSessionEventsRequest>,
+    ) -> Result<Response<Self::StreamSessionEventsStream>, Status> {
+        self.authorize(&request, AccessScope::ReadOnly)?;
+        let req = request.into_inner();
+        let instance_id = with_session(&req.session_id, |session| *session.instance_id())?;
         let receiver = BackboneState::server_event_sender().subscribe();
-        let receiver_stream = BroadcastStream::new(receiver).map(|value| match value {
-            Ok(v) => Ok(DoubleReply { value: v }),
-            Err(e) => Err(Status::unknown(e.to_string())),
+        let receiver_stream = BroadcastStream::new(receiver).filter_map(move |value| {
+            let item = match value {
+                Ok(evt) if evt.instance_id == instance_id => Some(Ok(convert_server_event(evt))),
+                Ok(_) => None,
+                Err(e) => {
+                    // The client was too slow and missed some events. Ending the stream because
+                    // of that would be worse than just continuing with the next events.
+                    tracing::debug!("Session event stream lagged: {}", e);
+                    None
+                }
+            };
+            futures::future::ready(item)
         });
         Ok(Response::new(Box::pin(receiver_stream)))
     }
+
+    type StreamSourceFeedbackStream = SyncBoxStream<'static, Result<SourceFeedback, Status>>;
+
+    async fn stream_source_feedback(
+        &self,
+        request: Request<StreamSourceFeedbackRequest>,
+    ) -> Result<Response<Self::StreamSourceFeedbackStream>, Status> {
+        self.authorize(&request, AccessScope::ReadOnly)?;
+        let req = request.into_inner();
+        let instance_id = with_session(&req.session_id, |session| *session.instance_id())?;
+        let receiver = BackboneState::source_feedback_event_sender().subscribe();
+        let receiver_stream = BroadcastStream::new(receiver).filter_map(move |value| {
+            let item = match value {
+                Ok(evt) if evt.instance_id == instance_id => {
+                    convert_source_feedback_event(evt).map(Ok)
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    // Missing some feedback is not nice but better than ending the stream.
+                    tracing::debug!("Source feedback stream lagged: {}", e);
+                    None
+                }
+            };
+            futures::future::ready(item)
+        });
+        Ok(Response::new(Box::pin(receiver_stream)))
+    }
+}
+
+fn with_session<R>(session_id: &str, f: impl FnOnce(&Session) -> R) -> Result<R, Status> {
+    let session = App::get()
+        .find_session_by_id(session_id)
+        .ok_or_else(|| Status::not_found("session not found"))?;
+    let session = session.borrow();
+    Ok(f(&session))
+}
+
+fn convert_compartment(value: i32) -> Result<Compartment, Status> {
+    let compartment = proto::Compartment::from_i32(value)
+        .ok_or_else(|| Status::invalid_argument("unknown compartment"))?;
+    let compartment = match compartment {
+        proto::Compartment::Main => Compartment::Main,
+        proto::Compartment::Controller => Compartment::Controller,
+    };
+    Ok(compartment)
+}
+
+fn convert_compartment_to_proto(compartment: Compartment) -> proto::Compartment {
+    match compartment {
+        Compartment::Main => proto::Compartment::Main,
+        Compartment::Controller => proto::Compartment::Controller,
+    }
+}
+
+fn convert_server_event(event: ServerEvent) -> SessionEvent {
+    let value = match event.payload {
+        ServerEventPayload::MappingOnStateChanged {
+            compartment,
+            mapping_key,
+            is_on,
+        } => session_event::Value::MappingOnStateChanged(MappingOnStateChanged {
+            compartment: convert_compartment_to_proto(compartment).into(),
+            mapping_key: mapping_key.into(),
+            is_on,
+        }),
+        ServerEventPayload::TargetValueChanged {
+            compartment,
+            mapping_key,
+            value,
+        } => session_event::Value::TargetValueChanged(TargetValueChanged {
+            compartment: convert_compartment_to_proto(compartment).into(),
+            mapping_key: mapping_key.into(),
+            value: value.get(),
+        }),
+    };
+    SessionEvent { value: Some(value) }
+}
+
+/// Returns `None` if the feedback can't be represented.
+fn convert_source_feedback_event(event: SourceFeedbackEvent) -> Option<SourceFeedback> {
+    let SourceFeedbackEvent {
+        origin,
+        text,
+        output,
+        value,
+        ..
+    } = event;
+    let (compartment, mapping_key) = match origin {
+        Some((compartment, mapping_key)) => (compartment, mapping_key.into()),
+        None => (Compartment::Main, String::new()),
+    };
+    let value = match (value, output) {
+        (SourceFeedbackValue::Midi(v), FeedbackOutput::Midi(dest)) => {
+            source_feedback::Value::Midi(MidiFeedback {
+                destination: Some(convert_midi_destination(dest)),
+                messages: convert_midi_source_value(&v),
+            })
+        }
+        (SourceFeedbackValue::Osc(msg), FeedbackOutput::Osc(dev_id)) => {
+            source_feedback::Value::Osc(convert_osc_message(msg, dev_id.to_string()))
+        }
+        _ => return None,
+    };
+    let feedback = SourceFeedback {
+        compartment: convert_compartment_to_proto(compartment).into(),
+        mapping_key,
+        text: text.unwrap_or_default(),
+        value: Some(value),
+    };
+    Some(feedback)
+}
+
+fn convert_midi_destination(dest: MidiDestination) -> midi_feedback::Destination {
+    match dest {
+        MidiDestination::FxOutput => midi_feedback::Destination::FxOutput(Empty {}),
+        MidiDestination::Device(id) => midi_feedback::Destination::DeviceId(id.get() as u32),
+    }
+}
+
+fn convert_midi_source_value(value: &MidiSourceValue<RawShortMessage>) -> Vec<Vec<u8>> {
+    if let Some(evts) = value.to_raw() {
+        return evts.iter().map(|e| e.bytes().to_vec()).collect();
+    }
+    value
+        .to_short_messages(DataEntryByteOrder::MsbFirst)
+        .iter()
+        .flatten()
+        .map(|msg| {
+            let (status, data_1, data_2) = msg.to_bytes();
+            vec![status, data_1.get(), data_2.get()]
+        })
+        .collect()
+}
+
+fn convert_osc_message(msg: OscMessage, device_id: String) -> OscFeedback {
+    let address = msg.addr.clone();
+    let packet = rosc::encoder::encode(&OscPacket::Message(msg)).unwrap_or_default();
+    OscFeedback {
+        device_id,
+        address,
+        packet,
+    }
 }
 
 type SyncBoxStream<'a, T> = Pin<Box<dyn Stream<Item = T> + Send + Sync + 'a>>;
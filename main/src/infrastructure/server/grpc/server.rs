@@ -1,19 +1,21 @@
-use crate::infrastructure::server::grpc::handlers::MyGreeter;
-use crate::infrastructure::server::grpc::proto::greeter_server::GreeterServer;
+use crate::infrastructure::server::auth::AccessControl;
+use crate::infrastructure::server::grpc::handlers::RealearnServiceImpl;
+use crate::infrastructure::server::grpc::proto::realearn_service_server::RealearnServiceServer;
 use crate::infrastructure::server::layers::MainThreadLayer;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::sync::broadcast;
 use tonic::transport::Server;
 
-#[allow(dead_code)]
 pub async fn start_grpc_server(
     address: SocketAddr,
     mut shutdown_receiver: broadcast::Receiver<()>,
+    access_control: Arc<AccessControl>,
 ) -> Result<(), tonic::transport::Error> {
-    let greeter = MyGreeter::default();
+    let realearn_service = RealearnServiceImpl::new(access_control);
     Server::builder()
-        .layer(MainThreadLayer)
-        .add_service(GreeterServer::new(greeter))
+        .layer(MainThreadLayer::new())
+        .add_service(RealearnServiceServer::new(realearn_service))
         .serve_with_shutdown(
             address,
             async move { shutdown_receiver.recv().await.unwrap() },
@@ -1,91 +1,254 @@
 //! Contains the actual application interface and implementation without any HTTP-specific stuff.
 
 use crate::application::{
-    ControllerPreset, Preset, PresetManager, Session, SourceCategory, TargetCategory,
+    ControllerPreset, Preset, Session, SourceCategory, SourceModel, TargetCategory,
 };
 use crate::base::NamedChannelSender;
 use crate::domain::{
-    Compartment, MappingKey, ProjectionFeedbackValue, RealearnControlSurfaceServerTask,
+    compartment_param_index_iter, BackboneState, Compartment, CompartmentParamIndex, MappingKey,
+    ProjectionFeedbackValue, QualifiedMappingId, RawParamValue, RealearnControlSurfaceServerTask,
+    VirtualControlElement, VirtualControlElementId, VirtualSourceValue,
+};
+use crate::infrastructure::api::convert::from_data::ConversionStyle;
+use crate::infrastructure::api::convert::{from_data, to_data};
+use crate::infrastructure::data::{
+    CompartmentModelData, ControllerPresetData, MappingModelData, PresetData,
 };
-use crate::infrastructure::data::{ControllerPresetData, PresetData};
 use crate::infrastructure::plugin::{App, RealearnControlSurfaceServerTaskSender};
-use helgoboss_learn::UnitValue;
+use crate::infrastructure::server::http::{AppSessionProvider, SessionProvider};
+use helgoboss_learn::{AbsoluteValue, ControlValue, UnitValue};
 use maplit::hashmap;
+use playtime_clip_engine::main::{ClipMatrixEvent, ClipSlotCoordinates};
+use playtime_clip_engine::rt::{
+    ClipChangedEvent, ColumnPlayClipOptions, QualifiedClipChangedEvent,
+};
+use realearn_api::schema;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::rc::Rc;
+use std::str::FromStr;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 // Right now just a placeholder
 pub struct SessionResponseData {}
 
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AddedMappingResponseData {
+    pub id: String,
+}
+
+#[derive(Eq, PartialEq, Debug)]
 pub enum DataError {
     SessionNotFound,
     SessionHasNoActiveController,
     ControllerNotFound,
-    OnlyPatchReplaceIsSupported,
     OnlyCustomDataKeyIsSupportedAsPatchPath,
     CouldntUpdateController,
+    InvalidCompartment,
+    CompartmentIsReadOnly,
+    MappingNotFound,
+    MappingKeyAlreadyExists,
+    InvalidCompartmentData,
+    InvalidMappingData,
+    PatchPathNotFound,
+    PatchValueMissing,
+    InvalidCommand,
+    ParameterIndexOutOfRange,
+    CouldntSetParameter,
+    InvalidControlElementName,
+    SessionHasNoClipMatrix,
+    InvalidClipMatrixAction,
+    ClipMatrixIndexOutOfRange,
+    ClipMatrixActionFailed(&'static str),
 }
 
-#[derive(Deserialize)]
+/// A single JSON Patch (RFC 6902) operation.
+///
+/// Only `add`, `remove` and `replace` are supported.
+#[derive(Deserialize, JsonSchema)]
 pub struct PatchRequest {
     op: PatchRequestOp,
     path: String,
-    value: serde_json::value::Value,
+    #[serde(default)]
+    value: Option<Value>,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 enum PatchRequestOp {
+    Add,
+    Remove,
     Replace,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ControllerRouting {
     main_preset: Option<LightMainPresetData>,
+    #[schemars(with = "HashMap<String, Vec<TargetDescriptor>>")]
     routes: HashMap<MappingKey, Vec<TargetDescriptor>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct LightMainPresetData {
     id: String,
     name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct TargetDescriptor {
     label: String,
 }
 
-pub fn get_session_data(session_id: String) -> Result<SessionResponseData, DataError> {
-    let _ = App::get()
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingsData {
+    #[schemars(with = "HashMap<String, MappingStateData>")]
+    main: HashMap<MappingKey, MappingStateData>,
+    #[schemars(with = "HashMap<String, MappingStateData>")]
+    controller: HashMap<MappingKey, MappingStateData>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct MappingStateData {
+    name: String,
+    is_on: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ParametersData {
+    main: Vec<ParameterData>,
+    controller: Vec<ParameterData>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ParameterData {
+    index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_count: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    value_labels: Vec<String>,
+    /// Normalized value between 0.0 and 1.0.
+    value: RawParamValue,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlEventData {
+    compartment: CompartmentData,
+    #[schemars(with = "String")]
+    mapping_id: MappingKey,
+}
+
+#[derive(Copy, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+enum CompartmentData {
+    Main,
+    Controller,
+}
+
+impl From<Compartment> for CompartmentData {
+    fn from(c: Compartment) -> Self {
+        match c {
+            Compartment::Main => Self::Main,
+            Compartment::Controller => Self::Controller,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ClipMatrixEventData {
+    AllClipsChanged,
+    #[serde(rename_all = "camelCase")]
+    ClipChanged {
+        column_index: usize,
+        row_index: usize,
+        change: ClipChangeData,
+    },
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum ClipChangeData {
+    PlayState(&'static str),
+    Volume(f64),
+    Looped(bool),
+    Position(f64),
+    Removed,
+    RecordingFinished,
+}
+
+impl From<&ClipMatrixEvent> for ClipMatrixEventData {
+    fn from(event: &ClipMatrixEvent) -> Self {
+        match event {
+            ClipMatrixEvent::AllClipsChanged => Self::AllClipsChanged,
+            ClipMatrixEvent::ClipChanged(QualifiedClipChangedEvent {
+                slot_coordinates,
+                event,
+            }) => {
+                use ClipChangedEvent::*;
+                let change = match event {
+                    PlayState(s) => ClipChangeData::PlayState(s.id_string()),
+                    ClipVolume(db) => ClipChangeData::Volume(db.get()),
+                    ClipLooped(looped) => ClipChangeData::Looped(*looped),
+                    ClipPosition(pos) => ClipChangeData::Position(pos.get()),
+                    Removed => ClipChangeData::Removed,
+                    RecordingFinished => ClipChangeData::RecordingFinished,
+                };
+                Self::ClipChanged {
+                    column_index: slot_coordinates.column(),
+                    row_index: slot_coordinates.row(),
+                    change,
+                }
+            }
+        }
+    }
+}
+
+pub fn get_session_data(
+    provider: &dyn SessionProvider,
+    session_id: String,
+) -> Result<SessionResponseData, DataError> {
+    let _ = provider
         .find_session_by_id(&session_id)
         .ok_or(DataError::SessionNotFound)?;
     Ok(SessionResponseData {})
 }
 
 pub fn get_controller_routing_by_session_id(
+    provider: &dyn SessionProvider,
     session_id: String,
 ) -> Result<ControllerRouting, DataError> {
-    let session = App::get()
+    let session = provider
         .find_session_by_id(&session_id)
         .ok_or(DataError::SessionNotFound)?;
     let routing = get_controller_routing(&session.borrow());
     Ok(routing)
 }
 
-pub fn get_controller_preset_data(session_id: String) -> Result<ControllerPresetData, DataError> {
-    let session = App::get()
+pub fn get_controller_preset_data(
+    provider: &dyn SessionProvider,
+    session_id: String,
+) -> Result<ControllerPresetData, DataError> {
+    let session = provider
         .find_session_by_id(&session_id)
         .ok_or(DataError::SessionNotFound)?;
     let session = session.borrow();
-    get_controller_preset_data_internal(&session)
+    get_controller_preset_data_internal(provider, &session)
 }
 
 #[cfg(feature = "realearn-metrics")]
@@ -119,15 +282,18 @@ pub fn get_controller_routing(session: &Session) -> ControllerRouting {
                     let matching_main_mappings = session.mappings(Compartment::Main).filter(|mp| {
                         let mp = mp.borrow();
                         mp.visible_in_projection()
-                            && mp.source_model.category() == SourceCategory::Virtual
-                            && mp.source_model.create_control_element() == control_element
+                            && source_uses_control_element(&mp.source_model, control_element)
                             && instance_state.mapping_is_on(mp.qualified_id())
                     });
                     let descriptors: Vec<_> = matching_main_mappings
                         .map(|m| {
                             let m = m.borrow();
                             TargetDescriptor {
-                                label: m.effective_name(),
+                                label: projection_label(
+                                    &m.source_model,
+                                    control_element,
+                                    m.effective_name(),
+                                ),
                             }
                         })
                         .collect();
@@ -154,28 +320,461 @@ pub fn get_controller_routing(session: &Session) -> ControllerRouting {
     }
 }
 
-pub fn patch_controller(controller_id: String, req: PatchRequest) -> Result<(), DataError> {
-    if req.op != PatchRequestOp::Replace {
-        return Err(DataError::OnlyPatchReplaceIsSupported);
+fn source_uses_control_element(source: &SourceModel, element: VirtualControlElement) -> bool {
+    match source.category() {
+        SourceCategory::Virtual => source.create_control_element() == element,
+        SourceCategory::Chord => source
+            .chord_members()
+            .iter()
+            .any(|m| source_uses_control_element(m, element)),
+        _ => false,
     }
+}
+
+/// For chords, mentions the other members which need to be held as well (e.g. "Mute (+ shift)").
+fn projection_label(
+    source: &SourceModel,
+    element: VirtualControlElement,
+    mapping_name: String,
+) -> String {
+    if source.category() != SourceCategory::Chord {
+        return mapping_name;
+    }
+    let other_members: Vec<_> = source
+        .chord_members()
+        .iter()
+        .filter(|m| !source_uses_control_element(m, element))
+        .map(|m| m.to_string().replace('\n', " "))
+        .collect();
+    if other_members.is_empty() {
+        return mapping_name;
+    }
+    format!("{} (+ {})", mapping_name, other_members.join(" + "))
+}
+
+pub fn patch_controller(
+    provider: &dyn SessionProvider,
+    controller_id: String,
+    req: PatchRequest,
+) -> Result<(), DataError> {
     let split_path: Vec<_> = req.path.split('/').collect();
     let custom_data_key = if let ["", "customData", key] = split_path.as_slice() {
         key
     } else {
         return Err(DataError::OnlyCustomDataKeyIsSupportedAsPatchPath);
     };
-    let controller_manager = App::get().controller_preset_manager();
-    let mut controller_manager = controller_manager.borrow_mut();
-    let mut controller = controller_manager
-        .find_by_id(&controller_id)
+    let mut controller = provider
+        .find_controller_preset_by_id(&controller_id)
         .ok_or(DataError::ControllerNotFound)?;
-    controller.update_custom_data(custom_data_key.to_string(), req.value);
-    controller_manager
-        .update_preset(controller)
+    match req.op {
+        PatchRequestOp::Add | PatchRequestOp::Replace => {
+            let value = req.value.ok_or(DataError::PatchValueMissing)?;
+            controller.update_custom_data(custom_data_key.to_string(), value);
+        }
+        PatchRequestOp::Remove => {
+            controller.remove_custom_data(custom_data_key);
+        }
+    }
+    provider
+        .update_controller_preset(controller)
         .map_err(|_| DataError::CouldntUpdateController)?;
     Ok(())
 }
 
+pub fn parse_compartment(compartment: &str) -> Result<Compartment, DataError> {
+    match compartment {
+        "main" => Ok(Compartment::Main),
+        "controller" => Ok(Compartment::Controller),
+        _ => Err(DataError::InvalidCompartment),
+    }
+}
+
+pub fn get_compartment(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    style: ConversionStyle,
+) -> Result<schema::Compartment, DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let session = session.borrow();
+    let model = session.extract_compartment_model(compartment);
+    let data = CompartmentModelData::from_model(&model);
+    from_data::convert_compartment(data, style).map_err(|_| DataError::InvalidCompartmentData)
+}
+
+/// Replaces the complete compartment.
+pub fn put_compartment(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    api_compartment: schema::Compartment,
+) -> Result<(), DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let mut session = session.borrow_mut();
+    if session.mappings_are_read_only(compartment) {
+        return Err(DataError::CompartmentIsReadOnly);
+    }
+    let data = to_data::convert_compartment(api_compartment)
+        .map_err(|_| DataError::InvalidCompartmentData)?;
+    let model = data
+        .to_model(Some(App::version()), compartment)
+        .map_err(|_| DataError::InvalidCompartmentData)?;
+    session.import_compartment(compartment, Some(model));
+    Ok(())
+}
+
+/// Applies the given JSON Patch operations to the compartment.
+///
+/// The operations are applied to the JSON representation of the compartment (including default
+/// values, so that paths to empty lists are valid). Either all operations succeed or nothing is
+/// changed at all.
+pub fn patch_compartment(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    operations: Vec<PatchRequest>,
+) -> Result<(), DataError> {
+    let api_compartment = get_compartment(
+        provider,
+        session_id,
+        compartment,
+        ConversionStyle::IncludeDefaultValues,
+    )?;
+    let mut json =
+        serde_json::to_value(api_compartment).map_err(|_| DataError::InvalidCompartmentData)?;
+    for op in operations {
+        apply_patch_operation(&mut json, op)?;
+    }
+    let patched_compartment: schema::Compartment =
+        serde_json::from_value(json).map_err(|_| DataError::InvalidCompartmentData)?;
+    put_compartment(provider, session_id, compartment, patched_compartment)
+}
+
+pub fn get_mapping(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    mapping_key: MappingKey,
+) -> Result<schema::Mapping, DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let session = session.borrow();
+    let mapping = session
+        .find_mapping_by_key(compartment, &mapping_key)
+        .ok_or(DataError::MappingNotFound)?;
+    let compartment_in_session = session.compartment_in_session(compartment);
+    let data = MappingModelData::from_model(&mapping.borrow(), &compartment_in_session);
+    from_data::convert_mapping(data, ConversionStyle::Minimal)
+        .map_err(|_| DataError::InvalidMappingData)
+}
+
+/// Adds the given mapping at the end of the compartment's mapping list.
+///
+/// Returns the key of the new mapping. If the mapping doesn't have an ID, a random one is
+/// assigned.
+pub fn add_mapping(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    api_mapping: schema::Mapping,
+) -> Result<MappingKey, DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let mut session = session.borrow_mut();
+    if session.mappings_are_read_only(compartment) {
+        return Err(DataError::CompartmentIsReadOnly);
+    }
+    if let Some(id) = api_mapping.id.as_ref() {
+        if session
+            .find_mapping_by_key(compartment, &MappingKey::from(id.clone()))
+            .is_some()
+        {
+            return Err(DataError::MappingKeyAlreadyExists);
+        }
+    }
+    let model = {
+        let compartment_in_session = session.compartment_in_session(compartment);
+        let data = to_data::convert_mapping(api_mapping, &compartment_in_session)
+            .map_err(|_| DataError::InvalidMappingData)?;
+        data.to_model(
+            compartment,
+            compartment_in_session,
+            Some(session.extended_context()),
+        )
+    };
+    let key = model.key().clone();
+    let index = session.mapping_count(compartment);
+    session.insert_mappings_at(compartment, index, std::iter::once(model));
+    Ok(key)
+}
+
+/// Replaces the mapping with the given key, keeping its position in the list.
+pub fn put_mapping(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    mapping_key: MappingKey,
+    mut api_mapping: schema::Mapping,
+) -> Result<(), DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let mut session = session.borrow_mut();
+    if session.mappings_are_read_only(compartment) {
+        return Err(DataError::CompartmentIsReadOnly);
+    }
+    let (index, mapping_id) = {
+        let (index, mapping) = session
+            .mappings(compartment)
+            .enumerate()
+            .find(|(_, m)| m.borrow().key() == &mapping_key)
+            .ok_or(DataError::MappingNotFound)?;
+        (index, mapping.borrow().id())
+    };
+    // The key in the path always wins.
+    api_mapping.id = Some(mapping_key.into());
+    let model = {
+        let compartment_in_session = session.compartment_in_session(compartment);
+        let data = to_data::convert_mapping(api_mapping, &compartment_in_session)
+            .map_err(|_| DataError::InvalidMappingData)?;
+        data.to_model(
+            compartment,
+            compartment_in_session,
+            Some(session.extended_context()),
+        )
+    };
+    session.remove_mapping(QualifiedMappingId::new(compartment, mapping_id));
+    session.insert_mappings_at(compartment, index, std::iter::once(model));
+    Ok(())
+}
+
+pub fn delete_mapping(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    compartment: Compartment,
+    mapping_key: MappingKey,
+) -> Result<(), DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let mut session = session.borrow_mut();
+    if session.mappings_are_read_only(compartment) {
+        return Err(DataError::CompartmentIsReadOnly);
+    }
+    let id = session
+        .find_mapping_by_key(compartment, &mapping_key)
+        .ok_or(DataError::MappingNotFound)?
+        .borrow()
+        .qualified_id();
+    session.remove_mapping(id);
+    Ok(())
+}
+
+/// An action which can be triggered on the clip matrix via HTTP.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ClipMatrixAction {
+    PlayClip(ClipSlotCoordinates),
+    StopClip(ClipSlotCoordinates),
+    RecordClip(ClipSlotCoordinates),
+    PlayRow(usize),
+    StopColumn(usize),
+    Stop,
+    Undo,
+    Redo,
+}
+
+impl ClipMatrixAction {
+    pub fn parse_slot_action(
+        action: &str,
+        column_index: usize,
+        row_index: usize,
+    ) -> Result<Self, DataError> {
+        let coordinates = ClipSlotCoordinates::new(column_index, row_index);
+        let action = match action {
+            "play" => Self::PlayClip(coordinates),
+            "stop" => Self::StopClip(coordinates),
+            "record" => Self::RecordClip(coordinates),
+            _ => return Err(DataError::InvalidClipMatrixAction),
+        };
+        Ok(action)
+    }
+
+    pub fn parse_row_action(action: &str, row_index: usize) -> Result<Self, DataError> {
+        match action {
+            "play" => Ok(Self::PlayRow(row_index)),
+            _ => Err(DataError::InvalidClipMatrixAction),
+        }
+    }
+
+    pub fn parse_column_action(action: &str, column_index: usize) -> Result<Self, DataError> {
+        match action {
+            "stop" => Ok(Self::StopColumn(column_index)),
+            _ => Err(DataError::InvalidClipMatrixAction),
+        }
+    }
+
+    /// Returns the column and row index this action refers to (if any).
+    fn indexes(&self) -> (Option<usize>, Option<usize>) {
+        use ClipMatrixAction::*;
+        match self {
+            PlayClip(c) | StopClip(c) | RecordClip(c) => (Some(c.column()), Some(c.row())),
+            PlayRow(row_index) => (None, Some(*row_index)),
+            StopColumn(column_index) => (Some(*column_index), None),
+            Stop | Undo | Redo => (None, None),
+        }
+    }
+
+    /// Makes sure that the column and row this action refers to (if any) exist in a matrix with
+    /// the given dimensions.
+    fn check_indexes(&self, column_count: usize, row_count: usize) -> Result<(), DataError> {
+        let (column_index, row_index) = self.indexes();
+        if column_index.map_or(false, |i| i >= column_count)
+            || row_index.map_or(false, |i| i >= row_count)
+        {
+            return Err(DataError::ClipMatrixIndexOutOfRange);
+        }
+        Ok(())
+    }
+
+    pub fn parse_matrix_action(action: &str) -> Result<Self, DataError> {
+        let action = match action {
+            "stop" => Self::Stop,
+            "undo" => Self::Undo,
+            "redo" => Self::Redo,
+            _ => return Err(DataError::InvalidClipMatrixAction),
+        };
+        Ok(action)
+    }
+}
+
+pub fn get_clip_matrix(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+) -> Result<playtime_api::Matrix, DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let session = session.borrow();
+    BackboneState::get()
+        .with_clip_matrix(session.instance_state(), |matrix| matrix.save())
+        .map_err(|_| DataError::SessionHasNoClipMatrix)
+}
+
+pub fn trigger_clip_matrix_action(
+    provider: &dyn SessionProvider,
+    session_id: &str,
+    action: ClipMatrixAction,
+) -> Result<(), DataError> {
+    let session = provider
+        .find_session_by_id(session_id)
+        .ok_or(DataError::SessionNotFound)?;
+    let session = session.borrow();
+    BackboneState::get()
+        .with_clip_matrix_mut(session.instance_state(), |matrix| {
+            // Check indexes here in the main thread. The real-time clip engine would otherwise
+            // grow its slot list up to the given index.
+            action.check_indexes(matrix.column_count(), matrix.row_count())?;
+            use ClipMatrixAction::*;
+            let result = match action {
+                PlayClip(coordinates) => {
+                    let options = ColumnPlayClipOptions {
+                        stop_column_if_slot_empty: false,
+                        start_timing: None,
+                    };
+                    matrix.play_clip(coordinates, options)
+                }
+                StopClip(coordinates) => matrix.stop_clip(coordinates, None),
+                RecordClip(coordinates) => matrix.record_clip(coordinates),
+                PlayRow(row_index) => {
+                    matrix.play_row(row_index);
+                    Ok(())
+                }
+                StopColumn(column_index) => matrix.stop_column(column_index),
+                Stop => {
+                    matrix.stop();
+                    Ok(())
+                }
+                Undo => matrix.undo(),
+                Redo => matrix.redo(),
+            };
+            result.map_err(DataError::ClipMatrixActionFailed)
+        })
+        .map_err(|_| DataError::SessionHasNoClipMatrix)?
+}
+
+fn apply_patch_operation(doc: &mut Value, req: PatchRequest) -> Result<(), DataError> {
+    match req.op {
+        PatchRequestOp::Replace => {
+            let target = doc
+                .pointer_mut(&req.path)
+                .ok_or(DataError::PatchPathNotFound)?;
+            *target = req.value.ok_or(DataError::PatchValueMissing)?;
+        }
+        PatchRequestOp::Add => {
+            let value = req.value.ok_or(DataError::PatchValueMissing)?;
+            let (parent, last_token) = resolve_patch_parent(doc, &req.path)?;
+            match parent {
+                Value::Object(map) => {
+                    map.insert(last_token, value);
+                }
+                Value::Array(array) => {
+                    if last_token == "-" {
+                        array.push(value);
+                    } else {
+                        let index: usize = last_token
+                            .parse()
+                            .map_err(|_| DataError::PatchPathNotFound)?;
+                        if index > array.len() {
+                            return Err(DataError::PatchPathNotFound);
+                        }
+                        array.insert(index, value);
+                    }
+                }
+                _ => return Err(DataError::PatchPathNotFound),
+            }
+        }
+        PatchRequestOp::Remove => {
+            let (parent, last_token) = resolve_patch_parent(doc, &req.path)?;
+            match parent {
+                Value::Object(map) => {
+                    map.remove(&last_token)
+                        .ok_or(DataError::PatchPathNotFound)?;
+                }
+                Value::Array(array) => {
+                    let index: usize = last_token
+                        .parse()
+                        .map_err(|_| DataError::PatchPathNotFound)?;
+                    if index >= array.len() {
+                        return Err(DataError::PatchPathNotFound);
+                    }
+                    array.remove(index);
+                }
+                _ => return Err(DataError::PatchPathNotFound),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits the given JSON pointer into the parent value and the (unescaped) last reference token.
+fn resolve_patch_parent<'a>(
+    doc: &'a mut Value,
+    path: &str,
+) -> Result<(&'a mut Value, String), DataError> {
+    let (parent_path, last_token) = path.rsplit_once('/').ok_or(DataError::PatchPathNotFound)?;
+    let parent = doc
+        .pointer_mut(parent_path)
+        .ok_or(DataError::PatchPathNotFound)?;
+    let last_token = last_token.replace("~1", "/").replace("~0", "~");
+    Ok((parent, last_token))
+}
+
 #[derive(Deserialize)]
 pub struct WebSocketRequest {
     pub topics: String,
@@ -187,6 +786,143 @@ impl WebSocketRequest {
     }
 }
 
+/// A command sent by a WebSocket client.
+///
+/// All values are normalized (between 0.0 and 1.0).
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WebSocketCommand {
+    #[serde(rename_all = "camelCase")]
+    SetParameter {
+        session_id: String,
+        compartment: String,
+        index: u32,
+        value: f64,
+    },
+    #[serde(rename_all = "camelCase")]
+    HitMapping {
+        session_id: String,
+        compartment: String,
+        mapping_id: String,
+        value: f64,
+    },
+    #[serde(rename_all = "camelCase")]
+    EmulateControlElement {
+        session_id: String,
+        /// Name or position of the virtual control element, e.g. "ch1/fader" or "5".
+        control_element: String,
+        #[serde(default)]
+        control_element_type: ControlElementTypeData,
+        value: f64,
+    },
+}
+
+#[derive(Copy, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ControlElementTypeData {
+    Multi,
+    Button,
+}
+
+impl Default for ControlElementTypeData {
+    fn default() -> Self {
+        Self::Multi
+    }
+}
+
+impl WebSocketCommand {
+    pub fn parse(text: &str) -> Result<Self, DataError> {
+        serde_json::from_str(text).map_err(|_| DataError::InvalidCommand)
+    }
+}
+
+/// Sent to the WebSocket client if one of its commands couldn't be executed.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandErrorData {
+    pub r#type: &'static str,
+    pub message: &'static str,
+}
+
+impl CommandErrorData {
+    pub fn new(message: &'static str) -> Self {
+        Self {
+            r#type: "error",
+            message,
+        }
+    }
+}
+
+/// Needs to be executed in the main thread!
+pub fn execute_websocket_command(
+    provider: &dyn SessionProvider,
+    command: WebSocketCommand,
+) -> Result<(), DataError> {
+    use WebSocketCommand::*;
+    match command {
+        SetParameter {
+            session_id,
+            compartment,
+            index,
+            value,
+        } => {
+            let compartment = parse_compartment(&compartment)?;
+            let index = CompartmentParamIndex::try_from(index)
+                .map_err(|_| DataError::ParameterIndexOutOfRange)?;
+            let session = provider
+                .find_session_by_id(&session_id)
+                .ok_or(DataError::SessionNotFound)?;
+            let session = session.borrow();
+            session
+                .set_compartment_param_value(compartment, index, UnitValue::new_clamped(value))
+                .map_err(|_| DataError::CouldntSetParameter)
+        }
+        HitMapping {
+            session_id,
+            compartment,
+            mapping_id,
+            value,
+        } => {
+            let compartment = parse_compartment(&compartment)?;
+            let session = provider
+                .find_session_by_id(&session_id)
+                .ok_or(DataError::SessionNotFound)?;
+            let session = session.borrow();
+            let mapping = session
+                .find_mapping_by_key(compartment, &MappingKey::from(mapping_id))
+                .ok_or(DataError::MappingNotFound)?;
+            let id = mapping.borrow().qualified_id();
+            let value = AbsoluteValue::Continuous(UnitValue::new_clamped(value));
+            session.hit_target(id, value);
+            Ok(())
+        }
+        EmulateControlElement {
+            session_id,
+            control_element,
+            control_element_type,
+            value,
+        } => {
+            let id = VirtualControlElementId::from_str(&control_element)
+                .map_err(|_| DataError::InvalidControlElementName)?;
+            let control_element = match control_element_type {
+                ControlElementTypeData::Multi => VirtualControlElement::Multi(id),
+                ControlElementTypeData::Button => VirtualControlElement::Button(id),
+            };
+            let control_value = ControlValue::AbsoluteContinuous(UnitValue::new_clamped(value));
+            let session = provider
+                .find_session_by_id(&session_id)
+                .ok_or(DataError::SessionNotFound)?;
+            session
+                .borrow()
+                .emulate_virtual_control_element(VirtualSourceValue::new(
+                    control_element,
+                    control_value,
+                ));
+            Ok(())
+        }
+    }
+}
+
 pub type Topics = HashSet<Topic>;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
@@ -195,6 +931,10 @@ pub enum Topic {
     ActiveController { session_id: String },
     ControllerRouting { session_id: String },
     Feedback { session_id: String },
+    Mappings { session_id: String },
+    Parameters { session_id: String },
+    ControlEvents { session_id: String },
+    ClipMatrix { session_id: String },
 }
 
 impl TryFrom<&str> for Topic {
@@ -212,6 +952,18 @@ impl TryFrom<&str> for Topic {
             ["realearn", "session", id, "feedback"] => Topic::Feedback {
                 session_id: id.to_string(),
             },
+            ["realearn", "session", id, "mappings"] => Topic::Mappings {
+                session_id: id.to_string(),
+            },
+            ["realearn", "session", id, "parameters"] => Topic::Parameters {
+                session_id: id.to_string(),
+            },
+            ["realearn", "session", id, "control-events"] => Topic::ControlEvents {
+                session_id: id.to_string(),
+            },
+            ["realearn", "session", id, "clip-matrix"] => Topic::ClipMatrix {
+                session_id: id.to_string(),
+            },
             ["realearn", "session", id] => Topic::Session {
                 session_id: id.to_string(),
             },
@@ -266,7 +1018,95 @@ pub fn get_controller_routing_updated_event(
     )
 }
 
-#[derive(Serialize)]
+pub fn get_mappings_updated_event(
+    session_id: &str,
+    session: Option<&Session>,
+) -> Event<Option<MappingsData>> {
+    Event::put(
+        format!("/realearn/session/{}/mappings", session_id),
+        session.map(get_mappings_data),
+    )
+}
+
+pub fn get_parameters_updated_event(
+    session_id: &str,
+    session: Option<&Session>,
+) -> Event<Option<ParametersData>> {
+    Event::put(
+        format!("/realearn/session/{}/parameters", session_id),
+        session.map(get_parameters_data),
+    )
+}
+
+pub fn get_control_event(
+    session_id: &str,
+    compartment: Compartment,
+    mapping_key: MappingKey,
+) -> Event<ControlEventData> {
+    Event::post(
+        format!("/realearn/session/{}/control-events", session_id),
+        ControlEventData {
+            compartment: compartment.into(),
+            mapping_id: mapping_key,
+        },
+    )
+}
+
+pub fn get_clip_matrix_event(
+    session_id: &str,
+    event: &ClipMatrixEvent,
+) -> Event<ClipMatrixEventData> {
+    Event::post(
+        format!("/realearn/session/{}/clip-matrix", session_id),
+        event.into(),
+    )
+}
+
+fn get_mappings_data(session: &Session) -> MappingsData {
+    let get_states = |compartment: Compartment| {
+        session
+            .mappings(compartment)
+            .map(|m| {
+                let m = m.borrow();
+                let state = MappingStateData {
+                    name: m.effective_name(),
+                    is_on: session.mapping_is_on(m.qualified_id()),
+                };
+                (m.key().clone(), state)
+            })
+            .collect()
+    };
+    MappingsData {
+        main: get_states(Compartment::Main),
+        controller: get_states(Compartment::Controller),
+    }
+}
+
+fn get_parameters_data(session: &Session) -> ParametersData {
+    let get_params = |compartment: Compartment| {
+        let params = session.params().compartment_params(compartment);
+        compartment_param_index_iter()
+            .map(|i| {
+                let param = params.at(i);
+                let setting = param.setting();
+                ParameterData {
+                    index: i.get(),
+                    key: setting.key.clone(),
+                    name: params.get_parameter_name(i).into_owned(),
+                    value_count: setting.value_count.map(|c| c.get()),
+                    value_labels: setting.value_labels.clone(),
+                    value: param.raw_value(),
+                }
+            })
+            .collect()
+    };
+    ParametersData {
+        main: get_params(Compartment::Main),
+        controller: get_params(Compartment::Controller),
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Event<T> {
     /// Roughly corresponds to the HTTP method of the resource.
@@ -296,20 +1136,32 @@ impl<T> Event<T> {
             body,
         }
     }
+
+    pub fn post(path: String, body: T) -> Event<T> {
+        Event {
+            r#type: EventType::Post,
+            path,
+            body,
+        }
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 enum EventType {
     Put,
     Patch,
+    /// Used for transient events which don't represent the state of a resource (e.g. a mapping
+    /// has matched), so the body describes what happened, not what is.
+    Post,
 }
 
 fn get_controller(session: &Session) -> Option<ControllerPresetData> {
-    get_controller_preset_data_internal(session).ok()
+    get_controller_preset_data_internal(&AppSessionProvider, session).ok()
 }
 
 fn get_controller_preset_data_internal(
+    provider: &dyn SessionProvider,
     session: &Session,
 ) -> Result<ControllerPresetData, DataError> {
     let data = session.extract_compartment_model(Compartment::Controller);
@@ -318,12 +1170,7 @@ fn get_controller_preset_data_internal(
     }
     let id = session.active_controller_preset_id();
     let name = id
-        .and_then(|id| {
-            App::get()
-                .controller_preset_manager()
-                .borrow()
-                .find_by_id(id)
-        })
+        .and_then(|id| provider.find_controller_preset_by_id(id))
         .map(|preset| preset.name().to_string());
     let preset = ControllerPreset::new(
         id.map(|id| id.to_string()).unwrap_or_default(),
@@ -332,3 +1179,348 @@ fn get_controller_preset_data_internal(
     );
     Ok(ControllerPresetData::from_model(&preset))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::server::http::fake::FakeSessionProvider;
+    use serde_json::json;
+
+    #[test]
+    fn parse_topics() {
+        // Given
+        let request = WebSocketRequest {
+            topics: "/realearn/session/a,/realearn/session/a/controller,\
+                /realearn/session/a/controller-routing,/realearn/session/a/feedback,\
+                /realearn/session/a/mappings,/realearn/session/a/parameters,\
+                /realearn/session/a/control-events,/realearn/session/a/clip-matrix"
+                .to_string(),
+        };
+        // When
+        let topics = request.parse_topics();
+        // Then
+        let session_id = || "a".to_string();
+        let expected_topics: Topics = vec![
+            Topic::Session {
+                session_id: session_id(),
+            },
+            Topic::ActiveController {
+                session_id: session_id(),
+            },
+            Topic::ControllerRouting {
+                session_id: session_id(),
+            },
+            Topic::Feedback {
+                session_id: session_id(),
+            },
+            Topic::Mappings {
+                session_id: session_id(),
+            },
+            Topic::Parameters {
+                session_id: session_id(),
+            },
+            Topic::ControlEvents {
+                session_id: session_id(),
+            },
+            Topic::ClipMatrix {
+                session_id: session_id(),
+            },
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(topics, expected_topics);
+    }
+
+    #[test]
+    fn parse_topics_ignores_invalid_ones() {
+        // Given
+        let request = WebSocketRequest {
+            topics: "realearn/session/a,/realearn/session/b/feedback,/realearn/session,\
+                /realearn/session/c/foo,,/foo"
+                .to_string(),
+        };
+        // When
+        let topics = request.parse_topics();
+        // Then
+        assert_eq!(topics.len(), 1);
+        assert!(topics.contains(&Topic::Feedback {
+            session_id: "b".to_string()
+        }));
+    }
+
+    #[test]
+    fn invalid_topics() {
+        assert!(Topic::try_from("").is_err());
+        assert!(Topic::try_from("/").is_err());
+        assert!(Topic::try_from("/realearn").is_err());
+        assert!(Topic::try_from("/realearn/session/a/feedback/foo").is_err());
+        assert!(Topic::try_from("/realearn/controller/a").is_err());
+    }
+
+    #[test]
+    fn serialize_event() {
+        // Given
+        let event = Event::put("/realearn/session/a".to_string(), Some(5));
+        // When
+        let json = serde_json::to_value(&event).unwrap();
+        // Then
+        assert_eq!(
+            json,
+            json!({
+                "type": "put",
+                "path": "/realearn/session/a",
+                "body": 5
+            })
+        );
+    }
+
+    #[test]
+    fn parse_websocket_command() {
+        let command = WebSocketCommand::parse(
+            r#"{"type": "setParameter", "sessionId": "a", "compartment": "main", "index": 5, "value": 0.5}"#,
+        );
+        assert!(matches!(
+            command,
+            Ok(WebSocketCommand::SetParameter { index: 5, .. })
+        ));
+        assert_eq!(
+            WebSocketCommand::parse(r#"{"type": "foo"}"#).err(),
+            Some(DataError::InvalidCommand)
+        );
+        assert_eq!(
+            WebSocketCommand::parse("foo").err(),
+            Some(DataError::InvalidCommand)
+        );
+    }
+
+    #[test]
+    fn apply_patch_operations() {
+        // Given
+        let mut doc = json!({
+            "mappings": [{ "id": "a" }],
+            "custom_data": {}
+        });
+        // When
+        let add = |path: &str, value: Value| PatchRequest {
+            op: PatchRequestOp::Add,
+            path: path.to_string(),
+            value: Some(value),
+        };
+        apply_patch_operation(&mut doc, add("/mappings/-", json!({ "id": "b" }))).unwrap();
+        apply_patch_operation(&mut doc, add("/mappings/0", json!({ "id": "c" }))).unwrap();
+        apply_patch_operation(&mut doc, add("/custom_data/x~1y", json!(1))).unwrap();
+        apply_patch_operation(
+            &mut doc,
+            PatchRequest {
+                op: PatchRequestOp::Replace,
+                path: "/mappings/1/id".to_string(),
+                value: Some(json!("d")),
+            },
+        )
+        .unwrap();
+        apply_patch_operation(
+            &mut doc,
+            PatchRequest {
+                op: PatchRequestOp::Remove,
+                path: "/mappings/2".to_string(),
+                value: None,
+            },
+        )
+        .unwrap();
+        // Then
+        assert_eq!(
+            doc,
+            json!({
+                "mappings": [{ "id": "c" }, { "id": "d" }],
+                "custom_data": { "x/y": 1 }
+            })
+        );
+        assert_eq!(
+            apply_patch_operation(&mut doc, add("/mappings/5", json!(null))),
+            Err(DataError::PatchPathNotFound)
+        );
+        assert_eq!(
+            apply_patch_operation(&mut doc, add("/foo/bar", json!(null))),
+            Err(DataError::PatchPathNotFound)
+        );
+        assert_eq!(
+            apply_patch_operation(
+                &mut doc,
+                PatchRequest {
+                    op: PatchRequestOp::Replace,
+                    path: "/mappings/0/id".to_string(),
+                    value: None,
+                }
+            ),
+            Err(DataError::PatchValueMissing)
+        );
+    }
+
+    #[test]
+    fn patch_controller_errors() {
+        // Given
+        let provider = FakeSessionProvider::default().with_controller("a");
+        let req = |op: PatchRequestOp, path: &str, value: Option<Value>| PatchRequest {
+            op,
+            path: path.to_string(),
+            value,
+        };
+        // When
+        // Then
+        assert_eq!(
+            patch_controller(
+                &provider,
+                "a".to_string(),
+                req(PatchRequestOp::Add, "/name", Some(json!("b")))
+            ),
+            Err(DataError::OnlyCustomDataKeyIsSupportedAsPatchPath)
+        );
+        assert_eq!(
+            patch_controller(
+                &provider,
+                "a".to_string(),
+                req(PatchRequestOp::Add, "/customData/x/y", Some(json!(1)))
+            ),
+            Err(DataError::OnlyCustomDataKeyIsSupportedAsPatchPath)
+        );
+        assert_eq!(
+            patch_controller(
+                &provider,
+                "b".to_string(),
+                req(PatchRequestOp::Add, "/customData/x", Some(json!(1)))
+            ),
+            Err(DataError::ControllerNotFound)
+        );
+        assert_eq!(
+            patch_controller(
+                &provider,
+                "a".to_string(),
+                req(PatchRequestOp::Replace, "/customData/x", None)
+            ),
+            Err(DataError::PatchValueMissing)
+        );
+        assert!(provider.controller_custom_data("a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn patch_controller_custom_data() {
+        // Given
+        let provider = FakeSessionProvider::default().with_controller("a");
+        // When
+        patch_controller(
+            &provider,
+            "a".to_string(),
+            PatchRequest {
+                op: PatchRequestOp::Add,
+                path: "/customData/x".to_string(),
+                value: Some(json!({ "y": 1 })),
+            },
+        )
+        .unwrap();
+        let data_after_add = provider.controller_custom_data("a").unwrap();
+        patch_controller(
+            &provider,
+            "a".to_string(),
+            PatchRequest {
+                op: PatchRequestOp::Remove,
+                path: "/customData/x".to_string(),
+                value: None,
+            },
+        )
+        .unwrap();
+        let data_after_remove = provider.controller_custom_data("a").unwrap();
+        // Then
+        assert_eq!(data_after_add["x"], json!({ "y": 1 }));
+        assert!(data_after_remove.is_empty());
+    }
+
+    #[test]
+    fn parse_clip_matrix_actions() {
+        let coordinates = ClipSlotCoordinates::new(2, 3);
+        assert_eq!(
+            ClipMatrixAction::parse_slot_action("record", 2, 3),
+            Ok(ClipMatrixAction::RecordClip(coordinates))
+        );
+        assert_eq!(
+            ClipMatrixAction::parse_row_action("play", 3),
+            Ok(ClipMatrixAction::PlayRow(3))
+        );
+        assert_eq!(
+            ClipMatrixAction::parse_column_action("stop", 2),
+            Ok(ClipMatrixAction::StopColumn(2))
+        );
+        assert_eq!(
+            ClipMatrixAction::parse_matrix_action("undo"),
+            Ok(ClipMatrixAction::Undo)
+        );
+        assert_eq!(
+            ClipMatrixAction::parse_slot_action("undo", 2, 3),
+            Err(DataError::InvalidClipMatrixAction)
+        );
+        assert_eq!(
+            ClipMatrixAction::parse_matrix_action("play"),
+            Err(DataError::InvalidClipMatrixAction)
+        );
+    }
+
+    #[test]
+    fn clip_matrix_action_indexes() {
+        // Given
+        let slot_action = ClipMatrixAction::parse_slot_action("play", 2, 3).unwrap();
+        let row_action = ClipMatrixAction::parse_row_action("play", 4).unwrap();
+        let column_action = ClipMatrixAction::parse_column_action("stop", 5).unwrap();
+        // When
+        // Then
+        assert_eq!(slot_action.indexes(), (Some(2), Some(3)));
+        assert_eq!(row_action.indexes(), (None, Some(4)));
+        assert_eq!(column_action.indexes(), (Some(5), None));
+        assert_eq!(ClipMatrixAction::Stop.indexes(), (None, None));
+    }
+
+    #[test]
+    fn clip_matrix_index_out_of_range() {
+        // Given
+        let slot_action = ClipMatrixAction::parse_slot_action("play", 2, 3).unwrap();
+        let row_action = ClipMatrixAction::parse_row_action("play", 4).unwrap();
+        let column_action = ClipMatrixAction::parse_column_action("stop", 5).unwrap();
+        // When
+        // Then
+        assert_eq!(slot_action.check_indexes(3, 4), Ok(()));
+        assert_eq!(
+            slot_action.check_indexes(2, 4),
+            Err(DataError::ClipMatrixIndexOutOfRange)
+        );
+        assert_eq!(
+            slot_action.check_indexes(3, 3),
+            Err(DataError::ClipMatrixIndexOutOfRange)
+        );
+        assert_eq!(row_action.check_indexes(0, 5), Ok(()));
+        assert_eq!(
+            row_action.check_indexes(8, 4),
+            Err(DataError::ClipMatrixIndexOutOfRange)
+        );
+        assert_eq!(column_action.check_indexes(6, 0), Ok(()));
+        assert_eq!(
+            column_action.check_indexes(5, 8),
+            Err(DataError::ClipMatrixIndexOutOfRange)
+        );
+        assert_eq!(ClipMatrixAction::Stop.check_indexes(0, 0), Ok(()));
+    }
+
+    #[test]
+    fn session_not_found() {
+        let provider = FakeSessionProvider::default();
+        assert_eq!(
+            get_session_data(&provider, "a".to_string()).err(),
+            Some(DataError::SessionNotFound)
+        );
+        assert_eq!(
+            get_compartment(&provider, "a", Compartment::Main, ConversionStyle::Minimal).err(),
+            Some(DataError::SessionNotFound)
+        );
+        assert_eq!(
+            trigger_clip_matrix_action(&provider, "a", ClipMatrixAction::Stop),
+            Err(DataError::SessionNotFound)
+        );
+    }
+}
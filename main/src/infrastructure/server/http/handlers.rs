@@ -1,19 +1,29 @@
+#[cfg(feature = "realearn-metrics")]
+use crate::base::export_channel_metrics;
 use crate::base::Global;
+use crate::domain::MappingKey;
+use crate::infrastructure::api::convert::from_data::ConversionStyle;
 use crate::infrastructure::data::ControllerPresetData;
 use crate::infrastructure::plugin::RealearnControlSurfaceServerTaskSender;
+use crate::infrastructure::server::auth::{log_rejection, AccessDenied, AccessScope};
 use crate::infrastructure::server::http::{
-    get_controller_preset_data, get_controller_routing_by_session_id, get_session_data,
-    obtain_control_surface_metrics_snapshot, patch_controller, send_initial_events,
-    ControllerRouting, DataError, PatchRequest, ServerClients, SessionResponseData, Topics,
-    WebSocketClient,
+    add_mapping, create_openapi_document, delete_mapping, execute_websocket_command,
+    get_clip_matrix, get_compartment, get_controller_preset_data,
+    get_controller_routing_by_session_id, get_mapping, get_session_data,
+    obtain_control_surface_metrics_snapshot, parse_compartment, patch_compartment,
+    patch_controller, put_compartment, put_mapping, send_initial_events,
+    trigger_clip_matrix_action, AddedMappingResponseData, ClipMatrixAction, CommandErrorData,
+    ControllerRouting, DataError, PatchRequest, ServerClients, SessionResponseData,
+    SharedSessionProvider, Topics, WebSocketClient, WebSocketCommand,
 };
 use axum::body::{boxed, Body, BoxBody};
 use axum::extract::ws::{Message, WebSocket};
-use axum::extract::Path;
+use axum::extract::{Extension, Path};
 use axum::http::{Response, StatusCode};
 use axum::response::Html;
 use axum::Json;
 use metrics_exporter_prometheus::PrometheusHandle;
+use realearn_api::schema;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -24,37 +34,207 @@ pub async fn welcome_handler() -> Html<&'static str> {
     Html(include_str!("../http/welcome_page.html"))
 }
 
+pub async fn openapi_handler(auth_enabled: bool) -> Json<serde_json::Value> {
+    Json(create_openapi_document(auth_enabled))
+}
+
 /// Needs to be executed in the main thread!
 pub async fn session_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
     Path(session_id): Path<String>,
 ) -> Result<Json<SessionResponseData>, SimpleResponse> {
-    let session_data = get_session_data(session_id).map_err(translate_data_error)?;
+    let session_data =
+        get_session_data(provider.as_ref(), session_id).map_err(translate_data_error)?;
     Ok(Json(session_data))
 }
 
 /// Needs to be executed in the main thread!
 pub async fn session_controller_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
     Path(session_id): Path<String>,
 ) -> Result<Json<ControllerPresetData>, SimpleResponse> {
-    let controller_data = get_controller_preset_data(session_id).map_err(translate_data_error)?;
+    let controller_data =
+        get_controller_preset_data(provider.as_ref(), session_id).map_err(translate_data_error)?;
     Ok(Json(controller_data))
 }
 
 /// Needs to be executed in the main thread!
 pub async fn controller_routing_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
     Path(session_id): Path<String>,
 ) -> Result<Json<ControllerRouting>, SimpleResponse> {
-    let controller_routing =
-        get_controller_routing_by_session_id(session_id).map_err(translate_data_error)?;
+    let controller_routing = get_controller_routing_by_session_id(provider.as_ref(), session_id)
+        .map_err(translate_data_error)?;
     Ok(Json(controller_routing))
 }
 
 /// Needs to be executed in the main thread!
 pub async fn patch_controller_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
     Path(controller_id): Path<String>,
     Json(patch_request): Json<PatchRequest>,
 ) -> Result<StatusCode, SimpleResponse> {
-    patch_controller(controller_id, patch_request).map_err(translate_data_error)?;
+    patch_controller(provider.as_ref(), controller_id, patch_request)
+        .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn get_compartment_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment)): Path<(String, String)>,
+) -> Result<Json<schema::Compartment>, SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    let api_compartment = get_compartment(
+        provider.as_ref(),
+        &session_id,
+        compartment,
+        ConversionStyle::Minimal,
+    )
+    .map_err(translate_data_error)?;
+    Ok(Json(api_compartment))
+}
+
+/// Needs to be executed in the main thread!
+pub async fn put_compartment_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment)): Path<(String, String)>,
+    Json(api_compartment): Json<schema::Compartment>,
+) -> Result<StatusCode, SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    put_compartment(provider.as_ref(), &session_id, compartment, api_compartment)
+        .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn patch_compartment_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment)): Path<(String, String)>,
+    Json(operations): Json<Vec<PatchRequest>>,
+) -> Result<StatusCode, SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    patch_compartment(provider.as_ref(), &session_id, compartment, operations)
+        .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn add_mapping_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment)): Path<(String, String)>,
+    Json(api_mapping): Json<schema::Mapping>,
+) -> Result<(StatusCode, Json<AddedMappingResponseData>), SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    let key = add_mapping(provider.as_ref(), &session_id, compartment, api_mapping)
+        .map_err(translate_data_error)?;
+    let response_data = AddedMappingResponseData { id: key.into() };
+    Ok((StatusCode::CREATED, Json(response_data)))
+}
+
+/// Needs to be executed in the main thread!
+pub async fn get_mapping_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment, mapping_key)): Path<(String, String, String)>,
+) -> Result<Json<schema::Mapping>, SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    let api_mapping = get_mapping(
+        provider.as_ref(),
+        &session_id,
+        compartment,
+        MappingKey::from(mapping_key),
+    )
+    .map_err(translate_data_error)?;
+    Ok(Json(api_mapping))
+}
+
+/// Needs to be executed in the main thread!
+pub async fn put_mapping_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment, mapping_key)): Path<(String, String, String)>,
+    Json(api_mapping): Json<schema::Mapping>,
+) -> Result<StatusCode, SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    put_mapping(
+        provider.as_ref(),
+        &session_id,
+        compartment,
+        MappingKey::from(mapping_key),
+        api_mapping,
+    )
+    .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn delete_mapping_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, compartment, mapping_key)): Path<(String, String, String)>,
+) -> Result<StatusCode, SimpleResponse> {
+    let compartment = parse_compartment(&compartment).map_err(translate_data_error)?;
+    delete_mapping(
+        provider.as_ref(),
+        &session_id,
+        compartment,
+        MappingKey::from(mapping_key),
+    )
+    .map_err(translate_data_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn get_clip_matrix_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path(session_id): Path<String>,
+) -> Result<Json<playtime_api::Matrix>, SimpleResponse> {
+    let matrix = get_clip_matrix(provider.as_ref(), &session_id).map_err(translate_data_error)?;
+    Ok(Json(matrix))
+}
+
+/// Needs to be executed in the main thread!
+pub async fn clip_matrix_action_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, action)): Path<(String, String)>,
+) -> Result<StatusCode, SimpleResponse> {
+    let action = ClipMatrixAction::parse_matrix_action(&action).map_err(translate_data_error)?;
+    trigger_clip_matrix_action(provider.as_ref(), &session_id, action)
+        .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn clip_column_action_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, column_index, action)): Path<(String, usize, String)>,
+) -> Result<StatusCode, SimpleResponse> {
+    let action = ClipMatrixAction::parse_column_action(&action, column_index)
+        .map_err(translate_data_error)?;
+    trigger_clip_matrix_action(provider.as_ref(), &session_id, action)
+        .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn clip_row_action_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, row_index, action)): Path<(String, usize, String)>,
+) -> Result<StatusCode, SimpleResponse> {
+    let action =
+        ClipMatrixAction::parse_row_action(&action, row_index).map_err(translate_data_error)?;
+    trigger_clip_matrix_action(provider.as_ref(), &session_id, action)
+        .map_err(translate_data_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Needs to be executed in the main thread!
+pub async fn clip_slot_action_handler(
+    Extension(provider): Extension<SharedSessionProvider>,
+    Path((session_id, column_index, row_index, action)): Path<(String, usize, usize, String)>,
+) -> Result<StatusCode, SimpleResponse> {
+    let action = ClipMatrixAction::parse_slot_action(&action, column_index, row_index)
+        .map_err(translate_data_error)?;
+    trigger_clip_matrix_action(provider.as_ref(), &session_id, action)
+        .map_err(translate_data_error)?;
     Ok(StatusCode::OK)
 }
 
@@ -76,6 +256,7 @@ pub async fn create_metrics_response(
     prometheus_handle: PrometheusHandle,
     control_surface_metrics_enabled: bool,
 ) -> Response<BoxBody> {
+    export_channel_metrics();
     let mut text = prometheus_handle.render();
     if control_surface_metrics_enabled {
         obtain_control_surface_metrics_snapshot(control_surface_task_sender)
@@ -105,7 +286,13 @@ pub async fn create_metrics_response(
     }
 }
 
-pub async fn handle_websocket_upgrade(socket: WebSocket, topics: Topics, clients: ServerClients) {
+pub async fn handle_websocket_upgrade(
+    socket: WebSocket,
+    topics: Topics,
+    clients: ServerClients,
+    access_scope: AccessScope,
+    session_provider: SharedSessionProvider,
+) {
     use futures::{FutureExt, StreamExt};
     let (ws_sender_sink, mut ws_receiver_stream) = socket.split();
     let (client_sender, client_receiver) = mpsc::unbounded_channel();
@@ -132,36 +319,65 @@ pub async fn handle_websocket_upgrade(socket: WebSocket, topics: Topics, clients
     // Memorize client
     clients.write().unwrap().insert(client_id, client.clone());
     // Send initial events
+    let initial_client = client.clone();
     Global::task_support()
         .do_later_in_main_thread_asap(move || {
-            send_initial_events(&client);
+            send_initial_events(&initial_client);
         })
         .unwrap();
     // Keep receiving websocket receiver stream messages
     while let Some(result) = ws_receiver_stream.next().await {
-        // We will need this as soon as we are interested in what the client says
-        let _msg = match result {
+        let msg = match result {
             Ok(msg) => msg,
             Err(e) => {
                 eprintln!("websocket error: {}", e);
                 break;
             }
         };
+        if let Message::Text(text) = msg {
+            handle_websocket_command(&client, access_scope, &session_provider, &text);
+        }
     }
     // Stream closed up, so remove from the client list
     clients.write().unwrap().remove(&client_id);
 }
 
+fn handle_websocket_command(
+    client: &WebSocketClient,
+    access_scope: AccessScope,
+    session_provider: &SharedSessionProvider,
+    text: &str,
+) {
+    if !access_scope.includes(AccessScope::ReadWrite) {
+        let reason = AccessDenied::ScopeInsufficient;
+        log_rejection("WebSocket", "command", reason);
+        let _ = client.send(CommandErrorData::new(
+            "access token doesn't allow write access",
+        ));
+        return;
+    }
+    let command = match WebSocketCommand::parse(text) {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = client.send(CommandErrorData::new(translate_data_error(e).1));
+            return;
+        }
+    };
+    let client = client.clone();
+    let session_provider = session_provider.clone();
+    let _ = Global::task_support().do_later_in_main_thread_asap(move || {
+        if let Err(e) = execute_websocket_command(session_provider.as_ref(), command) {
+            let _ = client.send(CommandErrorData::new(translate_data_error(e).1));
+        }
+    });
+}
+
 fn translate_data_error(e: DataError) -> SimpleResponse {
     use DataError::*;
     match e {
         SessionNotFound => not_found("session not found"),
         SessionHasNoActiveController => not_found("session doesn't have an active controller"),
         ControllerNotFound => not_found("session has controller but controller not found"),
-        OnlyPatchReplaceIsSupported => (
-            StatusCode::METHOD_NOT_ALLOWED,
-            "only 'replace' is supported as op",
-        ),
         OnlyCustomDataKeyIsSupportedAsPatchPath => (
             StatusCode::BAD_REQUEST,
             "only '/customData/{key}' is supported as path",
@@ -170,6 +386,37 @@ fn translate_data_error(e: DataError) -> SimpleResponse {
             StatusCode::INTERNAL_SERVER_ERROR,
             "couldn't update controller",
         ),
+        InvalidCompartment => (
+            StatusCode::BAD_REQUEST,
+            "compartment must be 'main' or 'controller'",
+        ),
+        CompartmentIsReadOnly => (
+            StatusCode::CONFLICT,
+            "compartment is read-only at the moment (e.g. because of preset auto-load)",
+        ),
+        MappingNotFound => not_found("mapping not found"),
+        MappingKeyAlreadyExists => (StatusCode::CONFLICT, "mapping with that ID exists already"),
+        InvalidCompartmentData => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "compartment data is invalid",
+        ),
+        InvalidMappingData => (StatusCode::UNPROCESSABLE_ENTITY, "mapping data is invalid"),
+        PatchPathNotFound => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "patch path doesn't point to an existing location",
+        ),
+        PatchValueMissing => (StatusCode::BAD_REQUEST, "patch operation requires a value"),
+        InvalidCommand => (StatusCode::BAD_REQUEST, "command is invalid"),
+        ParameterIndexOutOfRange => (StatusCode::BAD_REQUEST, "parameter index out of range"),
+        CouldntSetParameter => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "couldn't set parameter value",
+        ),
+        InvalidControlElementName => (StatusCode::BAD_REQUEST, "control element name is invalid"),
+        SessionHasNoClipMatrix => not_found("session doesn't have a clip matrix"),
+        InvalidClipMatrixAction => (StatusCode::BAD_REQUEST, "clip matrix action not supported"),
+        ClipMatrixIndexOutOfRange => not_found("clip matrix doesn't have such a column or row"),
+        ClipMatrixActionFailed(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
     }
 }
 
@@ -1,10 +1,14 @@
 mod client;
 mod data;
 mod handlers;
+mod openapi;
 mod send;
 mod server;
+mod session_provider;
 
 pub use client::*;
 pub use data::*;
+pub use openapi::*;
 pub use send::*;
 pub use server::*;
+pub use session_provider::*;
@@ -0,0 +1,346 @@
+//! Contains the OpenAPI description of the HTTP/WebSocket API.
+//!
+//! The payload schemas are derived from the Rust types, so they can't get out of sync. The paths
+//! need to be kept in sync with `create_router` manually.
+use crate::infrastructure::plugin::App;
+use crate::infrastructure::server::http::{
+    AddedMappingResponseData, ClipMatrixEventData, CommandErrorData, ControlEventData,
+    ControllerRouting, Event, MappingsData, ParametersData, PatchRequest, SessionResponseData,
+    WebSocketCommand,
+};
+use realearn_api::schema;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Creates the OpenAPI 3 document.
+///
+/// If authentication is enabled, the document declares the bearer token scheme and the responses
+/// for missing or insufficient tokens.
+pub fn create_openapi_document(auth_enabled: bool) -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let session_id = path_param("id", "ID of the ReaLearn session (instance)");
+    let compartment = path_param("compartment", "Either `main` or `controller`");
+    let mapping_key = path_param("key", "Key (ID) of the mapping");
+    let column_index = index_path_param("column", "Zero-based index of the clip column");
+    let row_index = index_path_param("row", "Zero-based index of the clip row");
+    let mut paths = Map::new();
+    paths.insert(
+        "/realearn/session/{id}".to_string(),
+        json!({
+            "get": {
+                "summary": "Returns basic information about the session",
+                "parameters": [&session_id],
+                "responses": ok_with(ref_for::<SessionResponseData>(&mut gen)),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/controller".to_string(),
+        json!({
+            "get": {
+                "summary": "Returns the controller preset which is active in the session",
+                "parameters": [&session_id],
+                "responses": ok_with(json!({ "type": "object" })),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/controller-routing".to_string(),
+        json!({
+            "get": {
+                "summary": "Returns which controller mappings currently lead to which targets",
+                "parameters": [&session_id],
+                "responses": ok_with(ref_for::<ControllerRouting>(&mut gen)),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/compartment/{compartment}".to_string(),
+        json!({
+            "get": {
+                "summary": "Returns the complete compartment",
+                "parameters": [&session_id, &compartment],
+                "responses": with_invalid_compartment(
+                    ok_with(ref_for::<schema::Compartment>(&mut gen))
+                ),
+            },
+            "put": {
+                "summary": "Replaces the complete compartment",
+                "parameters": [&session_id, &compartment],
+                "requestBody": body(ref_for::<schema::Compartment>(&mut gen)),
+                "responses": with_invalid_compartment(ok()),
+            },
+            "patch": {
+                "summary": "Applies JSON Patch operations to the compartment",
+                "parameters": [&session_id, &compartment],
+                "requestBody": body(ref_for::<Vec<PatchRequest>>(&mut gen)),
+                "responses": with_invalid_compartment(ok()),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/compartment/{compartment}/mappings".to_string(),
+        json!({
+            "post": {
+                "summary": "Adds a mapping to the end of the compartment",
+                "parameters": [&session_id, &compartment],
+                "requestBody": body(ref_for::<schema::Mapping>(&mut gen)),
+                "responses": with_invalid_compartment(json!({
+                    "201": {
+                        "description": "Created",
+                        "content": json_content(ref_for::<AddedMappingResponseData>(&mut gen)),
+                    }
+                })),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/compartment/{compartment}/mappings/{key}".to_string(),
+        json!({
+            "get": {
+                "summary": "Returns the mapping",
+                "parameters": [&session_id, &compartment, &mapping_key],
+                "responses": with_invalid_compartment(
+                    ok_with(ref_for::<schema::Mapping>(&mut gen))
+                ),
+            },
+            "put": {
+                "summary": "Replaces the mapping",
+                "parameters": [&session_id, &compartment, &mapping_key],
+                "requestBody": body(ref_for::<schema::Mapping>(&mut gen)),
+                "responses": with_invalid_compartment(ok()),
+            },
+            "delete": {
+                "summary": "Removes the mapping",
+                "parameters": [&session_id, &compartment, &mapping_key],
+                "responses": with_invalid_compartment(json!({
+                    "204": { "description": "Deleted" }
+                })),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/clip-matrix".to_string(),
+        json!({
+            "get": {
+                "summary": "Returns the complete Playtime clip matrix used by the session",
+                "description": "Changes of clip play states and positions can be observed by \
+                    subscribing to the WebSocket topic `/realearn/session/{id}/clip-matrix`.",
+                "parameters": [&session_id],
+                "responses": ok_with(ref_for::<playtime_api::Matrix>(&mut gen)),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/clip-matrix/{action}".to_string(),
+        json!({
+            "post": {
+                "summary": "Triggers a matrix action",
+                "parameters": [&session_id, action_param(&["stop", "undo", "redo"])],
+                "responses": ok(),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/clip-matrix/columns/{column}/{action}".to_string(),
+        json!({
+            "post": {
+                "summary": "Triggers a column action",
+                "parameters": [&session_id, &column_index, action_param(&["stop"])],
+                "responses": ok(),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/clip-matrix/rows/{row}/{action}".to_string(),
+        json!({
+            "post": {
+                "summary": "Triggers a row (scene) action",
+                "parameters": [&session_id, &row_index, action_param(&["play"])],
+                "responses": ok(),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/session/{id}/clip-matrix/slots/{column}/{row}/{action}".to_string(),
+        json!({
+            "post": {
+                "summary": "Triggers a slot action",
+                "parameters": [
+                    &session_id,
+                    &column_index,
+                    &row_index,
+                    action_param(&["play", "stop", "record"])
+                ],
+                "responses": ok(),
+            }
+        }),
+    );
+    paths.insert(
+        "/realearn/controller/{id}".to_string(),
+        json!({
+            "patch": {
+                "summary": "Applies a JSON Patch operation to the controller preset",
+                "parameters": [path_param("id", "ID of the controller preset")],
+                "requestBody": body(ref_for::<PatchRequest>(&mut gen)),
+                "responses": ok(),
+            }
+        }),
+    );
+    paths.insert(
+        "/ws".to_string(),
+        json!({
+            "get": {
+                "summary": "Opens a WebSocket connection",
+                "description": "Events sent by the server have the schemas starting with `Event`. \
+                    Commands sent by the client have the schema `WebSocketCommand`. If a command \
+                    fails, the server responds with `CommandErrorData`.",
+                "parameters": [{
+                    "name": "topics",
+                    "in": "query",
+                    "required": true,
+                    "description": "Comma-separated list of topics, \
+                        e.g. `/realearn/session/my-session/feedback`",
+                    "schema": { "type": "string" },
+                }],
+                "responses": {
+                    "101": { "description": "Switching to WebSocket protocol" }
+                },
+            }
+        }),
+    );
+    // Schemas which are only used over WebSocket
+    gen.subschema_for::<Event<Option<SessionResponseData>>>();
+    gen.subschema_for::<Event<Option<ControllerRouting>>>();
+    gen.subschema_for::<Event<Option<MappingsData>>>();
+    gen.subschema_for::<Event<Option<ParametersData>>>();
+    gen.subschema_for::<Event<HashMap<String, f64>>>();
+    gen.subschema_for::<Event<ControlEventData>>();
+    gen.subschema_for::<Event<ClipMatrixEventData>>();
+    gen.subschema_for::<WebSocketCommand>();
+    gen.subschema_for::<CommandErrorData>();
+    let schemas: Map<String, Value> = gen
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap_or_default()))
+        .collect();
+    let mut doc = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "ReaLearn API",
+            "version": App::version().to_string(),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+        },
+    });
+    if auth_enabled {
+        declare_bearer_auth(&mut doc);
+    }
+    doc
+}
+
+fn declare_bearer_auth(doc: &mut Value) {
+    doc["components"]["securitySchemes"] = json!({
+        "bearerAuth": {
+            "type": "http",
+            "scheme": "bearer",
+            "description": "Pairing code or configured access token. The WebSocket endpoint \
+                alternatively accepts it as `token` query parameter.",
+        }
+    });
+    doc["security"] = json!([{ "bearerAuth": [] }]);
+    let paths = match doc["paths"].as_object_mut() {
+        None => return,
+        Some(p) => p,
+    };
+    for operation in paths.values_mut().filter_map(Value::as_object_mut) {
+        for op in operation.values_mut() {
+            if let Some(responses) = op.get_mut("responses").and_then(Value::as_object_mut) {
+                responses.insert(
+                    "401".to_string(),
+                    json!({ "description": "Access token is missing or invalid" }),
+                );
+                responses.insert(
+                    "403".to_string(),
+                    json!({ "description": "Access token doesn't allow this operation" }),
+                );
+            }
+        }
+    }
+}
+
+fn ref_for<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.subschema_for::<T>()).unwrap_or_default()
+}
+
+fn path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn index_path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "integer", "minimum": 0 },
+    })
+}
+
+fn action_param(actions: &[&str]) -> Value {
+    json!({
+        "name": "action",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string", "enum": actions },
+    })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({
+        "application/json": {
+            "schema": schema,
+        }
+    })
+}
+
+fn body(schema: Value) -> Value {
+    json!({
+        "required": true,
+        "content": json_content(schema),
+    })
+}
+
+fn ok() -> Value {
+    json!({
+        "200": { "description": "OK" }
+    })
+}
+
+fn ok_with(schema: Value) -> Value {
+    json!({
+        "200": {
+            "description": "OK",
+            "content": json_content(schema),
+        }
+    })
+}
+
+/// Adds the response for a `compartment` path parameter which is neither `main` nor `controller`.
+fn with_invalid_compartment(mut responses: Value) -> Value {
+    responses["400"] = json!({
+        "description": "Compartment is neither `main` nor `controller`. ReaLearn versions before \
+            2.13 answered this with 404."
+    });
+    responses
+}
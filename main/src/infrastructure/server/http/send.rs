@@ -1,14 +1,16 @@
 //! Contains functions for sending data to WebSocket clients.
 use crate::application::{Session, SharedSession};
 use crate::base::{when, Global};
-use crate::domain::ProjectionFeedbackValue;
+use crate::domain::{MappingMatchedEvent, ProjectionFeedbackValue};
 use crate::infrastructure::plugin::App;
 use crate::infrastructure::server::http::client::WebSocketClient;
 use crate::infrastructure::server::http::data::{
-    get_active_controller_updated_event, get_controller_routing_updated_event,
+    get_active_controller_updated_event, get_clip_matrix_event, get_control_event,
+    get_controller_routing_updated_event, get_mappings_updated_event, get_parameters_updated_event,
     get_projection_feedback_event, get_session_updated_event, send_initial_feedback,
-    SessionResponseData, Topic,
+    ControlEventData, Event, SessionResponseData, Topic,
 };
+use playtime_clip_engine::main::ClipMatrixEvent;
 use rxrust::prelude::*;
 use serde::Serialize;
 use std::rc::Rc;
@@ -32,8 +34,13 @@ fn send_initial_events_for_topic(
             send_initial_feedback(session_id);
             Ok(())
         }
+        Mappings { session_id } => send_initial_mappings(client, session_id),
+        Parameters { session_id } => send_initial_parameters(client, session_id),
+        // These are pure event streams without initial state.
+        ControlEvents { .. } | ClipMatrix { .. } => Ok(()),
     }
 }
+
 pub fn send_initial_session(
     client: &WebSocketClient,
     session_id: &str,
@@ -67,6 +74,83 @@ fn send_initial_controller(client: &WebSocketClient, session_id: &str) -> Result
     client.send(&event)
 }
 
+fn send_initial_mappings(client: &WebSocketClient, session_id: &str) -> Result<(), &'static str> {
+    let event = if let Some(session) = App::get().find_session_by_id(session_id) {
+        get_mappings_updated_event(session_id, Some(&session.borrow()))
+    } else {
+        get_mappings_updated_event(session_id, None)
+    };
+    client.send(&event)
+}
+
+fn send_initial_parameters(client: &WebSocketClient, session_id: &str) -> Result<(), &'static str> {
+    let event = if let Some(session) = App::get().find_session_by_id(session_id) {
+        get_parameters_updated_event(session_id, Some(&session.borrow()))
+    } else {
+        get_parameters_updated_event(session_id, None)
+    };
+    client.send(&event)
+}
+
+pub fn send_updated_mappings(session: &Session) -> Result<(), &'static str> {
+    send_to_clients_subscribed_to(
+        &Topic::Mappings {
+            session_id: session.id().to_string(),
+        },
+        || get_mappings_updated_event(session.id(), Some(session)),
+    )
+}
+
+pub fn send_updated_parameters(session: &Session) -> Result<(), &'static str> {
+    send_to_clients_subscribed_to(
+        &Topic::Parameters {
+            session_id: session.id().to_string(),
+        },
+        || get_parameters_updated_event(session.id(), Some(session)),
+    )
+}
+
+pub fn send_control_event_to_subscribed_clients(
+    session: &Session,
+    event: MappingMatchedEvent,
+) -> Result<(), &'static str> {
+    let topic = Topic::ControlEvents {
+        session_id: session.id().to_string(),
+    };
+    for_each_client(
+        |client, cached: &Option<Event<ControlEventData>>| {
+            if let Some(control_event) = cached {
+                if client.is_subscribed_to(&topic) {
+                    let _ = client.send(control_event);
+                }
+            }
+        },
+        // Looking up the mapping is only worth it if there are clients at all
+        || {
+            let (_, mapping) =
+                session.find_mapping_and_index_by_id(event.compartment, event.mapping_id)?;
+            let mapping_key = mapping.borrow().key().clone();
+            Some(get_control_event(
+                session.id(),
+                event.compartment,
+                mapping_key,
+            ))
+        },
+    )
+}
+
+pub fn send_clip_matrix_event_to_subscribed_clients(
+    session: &Session,
+    event: &ClipMatrixEvent,
+) -> Result<(), &'static str> {
+    send_to_clients_subscribed_to(
+        &Topic::ClipMatrix {
+            session_id: session.id().to_string(),
+        },
+        || get_clip_matrix_event(session.id(), event),
+    )
+}
+
 pub fn send_updated_active_controller(session: &Session) -> Result<(), &'static str> {
     send_to_clients_subscribed_to(
         &Topic::ActiveController {
@@ -167,7 +251,9 @@ pub fn keep_informing_clients_about_session_events(shared_session: &SharedSessio
     )
     .with(Rc::downgrade(shared_session))
     .do_async(|session, _| {
-        let _ = send_updated_controller_routing(&session.borrow());
+        let session = session.borrow();
+        let _ = send_updated_controller_routing(&session);
+        let _ = send_updated_mappings(&session);
     });
     when(App::get().controller_preset_manager().borrow().changed())
         .with(Rc::downgrade(shared_session))
@@ -181,5 +267,7 @@ pub fn keep_informing_clients_about_session_events(shared_session: &SharedSessio
             let session = session.borrow();
             let _ = send_updated_active_controller(&session);
             let _ = send_updated_controller_routing(&session);
+            let _ = send_updated_mappings(&session);
+            let _ = send_updated_parameters(&session);
         });
 }
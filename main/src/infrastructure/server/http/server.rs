@@ -1,22 +1,26 @@
 use crate::infrastructure::plugin::{App, RealearnControlSurfaceServerTaskSender};
-use crate::infrastructure::server::http::{ServerClients, WebSocketRequest};
-use axum::extract::{Query, WebSocketUpgrade};
+use crate::infrastructure::server::auth::{AccessControl, AccessScope};
+use crate::infrastructure::server::http::{
+    AppSessionProvider, ServerClients, SharedSessionProvider, WebSocketRequest,
+};
+use axum::extract::{Extension, Query, WebSocketUpgrade};
 use axum::handler::Handler;
-use axum::http::header::CONTENT_TYPE;
-use axum::http::Method;
-use axum::routing::{get, patch};
-use axum::Router;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderValue, Method};
+use axum::routing::{get, patch, post};
+use axum::{AddExtensionLayer, Router};
 use axum_server::Handle;
 use metrics_exporter_prometheus::PrometheusHandle;
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
-use tower_http::cors::{any, CorsLayer};
+use tower_http::cors::{any, CorsLayer, Origin};
 
 use crate::base::Global;
 pub use crate::infrastructure::server::http::handlers::*;
-use crate::infrastructure::server::layers::MainThreadLayer;
+use crate::infrastructure::server::layers::{AuthLayer, MainThreadLayer};
 
 #[allow(clippy::too_many_arguments)]
 pub async fn start_http_server(
@@ -29,6 +33,8 @@ pub async fn start_http_server(
     mut https_shutdown_receiver: broadcast::Receiver<()>,
     control_surface_metrics_enabled: bool,
     prometheus_handle: PrometheusHandle,
+    access_control: Arc<AccessControl>,
+    allowed_origins: Vec<String>,
 ) -> Result<(), io::Error> {
     // Router
     let router = create_router(
@@ -37,6 +43,10 @@ pub async fn start_http_server(
         clients,
         prometheus_handle,
         control_surface_metrics_enabled,
+        access_control,
+        allowed_origins,
+        Arc::new(AppSessionProvider),
+        MainThreadLayer::new(),
     );
     // Binding
     let http_future = {
@@ -80,34 +90,71 @@ pub async fn start_http_server(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn create_router(
     cert: String,
     control_surface_task_sender: RealearnControlSurfaceServerTaskSender,
     clients: ServerClients,
     prometheus_handle: PrometheusHandle,
     control_surface_metrics_enabled: bool,
+    access_control: Arc<AccessControl>,
+    allowed_origins: Vec<String>,
+    session_provider: SharedSessionProvider,
+    main_thread_layer: MainThreadLayer,
 ) -> Router {
+    // Routes which expose or modify session data
     let router = Router::new()
-        .route("/", get(welcome_handler))
-        .route(
-            "/realearn.cer",
-            get(|| async move { create_cert_response(cert.clone(), "realearn.cer") }),
-        )
         .route(
             "/realearn/session/:id",
-            get(session_handler.layer(MainThreadLayer)),
+            get(session_handler.layer(main_thread_layer.clone())),
         )
         .route(
             "/realearn/session/:id/controller",
-            get(session_controller_handler.layer(MainThreadLayer)),
+            get(session_controller_handler.layer(main_thread_layer.clone())),
         )
         .route(
             "/realearn/session/:id/controller-routing",
-            get(controller_routing_handler.layer(MainThreadLayer)),
+            get(controller_routing_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/compartment/:compartment",
+            get(get_compartment_handler.layer(main_thread_layer.clone()))
+                .put(put_compartment_handler.layer(main_thread_layer.clone()))
+                .patch(patch_compartment_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/compartment/:compartment/mappings",
+            post(add_mapping_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/compartment/:compartment/mappings/:key",
+            get(get_mapping_handler.layer(main_thread_layer.clone()))
+                .put(put_mapping_handler.layer(main_thread_layer.clone()))
+                .delete(delete_mapping_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/clip-matrix",
+            get(get_clip_matrix_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/clip-matrix/:action",
+            post(clip_matrix_action_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/clip-matrix/columns/:column/:action",
+            post(clip_column_action_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/clip-matrix/rows/:row/:action",
+            post(clip_row_action_handler.layer(main_thread_layer.clone())),
+        )
+        .route(
+            "/realearn/session/:id/clip-matrix/slots/:column/:row/:action",
+            post(clip_slot_action_handler.layer(main_thread_layer.clone())),
         )
         .route(
             "/realearn/controller/:id",
-            patch(patch_controller_handler.layer(MainThreadLayer)),
+            patch(patch_controller_handler.layer(main_thread_layer.clone())),
         );
     #[cfg(feature = "realearn-metrics")]
     let router = router.route(
@@ -121,26 +168,389 @@ fn create_router(
             .await
         }),
     );
-    router
-        .layer(
-            CorsLayer::new()
-                .allow_origin(any())
-                .allow_methods(vec![
-                    Method::GET,
-                    Method::POST,
-                    Method::PUT,
-                    Method::DELETE,
-                    Method::PATCH,
-                ])
-                .allow_headers(vec![CONTENT_TYPE]),
+    // Routes which are needed before a client can authenticate at all
+    let auth_enabled = access_control.is_enabled();
+    let router = router
+        .layer(AddExtensionLayer::new(session_provider.clone()))
+        .layer(AuthLayer::new(access_control.clone()))
+        .route("/", get(welcome_handler))
+        .route(
+            "/realearn.cer",
+            get(|| async move { create_cert_response(cert.clone(), "realearn.cer") }),
         )
         .route(
-            "/ws",
-            get(
-                |ws: WebSocketUpgrade, Query(req): Query<WebSocketRequest>| async move {
-                    let topics = req.parse_topics();
-                    ws.on_upgrade(|socket| handle_websocket_upgrade(socket, topics, clients))
-                },
-            ),
+            "/realearn/openapi.json",
+            get(move || openapi_handler(auth_enabled)),
+        );
+    let cors_layer = CorsLayer::new()
+        .allow_methods(vec![
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::PATCH,
+        ])
+        .allow_headers(vec![CONTENT_TYPE, AUTHORIZATION]);
+    let cors_layer = if allowed_origins.is_empty() {
+        cors_layer.allow_origin(any())
+    } else {
+        let origins = allowed_origins
+            .iter()
+            .filter_map(|o| HeaderValue::from_str(o).ok());
+        cors_layer.allow_origin(Origin::list(origins))
+    };
+    let websocket_handler = |ws: WebSocketUpgrade,
+                             Query(req): Query<WebSocketRequest>,
+                             Extension(scope): Extension<AccessScope>| async move {
+        let topics = req.parse_topics();
+        ws.on_upgrade(move |socket| {
+            handle_websocket_upgrade(socket, topics, clients, scope, session_provider)
+        })
+    };
+    router.layer(cors_layer).route(
+        "/ws",
+        get(websocket_handler.layer(AuthLayer::for_websocket(access_control))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SenderToNormalThread;
+    use crate::infrastructure::server::auth::AccessControl;
+    use crate::infrastructure::server::http::fake::FakeSessionProvider;
+    use axum::body::{Body, BoxBody, HttpBody};
+    use axum::http::{Request, Response, StatusCode};
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn welcome_page() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let response = send(router, get_request("/")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn openapi_document() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let response = send(router, get_request("/realearn/openapi.json")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(json["openapi"], "3.0.3");
+        assert!(json["paths"]["/realearn/session/{id}"].is_object());
+        assert!(json.get("security").is_none());
+    }
+
+    #[tokio::test]
+    async fn openapi_document_declares_bearer_auth_if_enabled() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let response = send(router, get_request("/realearn/openapi.json")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(
+            json["components"]["securitySchemes"]["bearerAuth"]["scheme"],
+            "bearer"
+        );
+        assert_eq!(json["security"][0]["bearerAuth"], serde_json::json!([]));
+        let responses = &json["paths"]["/realearn/session/{id}"]["get"]["responses"];
+        assert!(responses["401"].is_object());
+        assert!(responses["403"].is_object());
+    }
+
+    #[tokio::test]
+    async fn unknown_route() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let response = send(router, get_request("/realearn/foo")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn session_not_found() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let response = send(router, get_request("/realearn/session/foo")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_text(response).await, "session not found");
+    }
+
+    #[tokio::test]
+    async fn invalid_compartment() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let response = send(router, get_request("/realearn/session/foo/compartment/bar")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body_text(response).await,
+            "compartment must be 'main' or 'controller'"
+        );
+    }
+
+    #[tokio::test]
+    async fn clip_matrix_action_of_unknown_session() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let request = post_request("/realearn/session/foo/clip-matrix/slots/0/1/play");
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_text(response).await, "session not found");
+    }
+
+    #[tokio::test]
+    async fn invalid_clip_matrix_action() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let request = post_request("/realearn/session/foo/clip-matrix/rows/0/stop");
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body_text(response).await,
+            "clip matrix action not supported"
+        );
+    }
+
+    #[tokio::test]
+    async fn patch_controller_with_unsupported_path() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let request = patch_request(
+            "/realearn/controller/foo",
+            r#"{"op": "replace", "path": "/name", "value": "bar"}"#,
+        );
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn patch_controller_not_found() {
+        // Given
+        let router = create_test_router(AccessControl::disabled());
+        // When
+        let request = patch_request(
+            "/realearn/controller/foo",
+            r#"{"op": "replace", "path": "/customData/bar", "value": 5}"#,
+        );
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            body_text(response).await,
+            "session has controller but controller not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn patch_controller_successfully() {
+        // Given
+        let provider = Arc::new(FakeSessionProvider::default().with_controller("foo"));
+        let router = create_router_with_provider(AccessControl::disabled(), provider.clone());
+        // When
+        let request = patch_request(
+            "/realearn/controller/foo",
+            r#"{"op": "add", "path": "/customData/bar", "value": 5}"#,
+        );
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            provider.controller_custom_data("foo").unwrap()["bar"],
+            serde_json::json!(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn patch_controller_with_missing_value() {
+        // Given
+        let provider = Arc::new(FakeSessionProvider::default().with_controller("foo"));
+        let router = create_router_with_provider(AccessControl::disabled(), provider);
+        // When
+        let request = patch_request(
+            "/realearn/controller/foo",
+            r#"{"op": "replace", "path": "/customData/bar"}"#,
+        );
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body_text(response).await,
+            "patch operation requires a value"
+        );
+    }
+
+    #[tokio::test]
+    async fn auth_rejects_missing_token() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let response = send(router, get_request("/realearn/session/foo")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_rejects_invalid_token() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let response = send(
+            router,
+            get_request_with_token("/realearn/session/foo", "invalid"),
         )
+        .await;
+        // Then
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_lets_read_only_token_read() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let response = send(
+            router,
+            get_request_with_token("/realearn/session/foo", "reader"),
+        )
+        .await;
+        // Then
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_text(response).await, "session not found");
+    }
+
+    #[tokio::test]
+    async fn auth_accepts_token_as_query_parameter_only_for_websocket() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let http_response = send(
+            router.clone(),
+            get_request("/realearn/session/foo?token=reader"),
+        )
+        .await;
+        let ws_response = send(router, get_request("/ws?topics=&token=reader")).await;
+        // Then
+        assert_eq!(http_response.status(), StatusCode::UNAUTHORIZED);
+        // Passes authentication but isn't a proper WebSocket upgrade request
+        assert_ne!(ws_response.status(), StatusCode::UNAUTHORIZED);
+        assert_ne!(ws_response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn auth_forbids_read_only_token_to_write() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let mut request = patch_request(
+            "/realearn/controller/foo",
+            r#"{"op": "remove", "path": "/customData/bar"}"#,
+        );
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer reader"));
+        let response = send(router, request).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn auth_leaves_welcome_page_public() {
+        // Given
+        let router = create_test_router(create_access_control());
+        // When
+        let response = send(router, get_request("/")).await;
+        // Then
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn create_access_control() -> AccessControl {
+        AccessControl::enabled(
+            AccessControl::parse_tokens("reader:read-only,writer:read-write").unwrap(),
+        )
+    }
+
+    fn create_test_router(access_control: AccessControl) -> Router {
+        create_router_with_provider(access_control, Arc::new(FakeSessionProvider::default()))
+    }
+
+    fn create_router_with_provider(
+        access_control: AccessControl,
+        session_provider: SharedSessionProvider,
+    ) -> Router {
+        let (control_surface_task_sender, _) =
+            SenderToNormalThread::new_unbounded_channel("test control surface tasks");
+        create_router(
+            "".to_string(),
+            control_surface_task_sender,
+            Default::default(),
+            PrometheusBuilder::new().build_recorder().handle(),
+            false,
+            Arc::new(access_control),
+            vec![],
+            session_provider,
+            MainThreadLayer::inline(),
+        )
+    }
+
+    async fn send(router: Router, request: Request<Body>) -> Response<BoxBody> {
+        router.oneshot(request).await.unwrap()
+    }
+
+    fn get_request(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    fn get_request_with_token(uri: &str, token: &str) -> Request<Body> {
+        Request::builder()
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn post_request(uri: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn patch_request(uri: &str, json: &'static str) -> Request<Body> {
+        Request::builder()
+            .method(Method::PATCH)
+            .uri(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(json))
+            .unwrap()
+    }
+
+    async fn body_text(response: Response<BoxBody>) -> String {
+        let mut body = response.into_body();
+        let mut bytes = vec![];
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        String::from_utf8(bytes).unwrap()
+    }
 }
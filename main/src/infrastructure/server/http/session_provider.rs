@@ -0,0 +1,101 @@
+//! Contains the abstraction of the app as far as the HTTP/WebSocket server is concerned.
+
+use crate::application::{ControllerPreset, PresetManager, SharedSession};
+use crate::infrastructure::plugin::App;
+use std::sync::Arc;
+
+/// Everything which the HTTP/WebSocket request handlers need from the app.
+///
+/// Exists so that the server can be tested without REAPER (by passing a fake implementation).
+pub trait SessionProvider {
+    fn find_session_by_id(&self, session_id: &str) -> Option<SharedSession>;
+
+    fn find_controller_preset_by_id(&self, id: &str) -> Option<ControllerPreset>;
+
+    fn update_controller_preset(&self, preset: ControllerPreset) -> Result<(), &'static str>;
+}
+
+pub type SharedSessionProvider = Arc<dyn SessionProvider + Send + Sync>;
+
+/// The real session provider, backed by the app instance.
+///
+/// Must only be used in the main thread (which is the case for all handlers wrapped by the
+/// `MainThreadLayer`).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AppSessionProvider;
+
+impl SessionProvider for AppSessionProvider {
+    fn find_session_by_id(&self, session_id: &str) -> Option<SharedSession> {
+        App::get().find_session_by_id(session_id)
+    }
+
+    fn find_controller_preset_by_id(&self, id: &str) -> Option<ControllerPreset> {
+        App::get()
+            .controller_preset_manager()
+            .borrow()
+            .find_by_id(id)
+    }
+
+    fn update_controller_preset(&self, preset: ControllerPreset) -> Result<(), &'static str> {
+        App::get()
+            .controller_preset_manager()
+            .borrow_mut()
+            .update_preset(preset)
+    }
+}
+
+#[cfg(test)]
+pub mod fake {
+    use super::*;
+    use crate::application::{CompartmentModel, GroupModel, Preset};
+    use crate::domain::Compartment;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Knows no sessions at all and manages controller presets in memory (just their custom data).
+    #[derive(Default)]
+    pub struct FakeSessionProvider {
+        controller_custom_data: Mutex<HashMap<String, HashMap<String, Value>>>,
+    }
+
+    impl FakeSessionProvider {
+        pub fn with_controller(self, id: &str) -> Self {
+            self.controller_custom_data
+                .lock()
+                .unwrap()
+                .insert(id.to_string(), Default::default());
+            self
+        }
+
+        pub fn controller_custom_data(&self, id: &str) -> Option<HashMap<String, Value>> {
+            self.controller_custom_data.lock().unwrap().get(id).cloned()
+        }
+    }
+
+    impl SessionProvider for FakeSessionProvider {
+        fn find_session_by_id(&self, _session_id: &str) -> Option<SharedSession> {
+            None
+        }
+
+        fn find_controller_preset_by_id(&self, id: &str) -> Option<ControllerPreset> {
+            let custom_data = self.controller_custom_data(id)?;
+            let data = CompartmentModel {
+                parameters: vec![],
+                default_group: GroupModel::default_for_compartment(Compartment::Controller),
+                groups: vec![],
+                mappings: vec![],
+                custom_data,
+            };
+            Some(ControllerPreset::new(id.to_string(), id.to_string(), data))
+        }
+
+        fn update_controller_preset(&self, preset: ControllerPreset) -> Result<(), &'static str> {
+            self.controller_custom_data
+                .lock()
+                .unwrap()
+                .insert(preset.id().to_string(), preset.data().custom_data.clone());
+            Ok(())
+        }
+    }
+}
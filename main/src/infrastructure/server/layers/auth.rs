@@ -0,0 +1,123 @@
+use crate::infrastructure::server::auth::{
+    log_rejection, parse_bearer_token, AccessControl, AccessDenied, AccessScope,
+};
+use axum::http::{header, Method, Request, Response};
+use futures::future::{BoxFuture, FutureExt};
+use hyper::StatusCode;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// A Tower layer that rejects HTTP requests which don't carry a valid access token.
+///
+/// The token is taken from the `Authorization: Bearer <token>` header. Reading requests (`GET`,
+/// `HEAD`, `OPTIONS`) require read-only access, all others read-write access.
+#[derive(Clone)]
+pub struct AuthLayer {
+    access_control: Arc<AccessControl>,
+    accept_query_token: bool,
+}
+
+impl AuthLayer {
+    pub fn new(access_control: Arc<AccessControl>) -> Self {
+        Self {
+            access_control,
+            accept_query_token: false,
+        }
+    }
+
+    /// Browsers can't set headers when opening WebSocket connections, so for the WebSocket
+    /// upgrade, the token may alternatively be passed as `token` query parameter.
+    pub fn for_websocket(access_control: Arc<AccessControl>) -> Self {
+        Self {
+            access_control,
+            accept_query_token: true,
+        }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            access_control: self.access_control.clone(),
+            accept_query_token: self.accept_query_token,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    access_control: Arc<AccessControl>,
+    accept_query_token: bool,
+}
+
+impl<S, E, ReqBody, ResBody> Service<Request<ReqBody>> for AuthService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error = E>,
+    S::Future: Send + 'static,
+    ResBody: Default + Send + 'static,
+    E: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<ResBody>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let required_scope = match *request.method() {
+            Method::GET | Method::HEAD | Method::OPTIONS => AccessScope::ReadOnly,
+            _ => AccessScope::ReadWrite,
+        };
+        let token = extract_token(&request, self.accept_query_token);
+        match self
+            .access_control
+            .authorize(token.as_deref(), required_scope)
+        {
+            Ok(scope) => {
+                // Handlers which need to distinguish (e.g. WebSocket) can extract the scope.
+                request.extensions_mut().insert(scope);
+                self.inner.call(request).boxed()
+            }
+            Err(e) => {
+                log_rejection("HTTP", request.uri().path(), e);
+                let status = match e {
+                    AccessDenied::TokenMissing | AccessDenied::TokenInvalid => {
+                        StatusCode::UNAUTHORIZED
+                    }
+                    AccessDenied::ScopeInsufficient => StatusCode::FORBIDDEN,
+                };
+                let response = Response::builder()
+                    .status(status)
+                    .header(header::WWW_AUTHENTICATE, "Bearer")
+                    .body(ResBody::default())
+                    .unwrap();
+                futures::future::ready(Ok(response)).boxed()
+            }
+        }
+    }
+}
+
+fn extract_token<B>(request: &Request<B>, accept_query_token: bool) -> Option<String> {
+    let header_token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_bearer_token);
+    if let Some(t) = header_token {
+        return Some(t.to_string());
+    }
+    if !accept_query_token {
+        return None;
+    }
+    let query = request.uri().query()?;
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "token")
+        .map(|(_, value)| value.into_owned())
+}
@@ -7,14 +7,33 @@ use std::task::{Context, Poll};
 use tower::{Layer, Service};
 
 /// A Tower layer that will cause wrapped services to be executed in REAPER's main thread.
-#[derive(Clone)]
-pub struct MainThreadLayer;
+#[derive(Clone, Default)]
+pub struct MainThreadLayer {
+    inline: bool,
+}
+
+impl MainThreadLayer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a layer which executes the wrapped services right in the calling thread.
+    ///
+    /// Only useful for testing the server without REAPER.
+    #[cfg(test)]
+    pub fn inline() -> Self {
+        Self { inline: true }
+    }
+}
 
 impl<S> Layer<S> for MainThreadLayer {
     type Service = MainThreadService<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        MainThreadService { inner }
+        MainThreadService {
+            inner,
+            inline: self.inline,
+        }
     }
 }
 
@@ -22,6 +41,7 @@ impl<S> Layer<S> for MainThreadLayer {
 #[derive(Clone)]
 pub struct MainThreadService<S> {
     inner: S,
+    inline: bool,
 }
 
 impl<S, E, Req, ResBody> Service<Req> for MainThreadService<S>
@@ -49,6 +69,9 @@ where
         // But don't await that future! We must do that in REAPER's main thread, that's the point
         // of this middleware.
         let inner_response_future = self.inner.call(request);
+        if self.inline {
+            return Box::pin(inner_response_future);
+        }
         // Spawn task on REAPER's main thread.
         let (tx, rx) = oneshot::channel::<Result<Response<ResBody>, S::Error>>();
         Global::future_support().spawn_in_main_thread(async move {
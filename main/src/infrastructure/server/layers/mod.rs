@@ -1,2 +1,4 @@
+mod auth;
 mod main_thread;
+pub use auth::*;
 pub use main_thread::*;
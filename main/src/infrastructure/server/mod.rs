@@ -9,13 +9,16 @@ use std::cell::RefCell;
 use std::fmt::Debug;
 use std::fs;
 
-use std::net::{IpAddr, Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
 
 use tokio::sync::broadcast;
 use url::Url;
 
+use crate::infrastructure::server::auth::{generate_pairing_code, AccessControl, AccessScope};
+use crate::infrastructure::server::grpc::start_grpc_server;
 use crate::infrastructure::server::http::start_http_server;
 use crate::infrastructure::server::http::ServerClients;
 use derivative::Derivative;
@@ -25,6 +28,7 @@ use std::time::Duration;
 
 pub type SharedRealearnServer = Rc<RefCell<RealearnServer>>;
 
+pub mod auth;
 pub mod grpc;
 pub mod http;
 mod layers;
@@ -34,6 +38,7 @@ mod layers;
 pub struct RealearnServer {
     http_port: u16,
     https_port: u16,
+    grpc_port: u16,
     state: ServerState,
     certs_dir_path: PathBuf,
     changed_subject: LocalSubject<'static, (), ()>,
@@ -42,6 +47,11 @@ pub struct RealearnServer {
     control_surface_metrics_enabled: bool,
     #[derivative(Debug = "ignore")]
     prometheus_handle: PrometheusHandle,
+    access_control: AccessControl,
+    /// Only set if authentication is enabled. Grants read-write access.
+    pairing_code: Option<String>,
+    /// Empty means any origin is allowed.
+    allowed_origins: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -74,14 +84,25 @@ impl RealearnServer {
     pub fn new(
         http_port: u16,
         https_port: u16,
+        grpc_port: u16,
         certs_dir_path: PathBuf,
         control_surface_task_sender: RealearnControlSurfaceServerTaskSender,
         control_surface_metrics_enabled: bool,
         prometheus_handle: PrometheusHandle,
+        mut access_control: AccessControl,
+        allowed_origins: Vec<String>,
     ) -> RealearnServer {
+        let pairing_code = if access_control.is_enabled() {
+            let code = generate_pairing_code();
+            access_control.add_token(code.clone(), AccessScope::ReadWrite);
+            Some(code)
+        } else {
+            None
+        };
         RealearnServer {
             http_port,
             https_port,
+            grpc_port,
             state: ServerState::Stopped,
             certs_dir_path,
             changed_subject: Default::default(),
@@ -89,6 +110,9 @@ impl RealearnServer {
             control_surface_task_sender,
             control_surface_metrics_enabled,
             prometheus_handle,
+            access_control,
+            pairing_code,
+            allowed_origins,
         }
     }
 
@@ -97,13 +121,14 @@ impl RealearnServer {
         if self.state.is_starting_or_running() {
             return Ok(());
         }
-        check_port(false, self.http_port)?;
-        check_port(true, self.https_port)?;
-        // TODO-high-grpc Notify user if gRPC port is in use, too. Also make it configurable.
+        check_port(PortKind::Http, self.http_port)?;
+        check_port(PortKind::Https, self.https_port)?;
+        check_port(PortKind::Grpc, self.grpc_port)?;
         let clients: ServerClients = Default::default();
         let clients_clone = clients.clone();
         let http_port = self.http_port;
         let https_port = self.https_port;
+        let grpc_port = self.grpc_port;
         let key_and_cert = self.key_and_cert();
         let control_surface_task_sender = self.control_surface_task_sender.clone();
         let (shutdown_sender, http_shutdown_receiver) = broadcast::channel(5);
@@ -111,6 +136,8 @@ impl RealearnServer {
         let grpc_shutdown_receiver = shutdown_sender.subscribe();
         let control_surface_metrics_enabled = self.control_surface_metrics_enabled;
         let prometheus_handle = self.prometheus_handle.clone();
+        let access_control = Arc::new(self.access_control.clone());
+        let allowed_origins = self.allowed_origins.clone();
         let server_thread_join_handle = std::thread::Builder::new()
             .name("ReaLearn server".to_string())
             .spawn(move || {
@@ -121,6 +148,7 @@ impl RealearnServer {
                 runtime.block_on(start_servers(
                     http_port,
                     https_port,
+                    grpc_port,
                     clients_clone,
                     key_and_cert,
                     control_surface_task_sender,
@@ -129,6 +157,8 @@ impl RealearnServer {
                     grpc_shutdown_receiver,
                     control_surface_metrics_enabled,
                     prometheus_handle,
+                    access_control,
+                    allowed_origins,
                 ));
                 runtime.shutdown_timeout(Duration::from_secs(1));
             })
@@ -194,12 +224,23 @@ impl RealearnServer {
     }
 
     pub fn generate_full_companion_app_url(&self, session_id: &str, localhost: bool) -> String {
+        self.generate_companion_app_url_internal(session_id, localhost, self.pairing_code())
+    }
+
+    /// The pairing code is put into the URL fragment, so it's not sent to the server which hosts
+    /// the Companion web app (and doesn't end up in its access logs).
+    fn generate_companion_app_url_internal(
+        &self,
+        session_id: &str,
+        localhost: bool,
+        pairing_code: Option<&str>,
+    ) -> String {
         let host = if localhost {
             None
         } else {
             self.local_ip().map(|ip| ip.to_string())
         };
-        Url::parse_with_params(
+        let mut url = Url::parse_with_params(
             App::get()
                 .config()
                 .companion_web_app_url()
@@ -217,8 +258,11 @@ impl RealearnServer {
                 ("generated", "true".to_string()),
             ],
         )
-        .expect("invalid URL")
-        .into()
+        .expect("invalid URL");
+        if let Some(code) = pairing_code {
+            url.set_fragment(Some(&format!("token={}", code)));
+        }
+        url.into()
     }
 
     pub fn local_ip(&self) -> Option<IpAddr> {
@@ -243,6 +287,15 @@ impl RealearnServer {
         self.https_port
     }
 
+    pub fn grpc_port(&self) -> u16 {
+        self.grpc_port
+    }
+
+    /// Returns the code which clients can use as access token if authentication is enabled.
+    pub fn pairing_code(&self) -> Option<&str> {
+        self.pairing_code.as_deref()
+    }
+
     pub fn log_debug_info(&self, session_id: &str) {
         let msg = format!(
             "\n\
@@ -252,11 +305,17 @@ impl RealearnServer {
         - ReaLearn local hostname: {:?}\n\
         - ReaLearn local hostname with DNS lookup: {:?}\n\
         - ReaLearn local IP address: {:?}\n\
+        - Authentication enabled: {}\n\
         ",
-            self.generate_full_companion_app_url(session_id, false),
+            self.generate_companion_app_url_internal(
+                session_id,
+                false,
+                self.pairing_code().map(|_| "REDACTED")
+            ),
             self.local_hostname(),
             self.local_hostname_dns(),
-            self.local_ip()
+            self.local_ip(),
+            self.access_control.is_enabled()
         );
         Reaper::get().show_console_msg(msg);
     }
@@ -270,14 +329,17 @@ impl RealearnServer {
 async fn start_servers(
     http_port: u16,
     https_port: u16,
+    grpc_port: u16,
     clients: ServerClients,
     (key, cert): (String, String),
     control_surface_task_sender: RealearnControlSurfaceServerTaskSender,
     http_shutdown_receiver: broadcast::Receiver<()>,
     https_shutdown_receiver: broadcast::Receiver<()>,
-    _grpc_shutdown_receiver: broadcast::Receiver<()>,
+    grpc_shutdown_receiver: broadcast::Receiver<()>,
     control_surface_metrics_enabled: bool,
     prometheus_handle: PrometheusHandle,
+    access_control: Arc<AccessControl>,
+    allowed_origins: Vec<String>,
 ) {
     let http_server_future = start_http_server(
         http_port,
@@ -289,16 +351,30 @@ async fn start_servers(
         https_shutdown_receiver,
         control_surface_metrics_enabled,
         prometheus_handle,
+        access_control.clone(),
+        allowed_origins,
+    );
+    // Without authentication, gRPC clients could control everything, so we expose it to other
+    // devices only if authentication is enabled.
+    let grpc_ip = if access_control.is_enabled() {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let grpc_server_future = start_grpc_server(
+        SocketAddr::from((grpc_ip, grpc_port)),
+        grpc_shutdown_receiver,
+        access_control,
     );
-    http_server_future.await.expect("HTTP server error");
-    // let grpc_server_future = start_grpc_server(
-    //     SocketAddr::from(([127, 0, 0, 1], 50051)),
-    //     grpc_shutdown_receiver,
-    // );
-    // let (http_result, grpc_result) =
-    //     futures::future::join(http_server_future, grpc_server_future).await;
-    // http_result.expect("HTTP server error");
-    // grpc_result.expect("gRPC server error");
+    let (http_result, grpc_result) =
+        futures::future::join(http_server_future, grpc_server_future).await;
+    // Panicking here would just take down the server thread, so we log instead.
+    if let Err(e) = http_result {
+        tracing::error!("HTTP server error: {}", e);
+    }
+    if let Err(e) = grpc_result {
+        tracing::error!("gRPC server error: {}", e);
+    }
 }
 
 fn get_key_and_cert(ip: IpAddr, cert_dir_path: &Path) -> (String, String) {
@@ -367,7 +443,40 @@ pub fn get_local_ip() -> Option<std::net::IpAddr> {
     }
 }
 
-fn check_port(is_https: bool, port: u16) -> Result<(), String> {
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum PortKind {
+    Http,
+    Https,
+    Grpc,
+}
+
+impl PortKind {
+    fn lower_case_label(self) -> &'static str {
+        match self {
+            PortKind::Http => "http",
+            PortKind::Https => "https",
+            PortKind::Grpc => "grpc",
+        }
+    }
+
+    fn upper_case_label(self) -> &'static str {
+        match self {
+            PortKind::Http => "HTTP",
+            PortKind::Https => "HTTPS",
+            PortKind::Grpc => "gRPC",
+        }
+    }
+
+    fn alternate_port(self) -> u16 {
+        match self {
+            PortKind::Http => 40080,
+            PortKind::Https => 40443,
+            PortKind::Grpc => 40051,
+        }
+    }
+}
+
+fn check_port(kind: PortKind, port: u16) -> Result<(), String> {
     if !local_port_available(port) {
         let msg = format!(
             r#"{upper_case_port_label} port {port} is not available. Possible causes and solutions:
@@ -386,10 +495,10 @@ Set another {upper_case_port_label} port in "realearn.ini", for example:
   
     server_{lower_case_port_label}_port = {alternate_port}
 "#,
-            lower_case_port_label = if is_https { "https" } else { "http" },
-            upper_case_port_label = if is_https { "HTTPS" } else { "HTTP" },
+            lower_case_port_label = kind.lower_case_label(),
+            upper_case_port_label = kind.upper_case_label(),
             port = port,
-            alternate_port = if is_https { 40443 } else { 40080 },
+            alternate_port = kind.alternate_port(),
         );
         return Err(msg);
     }
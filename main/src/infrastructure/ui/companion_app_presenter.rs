@@ -75,6 +75,7 @@ impl CompanionAppPresenter {
             server_http_port: server.http_port(),
             server_https_port: server.https_port(),
             session_id: session.id().to_string(),
+            pairing_code: server.pairing_code().unwrap_or_default().to_string(),
             os: std::env::consts::OS,
         };
         let index_file = dir.path().join("index.html");
@@ -163,6 +164,8 @@ struct AppSetupState {
     server_https_port: u16,
     // Can change per session
     session_id: String,
+    // Can only change after restart, empty if authentication is disabled
+    pairing_code: String,
     // Can't change at all
     os: &'static str,
 }
@@ -1,11 +1,10 @@
 use std::error::Error;
 use std::fmt::Debug;
-use std::time::Duration;
 
 use playtime_api::Matrix;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::SafeLua;
+use crate::domain::{SafeLua, LUA_EXECUTION_TIME_LIMIT};
 use crate::infrastructure::api::convert::from_data::ConversionStyle;
 use crate::infrastructure::api::convert::to_data::ApiToDataConversionContext;
 use crate::infrastructure::api::convert::{from_data, to_data};
@@ -267,7 +266,7 @@ pub fn serialize_data_object_to_lua(
 
 pub fn deserialize_api_object_from_lua(text: &str) -> Result<ApiObject, Box<dyn Error>> {
     let lua = SafeLua::new()?;
-    let lua = lua.start_execution_time_limit_countdown(Duration::from_millis(200))?;
+    let lua = lua.start_execution_time_limit_countdown(LUA_EXECUTION_TIME_LIMIT)?;
     let value = execute_lua_import_script(&lua, text)?;
     Ok(lua.as_ref().from_value(value)?)
 }
@@ -4,6 +4,7 @@ use crate::infrastructure::ui::{
 };
 
 use lazycell::LazyCell;
+use playtime_clip_engine::main::ClipMatrixEvent;
 use reaper_high::Reaper;
 
 use slog::debug;
@@ -16,7 +17,9 @@ use crate::domain::{
 };
 use crate::infrastructure::plugin::{App, RealearnPluginParameters};
 use crate::infrastructure::server::http::{
+    send_clip_matrix_event_to_subscribed_clients, send_control_event_to_subscribed_clients,
     send_projection_feedback_to_subscribed_clients, send_updated_controller_routing,
+    send_updated_parameters,
 };
 use crate::infrastructure::ui::util::{format_tags_as_csv, parse_tags_from_csv};
 use rxrust::prelude::*;
@@ -307,6 +310,7 @@ impl SessionUi for Weak<MainPanel> {
     }
 
     fn parameters_changed(&self, session: &Session) {
+        let _ = send_updated_parameters(session);
         upgrade_panel(self).handle_changed_parameters(session);
     }
 
@@ -314,10 +318,15 @@ impl SessionUi for Weak<MainPanel> {
         let _ = send_projection_feedback_to_subscribed_clients(session.id(), value);
     }
 
-    fn mapping_matched(&self, event: MappingMatchedEvent) {
+    fn mapping_matched(&self, session: &Session, event: MappingMatchedEvent) {
+        let _ = send_control_event_to_subscribed_clients(session, event);
         upgrade_panel(self).handle_matched_mapping(event);
     }
 
+    fn clip_matrix_changed(&self, session: &Session, event: &ClipMatrixEvent) {
+        let _ = send_clip_matrix_event_to_subscribed_clients(session, event);
+    }
+
     #[allow(clippy::single_match)]
     fn handle_affected(
         &self,
@@ -25,7 +25,7 @@ use helgoboss_learn::{
     OutOfRangeBehavior, PercentIo, RgbColor, SoftSymmetricUnitValue, SourceCharacter, TakeoverMode,
     Target, UnitValue, ValueSequence, VirtualColor, DEFAULT_OSC_ARG_VALUE_RANGE,
 };
-use realearn_api::schema::{MidiScriptKind, MonitoringMode};
+use realearn_api::schema::{MidiScriptKind, MonitoringMode, TransformationLanguage};
 use swell_ui::{
     DialogUnits, MenuBar, Point, SharedView, SwellStringArg, View, ViewContext, WeakView, Window,
 };
@@ -47,8 +47,9 @@ use crate::base::Global;
 use crate::base::{notification, when, Prop};
 use crate::domain::ui_util::parse_unit_value_from_percentage;
 use crate::domain::{
-    control_element_domains, AnyOnParameter, ControlContext, Exclusivity, FeedbackSendBehavior,
-    KeyStrokePortability, PortabilityIssue, ReaperTargetType, SendMidiDestination,
+    compartment_param_index_iter, control_element_domains, AnyOnParameter, BeatGrid,
+    BeatSourceOutput, ControlContext, Exclusivity, FeedbackSendBehavior, KeySourceMode,
+    KeyStrokePortability, LfoWaveform, PortabilityIssue, ReaperTargetType, SendMidiDestination,
     SimpleExclusivity, TouchedRouteParameterType, WithControlContext,
 };
 use crate::domain::{
@@ -65,8 +66,8 @@ use crate::infrastructure::ui::util::{
     format_tags_as_csv, open_in_browser, parse_tags_from_csv, symbols,
 };
 use crate::infrastructure::ui::{
-    EelMidiScriptEngine, ItemProp, LuaMidiScriptEngine, MainPanel, MappingHeaderPanel,
-    ScriptEditorPanel, ScriptEngine, YamlEditorPanel,
+    EelMidiScriptEngine, ItemProp, LuaMidiControlScriptEngine, LuaMidiScriptEngine, MainPanel,
+    MappingHeaderPanel, ScriptEditorPanel, ScriptEngine, YamlEditorPanel,
 };
 
 #[derive(Debug)]
@@ -268,9 +269,33 @@ impl MappingPanel {
                                                 view.invalidate_mode_controls();
                                                 view.invalidate_help();
                                             }
-                                            P::MidiClockTransportMessage => {
+                                            P::MidiClockTransportMessage | P::LfoWaveform => {
                                                 view.invalidate_source_line_3_combo_box_2();
                                             }
+                                            P::BeatOutput => {
+                                                view.invalidate_source_line_3_combo_box_2();
+                                                view.invalidate_mode_controls();
+                                                view.invalidate_help();
+                                            }
+                                            P::BeatGrid => {
+                                                view.invalidate_source_line_3_combo_box_1();
+                                                view.invalidate_source_line_4(initiator);
+                                            }
+                                            P::BeatSubdivisions => {
+                                                view.invalidate_source_line_4_edit_control(initiator);
+                                            }
+                                            P::BeatOnlyWhilePlaying => {
+                                                view.invalidate_source_check_box_2();
+                                            }
+                                            P::ParameterIndex => {
+                                                view.invalidate_source_line_3_combo_box_1();
+                                            }
+                                            P::LfoPhase => {
+                                                view.invalidate_source_line_4_edit_control(initiator);
+                                            }
+                                            P::LfoDepth => {
+                                                view.invalidate_source_line_5_edit_control(initiator);
+                                            }
                                             P::IsRegistered => {
                                                 view.invalidate_source_line_4_check_box();
                                             }
@@ -288,7 +313,7 @@ impl MappingPanel {
                                                 view.invalidate_source_line_5_combo_box();
                                             }
                                             P::OscAddressPattern |
-                                            P::RawMidiPattern | P::TimerMillis => {
+                                            P::RawMidiPattern | P::TimerMillis | P::LfoRate => {
                                                 view.invalidate_source_line_3_edit_control(initiator);
                                             }
                                             P::MidiScriptKind => {
@@ -308,6 +333,23 @@ impl MappingPanel {
                                             P::Keystroke => {
                                                 view.invalidate_source_line_3(initiator);
                                             }
+                                            P::KeyMode => {
+                                                view.invalidate_source_line_3_combo_box_2();
+                                                view.invalidate_mode_controls();
+                                                view.invalidate_help();
+                                            }
+                                            P::ChordMembers => {
+                                                view.invalidate_source_line_3_edit_control(initiator);
+                                            }
+                                            P::ChordWindowMillis => {
+                                                view.invalidate_source_line_4_edit_control(initiator);
+                                            }
+                                            P::SequenceSteps => {
+                                                view.invalidate_source_line_3_edit_control(initiator);
+                                            }
+                                            P::SequenceTimeoutMillis => {
+                                                view.invalidate_source_line_4_edit_control(initiator);
+                                            }
                                         }
                                     }
                                 }
@@ -365,6 +407,9 @@ impl MappingPanel {
                                             P::EelFeedbackTransformation | P::TextualFeedbackExpression => {
                                                 view.invalidate_mode_eel_feedback_transformation_edit_control(initiator);
                                             }
+                                            P::TransformationLanguage => {
+                                                view.invalidate_mode_control_transformation_label();
+                                            }
                                             P::StepInterval => {
                                                 view.invalidate_mode_step_controls(initiator);
                                             }
@@ -602,6 +647,12 @@ impl MappingPanel {
 
     fn handle_source_line_4_button_press(&self) -> Result<(), &'static str> {
         let mapping = self.displayed_mapping().ok_or("no mapping set")?;
+        if mapping.borrow().source_model.category() == SourceCategory::Sequence {
+            self.change_mapping(MappingCommand::ChangeSource(
+                SourceCommand::SetSequenceSteps(vec![]),
+            ));
+            return Ok(());
+        }
         let control_element_type = mapping.borrow().source_model.control_element_type();
         let window = self.view.require_window();
         let controller_mappings: Vec<_> = {
@@ -783,14 +834,26 @@ impl MappingPanel {
         apply: impl Fn(&mut MappingModel, String) + 'static,
     ) {
         let mapping = self.mapping();
-        let engine: Box<dyn ScriptEngine> = match mapping.borrow().source_model.midi_script_kind() {
-            MidiScriptKind::Eel => Box::new(EelMidiScriptEngine::new()),
-            MidiScriptKind::Lua => Box::new(LuaMidiScriptEngine::new()),
+        let (engine, help_url): (Box<dyn ScriptEngine>, _) = {
+            let source_model = &mapping.borrow().source_model;
+            if source_model.midi_source_type() == MidiSourceType::ControlScript {
+                (
+                    Box::new(LuaMidiControlScriptEngine::new()),
+                    "https://github.com/helgoboss/realearn/blob/master/doc/user-guide.adoc#control-script-source",
+                )
+            } else {
+                let engine: Box<dyn ScriptEngine> = match source_model.midi_script_kind() {
+                    MidiScriptKind::Eel => Box::new(EelMidiScriptEngine::new()),
+                    MidiScriptKind::Lua => Box::new(LuaMidiScriptEngine::new()),
+                };
+                (
+                    engine,
+                    "https://github.com/helgoboss/realearn/blob/master/doc/user-guide.adoc#script-source",
+                )
+            }
         };
         let weak_mapping = Rc::downgrade(&mapping);
         let initial_value = { get_initial_value(&mapping.borrow()) };
-        let help_url =
-            "https://github.com/helgoboss/realearn/blob/master/doc/user-guide.adoc#script-source";
         let editor =
             ScriptEditorPanel::new(initial_value, engine, help_url, move |edited_script| {
                 let m = match weak_mapping.upgrade() {
@@ -1276,7 +1339,14 @@ impl<'a> MutableMappingPanel<'a> {
                     SourceCommand::SetOscArgIsRelative(checked),
                 ));
             }
-            Reaper | Virtual | Never | Keyboard => {}
+            Reaper => {
+                if self.mapping.source_model.reaper_source_type() == ReaperSourceType::Beat {
+                    self.change_mapping(MappingCommand::ChangeSource(
+                        SourceCommand::SetBeatOnlyWhilePlaying(checked),
+                    ));
+                }
+            }
+            Virtual | Never | Keyboard | Chord | Sequence => {}
         };
     }
 
@@ -1304,6 +1374,23 @@ impl<'a> MutableMappingPanel<'a> {
                 }
                 _ => {}
             },
+            Reaper => match self.mapping.source_model.reaper_source_type() {
+                ReaperSourceType::Beat => {
+                    let i = b.selected_combo_box_item_index();
+                    let grid = i.try_into().expect("invalid beat grid");
+                    self.change_mapping(MappingCommand::ChangeSource(SourceCommand::SetBeatGrid(
+                        grid,
+                    )));
+                }
+                ReaperSourceType::CompartmentParameterValue => {
+                    let i = b.selected_combo_box_item_index();
+                    let index = (i as u32).try_into().expect("invalid parameter index");
+                    self.change_mapping(MappingCommand::ChangeSource(
+                        SourceCommand::SetParameterIndex(index),
+                    ));
+                }
+                _ => {}
+            },
             _ => {}
         };
     }
@@ -1468,10 +1555,35 @@ impl<'a> MutableMappingPanel<'a> {
                 }
                 _ => {}
             },
+            Reaper => match self.mapping.source_model.reaper_source_type() {
+                ReaperSourceType::Lfo => {
+                    let i = b.selected_combo_box_item_index();
+                    let waveform = i.try_into().expect("invalid LFO waveform");
+                    self.change_mapping(MappingCommand::ChangeSource(
+                        SourceCommand::SetLfoWaveform(waveform),
+                    ));
+                }
+                ReaperSourceType::Beat => {
+                    let i = b.selected_combo_box_item_index();
+                    let output = i.try_into().expect("invalid beat source output");
+                    self.change_mapping(MappingCommand::ChangeSource(
+                        SourceCommand::SetBeatOutput(output),
+                    ));
+                }
+                _ => {}
+            },
+            Keyboard => {
+                let i = b.selected_combo_box_item_index();
+                let mode = i.try_into().expect("invalid key source mode");
+                self.change_mapping(MappingCommand::ChangeSource(SourceCommand::SetKeyMode(
+                    mode,
+                )));
+            }
             _ => {}
         }
     }
 
+    #[allow(clippy::single_match)]
     fn handle_source_line_4_edit_control_change(&mut self) {
         let edit_control_id = root::ID_SOURCE_NUMBER_EDIT_CONTROL;
         let c = self.view.require_control(edit_control_id);
@@ -1494,7 +1606,41 @@ impl<'a> MutableMappingPanel<'a> {
                     Some(edit_control_id),
                 );
             }
-            Reaper | Never | Keyboard | Osc => {}
+            Reaper => match self.mapping.source_model.reaper_source_type() {
+                ReaperSourceType::Lfo => {
+                    let value = parse_unit_value_from_percentage(&text).unwrap_or_default();
+                    self.change_mapping_with_initiator(
+                        MappingCommand::ChangeSource(SourceCommand::SetLfoPhase(value)),
+                        Some(edit_control_id),
+                    );
+                }
+                ReaperSourceType::Beat => {
+                    if let Ok(value) = text.parse::<u32>() {
+                        self.change_mapping_with_initiator(
+                            MappingCommand::ChangeSource(SourceCommand::SetBeatSubdivisions(
+                                value.max(1),
+                            )),
+                            Some(edit_control_id),
+                        );
+                    }
+                }
+                _ => {}
+            },
+            Chord => {
+                let value = text.parse().ok();
+                self.change_mapping_with_initiator(
+                    MappingCommand::ChangeSource(SourceCommand::SetChordWindowMillis(value)),
+                    Some(edit_control_id),
+                );
+            }
+            Sequence => {
+                let value = text.parse().ok();
+                self.change_mapping_with_initiator(
+                    MappingCommand::ChangeSource(SourceCommand::SetSequenceTimeoutMillis(value)),
+                    Some(edit_control_id),
+                );
+            }
+            Never | Keyboard | Osc => {}
         };
     }
 
@@ -1512,6 +1658,13 @@ impl<'a> MutableMappingPanel<'a> {
                     Some(edit_control_id),
                 );
             }
+            Reaper if self.mapping.source_model.reaper_source_type() == ReaperSourceType::Lfo => {
+                let v = parse_unit_value_from_percentage(&text).unwrap_or(UnitValue::MAX);
+                self.change_mapping_with_initiator(
+                    MappingCommand::ChangeSource(SourceCommand::SetLfoDepth(v)),
+                    Some(edit_control_id),
+                );
+            }
             _ => {}
         };
     }
@@ -1546,9 +1699,18 @@ impl<'a> MutableMappingPanel<'a> {
                             Some(edit_control_id),
                         )
                     }
+                    ReaperSourceType::Lfo => {
+                        // Ignore incomplete input such as "1/" while typing
+                        if let Ok(rate) = value.parse() {
+                            self.change_mapping_with_initiator(
+                                MappingCommand::ChangeSource(SourceCommand::SetLfoRate(rate)),
+                                Some(edit_control_id),
+                            )
+                        }
+                    }
                     _ => {}
                 },
-                Virtual | Never | Keyboard => {}
+                Virtual | Never | Keyboard | Chord | Sequence => {}
             }
         }
     }
@@ -1561,7 +1723,7 @@ impl<'a> MutableMappingPanel<'a> {
         use SourceCategory::*;
         match self.mapping.source_model.category() {
             Midi => match self.mapping.source_model.midi_source_type() {
-                MidiSourceType::Script => {
+                MidiSourceType::Script | MidiSourceType::ControlScript => {
                     self.change_mapping_with_initiator(
                         MappingCommand::ChangeSource(SourceCommand::SetMidiScript(value)),
                         Some(edit_control_id),
@@ -3180,9 +3342,14 @@ impl<'a> ImmutableMappingPanel<'a> {
             Osc => Some("Address"),
             Reaper => match self.source.reaper_source_type() {
                 ReaperSourceType::Timer => Some("Millis"),
+                ReaperSourceType::Lfo => Some("Rate"),
+                ReaperSourceType::Beat => Some("Grid"),
+                ReaperSourceType::CompartmentParameterValue => Some("Parameter"),
                 _ => None,
             },
             Keyboard => Some("Keystroke"),
+            Chord => Some("Members"),
+            Sequence => Some("Steps"),
             _ => None,
         };
         self.view
@@ -3199,6 +3366,12 @@ impl<'a> ImmutableMappingPanel<'a> {
                 MidiSourceType::Display => Some("Protocol"),
                 _ => None,
             },
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Lfo => Some("Waveform"),
+                ReaperSourceType::Beat => Some("Emit"),
+                _ => None,
+            },
+            Keyboard => Some("Mode"),
             _ => None,
         };
         self.view
@@ -3234,6 +3407,29 @@ impl<'a> ImmutableMappingPanel<'a> {
                 }
                 _ => b.hide(),
             },
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Beat => {
+                    b.show();
+                    b.fill_combo_box_indexed(BeatGrid::into_enum_iter());
+                    b.select_combo_box_item_by_index(self.source.beat_grid().into())
+                        .unwrap();
+                }
+                ReaperSourceType::CompartmentParameterValue => {
+                    b.show();
+                    let params = self
+                        .session
+                        .params()
+                        .compartment_params(self.mapping.compartment());
+                    b.fill_combo_box_indexed_vec(
+                        compartment_param_index_iter()
+                            .map(|i| format!("{}. {}", i.get() + 1, params.get_parameter_name(i)))
+                            .collect(),
+                    );
+                    b.select_combo_box_item_by_index(self.source.parameter_index().get() as _)
+                        .unwrap();
+                }
+                _ => b.hide(),
+            },
             _ => {
                 b.hide();
             }
@@ -3258,6 +3454,12 @@ impl<'a> ImmutableMappingPanel<'a> {
                 }
             }
             Osc => Some(("Is relative", self.source.osc_arg_is_relative())),
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Beat => {
+                    Some(("Only while playing", self.source.beat_only_while_playing()))
+                }
+                _ => None,
+            },
             _ => None,
         };
         self.invalidate_check_box(root::ID_SOURCE_14_BIT_CHECK_BOX, state);
@@ -3296,6 +3498,7 @@ impl<'a> ImmutableMappingPanel<'a> {
         use SourceCategory::*;
         let text = match self.source.category() {
             Virtual => Some("Pick"),
+            Sequence => Some("Clear"),
             _ => None,
         };
         self.view
@@ -3327,6 +3530,15 @@ impl<'a> ImmutableMappingPanel<'a> {
             }
             Virtual => Some("ID"),
             Osc => Some("Argument"),
+            Chord => Some("Window (ms)"),
+            Sequence => Some("Timeout (ms)"),
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Lfo => Some("Phase"),
+                ReaperSourceType::Beat if self.source.beat_grid() == BeatGrid::Subdivision => {
+                    Some("Steps/beat")
+                }
+                _ => None,
+            },
             _ => None,
         };
         self.view
@@ -3436,6 +3648,27 @@ impl<'a> ImmutableMappingPanel<'a> {
                 _ => None,
             },
             Virtual => Some(self.source.control_element_id().to_string()),
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Lfo => Some(format_percentage_without_unit(
+                    self.source.lfo_phase().get(),
+                )),
+                ReaperSourceType::Beat if self.source.beat_grid() == BeatGrid::Subdivision => {
+                    Some(self.source.beat_subdivisions().to_string())
+                }
+                _ => None,
+            },
+            Chord => Some(
+                self.source
+                    .chord_window_millis()
+                    .map(|m| m.to_string())
+                    .unwrap_or_default(),
+            ),
+            Sequence => Some(
+                self.source
+                    .sequence_timeout_millis()
+                    .map(|m| m.to_string())
+                    .unwrap_or_default(),
+            ),
             _ => None,
         };
         self.view
@@ -3464,7 +3697,7 @@ impl<'a> ImmutableMappingPanel<'a> {
         use SourceCategory::*;
         let text = match self.source.category() {
             Midi => match self.source.midi_source_type() {
-                MidiSourceType::Script => Some("Script"),
+                MidiSourceType::Script | MidiSourceType::ControlScript => Some("Script"),
                 _ => None,
             },
             Osc => Some("Feedback arguments"),
@@ -3490,6 +3723,7 @@ impl<'a> ImmutableMappingPanel<'a> {
             Osc => Some((self.source.osc_address_pattern().to_owned(), true)),
             Reaper => match self.source.reaper_source_type() {
                 ReaperSourceType::Timer => Some((self.source.timer_millis().to_string(), true)),
+                ReaperSourceType::Lfo => Some((self.source.lfo_rate().to_string(), true)),
                 _ => None,
             },
             Keyboard => {
@@ -3516,6 +3750,30 @@ impl<'a> ImmutableMappingPanel<'a> {
                     .unwrap_or_else(|| KEY_UNDEFINED_LABEL.to_string());
                 Some((text, false))
             }
+            Chord => {
+                let members = self.source.chord_members();
+                let text = if members.is_empty() {
+                    "<Press Learn to add members>".to_string()
+                } else {
+                    itertools::join(
+                        members.iter().map(|m| m.to_string().replace('\n', " ")),
+                        " + ",
+                    )
+                };
+                Some((text, false))
+            }
+            Sequence => {
+                let steps = self.source.sequence_steps();
+                let text = if steps.is_empty() {
+                    "<Press Learn to add steps>".to_string()
+                } else {
+                    itertools::join(
+                        steps.iter().map(|m| m.to_string().replace('\n', " ")),
+                        ", then ",
+                    )
+                };
+                Some((text, false))
+            }
             _ => None,
         };
         if let Some((value_text, enabled)) = content {
@@ -3536,7 +3794,7 @@ impl<'a> ImmutableMappingPanel<'a> {
         use SourceCategory::*;
         let (value_text, read_only) = match self.source.category() {
             Midi => match self.source.midi_source_type() {
-                MidiSourceType::Script => {
+                MidiSourceType::Script | MidiSourceType::ControlScript => {
                     let midi_script = self.source.midi_script();
                     (
                         Some(midi_script.lines().next().unwrap_or_default().to_owned()),
@@ -3579,6 +3837,10 @@ impl<'a> ImmutableMappingPanel<'a> {
                 }
             }
             Osc if self.source.supports_osc_arg_value_range() => Some("Range"),
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Lfo => Some("Depth"),
+                _ => None,
+            },
             _ => None,
         };
         self.view
@@ -3646,6 +3908,12 @@ impl<'a> ImmutableMappingPanel<'a> {
                 );
                 Some(text)
             }
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Lfo => Some(format_percentage_without_unit(
+                    self.source.lfo_depth().get(),
+                )),
+                _ => None,
+            },
             _ => None,
         };
         self.view
@@ -3678,6 +3946,29 @@ impl<'a> ImmutableMappingPanel<'a> {
                     b.hide();
                 }
             },
+            Reaper => match self.source.reaper_source_type() {
+                ReaperSourceType::Lfo => {
+                    b.show();
+                    b.fill_combo_box_indexed(LfoWaveform::into_enum_iter());
+                    b.select_combo_box_item_by_index(self.source.lfo_waveform().into())
+                        .unwrap();
+                }
+                ReaperSourceType::Beat => {
+                    b.show();
+                    b.fill_combo_box_indexed(BeatSourceOutput::into_enum_iter());
+                    b.select_combo_box_item_by_index(self.source.beat_output().into())
+                        .unwrap();
+                }
+                _ => {
+                    b.hide();
+                }
+            },
+            Keyboard => {
+                b.show();
+                b.fill_combo_box_indexed(KeySourceMode::into_enum_iter());
+                b.select_combo_box_item_by_index(self.source.key_mode().into())
+                    .unwrap();
+            }
             _ => {
                 b.hide();
             }
@@ -4984,6 +5275,7 @@ impl<'a> ImmutableMappingPanel<'a> {
         self.invalidate_mode_encoder_usage_combo_box();
         self.invalidate_mode_reverse_check_box();
         self.invalidate_mode_target_value_sequence_edit_control(None);
+        self.invalidate_mode_control_transformation_label();
         self.invalidate_mode_eel_control_transformation_edit_control(None);
         self.invalidate_mode_eel_feedback_transformation_edit_control(None);
     }
@@ -5652,6 +5944,19 @@ impl<'a> ImmutableMappingPanel<'a> {
             .set_text(formatted);
     }
 
+    fn invalidate_mode_control_transformation_label(&self) {
+        let text = match self.mode.transformation_language() {
+            TransformationLanguage::Eel => "Control transformation (EEL)",
+            TransformationLanguage::Lua if self.mapping.control_is_always_real_time() => {
+                "Control transformation (Lua, unsupported for virtual targets)"
+            }
+            TransformationLanguage::Lua => "Control transformation (Lua)",
+        };
+        self.view
+            .require_control(root::ID_MODE_EEL_CONTROL_TRANSFORMATION_LABEL)
+            .set_text(text);
+    }
+
     fn invalidate_mode_eel_control_transformation_edit_control(&self, initiator: Option<u32>) {
         if initiator == Some(root::ID_MODE_EEL_CONTROL_TRANSFORMATION_EDIT_CONTROL) {
             return;
@@ -5708,7 +6013,7 @@ impl<'a> ImmutableMappingPanel<'a> {
             Midi => b.fill_combo_box_indexed(MidiSourceType::into_enum_iter()),
             Reaper => b.fill_combo_box_indexed(ReaperSourceType::into_enum_iter()),
             Virtual => b.fill_combo_box_indexed(VirtualControlElementType::into_enum_iter()),
-            Osc | Never | Keyboard => {}
+            Osc | Never | Keyboard | Chord | Sequence => {}
         };
     }
 
@@ -1,5 +1,5 @@
 use crate::base::eel::Vm;
-use crate::domain::{EelMidiSourceScript, LuaMidiSourceScript, SafeLua};
+use crate::domain::{EelMidiSourceScript, LuaMidiControlScript, LuaMidiSourceScript, SafeLua};
 use crate::infrastructure::ui::bindings::root;
 use crate::infrastructure::ui::util::{open_in_browser, open_in_text_editor};
 use derivative::Derivative;
@@ -30,6 +30,18 @@ impl LuaMidiScriptEngine {
     }
 }
 
+pub struct LuaMidiControlScriptEngine {
+    lua: SafeLua,
+}
+
+impl LuaMidiControlScriptEngine {
+    pub fn new() -> Self {
+        Self {
+            lua: SafeLua::new().unwrap(),
+        }
+    }
+}
+
 pub struct EelMidiScriptEngine(());
 
 impl EelMidiScriptEngine {
@@ -80,6 +92,19 @@ impl ScriptEngine for LuaMidiScriptEngine {
     }
 }
 
+impl ScriptEngine for LuaMidiControlScriptEngine {
+    fn compile(&self, code: &str) -> Result<(), Box<dyn Error>> {
+        let script = LuaMidiControlScript::compile(&self.lua, code)?;
+        // Note on, channel 1, note number 60, velocity 100
+        script.execute(&[0x90, 60, 100])?;
+        Ok(())
+    }
+
+    fn file_extension(&self) -> &'static str {
+        ".lua"
+    }
+}
+
 impl ScriptEngine for EelMidiScriptEngine {
     fn compile(&self, code: &str) -> Result<(), Box<dyn Error>> {
         let script = EelMidiSourceScript::compile(code)?;